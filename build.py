@@ -27,9 +27,14 @@ def build_rust_extension():
     
     # Build the extension
     print(f"Building Rust extension using Python: {python_path}")
+    # Extra cargo features, e.g. RUST_EXPR_FEATURES=polars
+    command = ["cargo", "build", "--release"]
+    features = os.environ.get("RUST_EXPR_FEATURES")
+    if features:
+        command += ["--features", features]
     try:
         subprocess.run(
-            ["cargo", "build", "--release"],
+            command,
             cwd=rust_dir,
             check=True,
             env=os.environ
@@ -14,5 +14,8 @@ setup(
         "scipy>=1.7.0",
         "statsmodels>=0.13.0",
     ],
+    extras_require={
+        "polars": ["polars>=0.19"],
+    },
     python_requires=">=3.8",
 ) 
\ No newline at end of file
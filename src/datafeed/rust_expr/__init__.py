@@ -8,11 +8,353 @@ import sys
 # Add the current directory to PATH for DLL loading
 os.environ["PATH"] = os.path.dirname(os.path.abspath(__file__)) + os.pathsep + os.environ.get("PATH", "")
 
-from .rust_expr import momentum_factor, mean_reversion_factor, relative_strength_factor, alpha101_factor_42
+from .rust_expr import (
+    momentum_factor,
+    mean_reversion_factor,
+    relative_strength_factor,
+    alpha101_factor_42,
+    hybrid_zscore,
+    pair_zscore,
+    ew_zscore,
+    rolling_ess,
+    RollingRank,
+    rolling_lead_lag,
+    rolling_volume_delta,
+    rolling_up_fraction,
+    rolling_mean_chunks,
+    StreamingCorrMatrix,
+    rolling_beta,
+    rolling_jarque_bera,
+    round_window,
+    drop_warmup,
+    cs_dispersion,
+    regime_rolling_mean,
+    discounted_sum,
+    rolling_mean_partial,
+    rolling_std_partial,
+    rolling_logret_corr,
+    rolling_drawdown_duration,
+    nan_report,
+    rolling_robust_clean,
+    window_at,
+    rolling_predictive_r2,
+    rolling_coint_stat,
+    adf_test,
+    set_precision_mode,
+    get_precision_mode,
+    rolling_risk_adjusted,
+    ewm_correlation,
+    momentum_and_reversion,
+    rolling_zero_crossings,
+    percentile_breakout,
+    rolling_pc1_share,
+    rolling_mean_abs_dev,
+    rolling_iqr,
+    rolling_nunique,
+    warmup_fill,
+    rolling_corr_pvalue,
+    StreamingQuantile,
+    factor_decay_flag,
+    rolling_beta_stability,
+    rolling_pairwise_corr,
+    fractional_window_mean,
+    rolling_rank,
+    ts_mean,
+    ts_std,
+    ts_rank,
+    ts_corr,
+    cost_aware_smooth,
+    rolling_cov,
+    rolling_max_with_pos,
+    rolling_min,
+    rolling_max,
+    pct_change,
+    rolling_quantile,
+    rolling_median,
+    weighted_cs_rank_ic,
+    regime_label,
+    rolling_skew,
+    rolling_kurt,
+    rolling_residualize,
+    ema,
+    set_zero_tolerance,
+    get_zero_tolerance,
+    decay_linear,
+    rolling_vol_of_vol,
+    groupby_rolling,
+    rolling_kelly,
+    ts_mean_2d,
+    ts_std_2d,
+    ts_rank_2d,
+    momentum_factor_2d,
+    mean_reversion_factor_2d,
+    rolling_current_drawdown,
+    cs_rank,
+    ts_delay,
+    ts_delta,
+    rolling_robust_corr,
+    ts_argmax,
+    ts_argmin,
+    rolling_partial_corr,
+    scale,
+    rolling_snr,
+    signed_power,
+    eval_expr,
+    winsorize,
+    winsorize_mad,
+    ExprError,
+    InvalidPeriod,
+    ComputationError,
+    InvalidParameter,
+    LengthMismatch,
+    ParseError,
+    neutralize,
+    cumsum,
+    cumprod,
+    cummax,
+    cummin,
+    max_drawdown,
+    rolling_sharpe,
+    rolling_downside_dev,
+    rolling_sortino,
+    ewm_var,
+    ewm_std,
+    compute_alphas,
+    rolling_spearman,
+    rolling_apply,
+    grouped_rolling_mean,
+    grouped_rolling_std,
+    grouped_rolling_rank,
+    rolling_mean_time,
+    clip,
+    sigmoid,
+    tanh_squash,
+    log_return,
+    momentum_factor_into,
+    mean_reversion_factor_into,
+    RollingMean,
+    RollingStd,
+    rolling_sum,
+    rolling_var,
+    alpha101_ts_rank,
+    rolling_zscore,
+    rolling_prod,
+    rolling_geomean,
+    rolling_minmax,
+    rolling_range,
+    stochastic,
+    sign,
+    sign_change,
+    rolling_autocorr,
+    rolling_slope,
+    rolling_r2,
+    rank_ic,
+    forward_fill,
+    backward_fill,
+    rolling_entropy,
+    cs_demean,
+    cs_normalize,
+    alpha101_factor_42_2d,
+    panel_beta,
+    rolling_vwap,
+    where_,
+    gt,
+    lt,
+    ge,
+    le,
+    rolling_mad,
+    rolling_mean_with_count,
+    bollinger_percent_b,
+    bollinger_bandwidth,
+    shuffle_rows,
+    dema,
+    tema,
+    cs_bucket,
+    expanding_rank,
+    expanding_mean,
+    expanding_std,
+    Factor,
+    hampel_filter,
+    rolling_count,
+    rolling_nan_ratio,
+    weighted_rolling_mean,
+)
+
+# Alpha101 spelling
+signedpower = signed_power
+# Name for the trailing-window drawdown alongside `max_drawdown`
+rolling_drawdown = rolling_current_drawdown
 
 __all__ = [
     'momentum_factor',
     'mean_reversion_factor',
     'relative_strength_factor',
-    'alpha101_factor_42'
+    'alpha101_factor_42',
+    'hybrid_zscore',
+    'pair_zscore',
+    'ew_zscore',
+    'rolling_ess',
+    'RollingRank',
+    'rolling_lead_lag',
+    'rolling_volume_delta',
+    'rolling_up_fraction',
+    'rolling_mean_chunks',
+    'StreamingCorrMatrix',
+    'rolling_beta',
+    'rolling_jarque_bera',
+    'round_window',
+    'drop_warmup',
+    'cs_dispersion',
+    'regime_rolling_mean',
+    'discounted_sum',
+    'rolling_mean_partial',
+    'rolling_std_partial',
+    'rolling_logret_corr',
+    'rolling_drawdown_duration',
+    'nan_report',
+    'rolling_robust_clean',
+    'window_at',
+    'rolling_predictive_r2',
+    'rolling_coint_stat',
+    'adf_test',
+    'set_precision_mode',
+    'get_precision_mode',
+    'rolling_risk_adjusted',
+    'ewm_correlation',
+    'momentum_and_reversion',
+    'rolling_zero_crossings',
+    'percentile_breakout',
+    'rolling_pc1_share',
+    'rolling_mean_abs_dev',
+    'rolling_iqr',
+    'rolling_nunique',
+    'warmup_fill',
+    'rolling_corr_pvalue',
+    'StreamingQuantile',
+    'factor_decay_flag',
+    'rolling_beta_stability',
+    'rolling_pairwise_corr',
+    'fractional_window_mean',
+    'rolling_rank',
+    'ts_mean',
+    'ts_std',
+    'ts_rank',
+    'ts_corr',
+    'cost_aware_smooth',
+    'rolling_cov',
+    'rolling_max_with_pos',
+    'rolling_min',
+    'rolling_max',
+    'pct_change',
+    'rolling_quantile',
+    'rolling_median',
+    'weighted_cs_rank_ic',
+    'regime_label',
+    'rolling_skew',
+    'rolling_kurt',
+    'rolling_residualize',
+    'ema',
+    'set_zero_tolerance',
+    'get_zero_tolerance',
+    'decay_linear',
+    'rolling_vol_of_vol',
+    'groupby_rolling',
+    'rolling_kelly',
+    'ts_mean_2d',
+    'ts_std_2d',
+    'ts_rank_2d',
+    'momentum_factor_2d',
+    'mean_reversion_factor_2d',
+    'rolling_current_drawdown',
+    'rolling_drawdown',
+    'cs_rank',
+    'ts_delay',
+    'ts_delta',
+    'rolling_robust_corr',
+    'ts_argmax',
+    'ts_argmin',
+    'rolling_partial_corr',
+    'scale',
+    'rolling_snr',
+    'signed_power',
+    'signedpower',
+    'eval_expr',
+    'winsorize',
+    'winsorize_mad',
+    'ExprError',
+    'InvalidPeriod',
+    'ComputationError',
+    'InvalidParameter',
+    'LengthMismatch',
+    'ParseError',
+    'neutralize',
+    'cumsum',
+    'cumprod',
+    'cummax',
+    'cummin',
+    'max_drawdown',
+    'rolling_sharpe',
+    'rolling_downside_dev',
+    'rolling_sortino',
+    'ewm_var',
+    'ewm_std',
+    'compute_alphas',
+    'rolling_spearman',
+    'rolling_apply',
+    'grouped_rolling_mean',
+    'grouped_rolling_std',
+    'grouped_rolling_rank',
+    'rolling_mean_time',
+    'clip',
+    'sigmoid',
+    'tanh_squash',
+    'log_return',
+    'momentum_factor_into',
+    'mean_reversion_factor_into',
+    'RollingMean',
+    'RollingStd',
+    'rolling_sum',
+    'rolling_var',
+    'alpha101_ts_rank',
+    'rolling_zscore',
+    'rolling_prod',
+    'rolling_geomean',
+    'rolling_minmax',
+    'rolling_range',
+    'stochastic',
+    'sign',
+    'sign_change',
+    'rolling_autocorr',
+    'rolling_slope',
+    'rolling_r2',
+    'rank_ic',
+    'forward_fill',
+    'backward_fill',
+    'rolling_entropy',
+    'cs_demean',
+    'cs_normalize',
+    'alpha101_factor_42_2d',
+    'panel_beta',
+    'rolling_vwap',
+    'where_',
+    'gt',
+    'lt',
+    'ge',
+    'le',
+    'rolling_mad',
+    'rolling_mean_with_count',
+    'bollinger_percent_b',
+    'bollinger_bandwidth',
+    'shuffle_rows',
+    'dema',
+    'tema',
+    'cs_bucket',
+    'expanding_rank',
+    'expanding_mean',
+    'expanding_std',
+    'Factor',
+    'hampel_filter',
+    'rolling_count',
+    'rolling_nan_ratio',
+    'weighted_rolling_mean'
 ] 
\ No newline at end of file
@@ -0,0 +1,54 @@
+"""Polars ``Series`` entry points for the main rolling functions.
+
+These are the ``rust_expr.polars`` functions compiled in with the ``polars`` cargo
+feature; build with ``RUST_EXPR_FEATURES=polars python build.py`` and install polars
+(``pip install kkexpr[polars]``). Each takes Float64 ``pl.Series`` in place of the numpy
+arrays of its namesake and returns a Float64 ``pl.Series`` carrying the first input's name.
+
+A single-chunk series without nulls is read straight from its Arrow buffer. Chunked
+series are gathered once, with nulls becoming NaN so they are skipped like any other
+missing value. Series of any other dtype raise ``TypeError``; cast them explicitly.
+"""
+
+from . import rust_expr as _rust
+
+try:
+    _polars = _rust.polars
+except AttributeError:
+    raise ImportError(
+        "rust_expr was built without the polars feature; rebuild with RUST_EXPR_FEATURES=polars"
+    ) from None
+
+ts_mean = _polars.ts_mean
+ts_std = _polars.ts_std
+ts_rank = _polars.ts_rank
+ts_corr = _polars.ts_corr
+rolling_sum = _polars.rolling_sum
+rolling_var = _polars.rolling_var
+rolling_zscore = _polars.rolling_zscore
+rolling_min = _polars.rolling_min
+rolling_max = _polars.rolling_max
+rolling_median = _polars.rolling_median
+rolling_quantile = _polars.rolling_quantile
+rolling_skew = _polars.rolling_skew
+ema = _polars.ema
+momentum_factor = _polars.momentum_factor
+mean_reversion_factor = _polars.mean_reversion_factor
+
+__all__ = [
+    'ts_mean',
+    'ts_std',
+    'ts_rank',
+    'ts_corr',
+    'rolling_sum',
+    'rolling_var',
+    'rolling_zscore',
+    'rolling_min',
+    'rolling_max',
+    'rolling_median',
+    'rolling_quantile',
+    'rolling_skew',
+    'ema',
+    'momentum_factor',
+    'mean_reversion_factor',
+]
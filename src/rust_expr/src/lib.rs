@@ -1,7 +1,15 @@
-use numpy::{IntoPyArray, PyArray1};
-use pyo3::prelude::*;
-use ndarray::{Array1, s};
-use std::collections::VecDeque;
+//! Rolling-window statistics and factor kernels over `ndarray` series
+//!
+//! Every kernel is a plain function on `&Array1<f64>` (or `&Array2<f64>` for panels)
+//! returning `Result<_, ExprError>`, usable without Python. The `python` feature (on by
+//! default) adds the `rust_expr` extension module; build with `default-features = false`
+//! to drop the pyo3/numpy dependency.
+
+use ndarray::{Array, Array1, Array2, Array3, ArrayView1, Axis, Dimension, s};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use statrs::distribution::{ContinuousCDF, StudentsT};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ExprError {
@@ -9,327 +17,8080 @@ pub enum ExprError {
     InvalidPeriod(String),
     #[error("Computation error: {0}")]
     ComputationError(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("Length mismatch: {0}")]
+    LengthMismatch(String),
+    #[error("Parse error: {0}")]
+    ParseError(String),
 }
 
-type Result<T> = std::result::Result<T, ExprError>;
+pub type Result<T> = std::result::Result<T, ExprError>;
 
-/// Calculate rolling mean
-fn rolling_mean(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
-    if window < 1 {
-        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+#[cfg(feature = "python")]
+mod python;
+
+/// Check that two paired inputs have the same length
+fn check_same_length(x_name: &str, x: &Array1<f64>, y_name: &str, y: &Array1<f64>) -> Result<()> {
+    if x.len() != y.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "{} has length {} but {} has length {}",
+            x_name, x.len(), y_name, y.len()
+        )));
     }
+    Ok(())
+}
 
-    let n = data.len();
-    let mut result = Array1::zeros(n);
-    let mut sum = 0.0;
-    let mut count = 0;
-    let mut queue = VecDeque::with_capacity(window);
+/// Rounding rule for deriving an integer window from a fractional specification
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
+enum RoundMode {
+    Floor,
+    Ceil,
+    Nearest,
+}
 
-    for (i, &val) in data.iter().enumerate() {
-        queue.push_back(val);
-        sum += val;
-        count += 1;
+impl std::str::FromStr for RoundMode {
+    type Err = ExprError;
 
-        if count > window {
-            sum -= queue.pop_front().unwrap();
-            count -= 1;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "floor" => Ok(RoundMode::Floor),
+            "ceil" => Ok(RoundMode::Ceil),
+            "nearest" => Ok(RoundMode::Nearest),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown rounding mode '{}', expected one of: floor, ceil, nearest", s
+            ))),
         }
+    }
+}
 
-        result[i] = if count < window {
-            f64::NAN
-        } else {
-            sum / count as f64
-        };
+/// Round a fractional window (e.g. `periods_per_year / 12`) to an integer of at least 1
+///
+/// "nearest" rounds halves away from zero.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
+fn round_window(value: f64, mode: RoundMode) -> Result<usize> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(ExprError::InvalidPeriod(format!("window {} must be finite and non-negative", value)));
     }
 
-    Ok(result)
+    let rounded = match mode {
+        RoundMode::Floor => value.floor(),
+        RoundMode::Ceil => value.ceil(),
+        RoundMode::Nearest => value.round(),
+    };
+
+    if rounded < 1.0 {
+        return Err(ExprError::InvalidPeriod(format!("window {} rounds to {}, expected at least 1", value, rounded)));
+    }
+    Ok(rounded as usize)
 }
 
-/// Calculate rolling standard deviation
-fn rolling_std(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
-    if window < 2 {
-        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+/// Accumulator used by the rolling std and correlation kernels
+///
+/// `Fast`, the default, slides Welford updates of the mean and centred moments across the
+/// series. `Accurate` recomputes every window about its own mean, trading O(window) work
+/// per step for freedom from the slow drift a sliding update can build over very long series.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrecisionMode {
+    /// Sliding Welford updates of the mean and centred moments, O(1) per step
+    Fast,
+    /// Moments recomputed about the window mean, O(window) per step
+    Accurate,
+}
+
+impl std::str::FromStr for PrecisionMode {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fast" => Ok(PrecisionMode::Fast),
+            "accurate" => Ok(PrecisionMode::Accurate),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown precision mode '{}', expected one of: fast, accurate", s
+            ))),
+        }
     }
+}
 
-    let n = data.len();
-    let mut result = Array1::zeros(n);
-    let mut queue = VecDeque::with_capacity(window);
-    let mut sum = 0.0;
-    let mut sum_sq = 0.0;
-    let mut count = 0;
+/// Module-wide default for kernels called without an explicit precision; `Fast` unless set
+static ACCURATE_PRECISION: AtomicBool = AtomicBool::new(false);
 
-    for (i, &val) in data.iter().enumerate() {
-        if val.is_nan() || val.is_infinite() {
-            result[i] = f64::NAN;
-            continue;
+fn precision_mode() -> PrecisionMode {
+    if ACCURATE_PRECISION.load(Ordering::Relaxed) {
+        PrecisionMode::Accurate
+    } else {
+        PrecisionMode::Fast
+    }
+}
+
+/// A per-call precision override, falling back to the module-wide mode
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
+fn resolve_precision(precision: Option<&str>) -> Result<PrecisionMode> {
+    precision.map_or_else(|| Ok(precision_mode()), str::parse)
+}
+
+/// Module-wide tolerances for treating a denominator as zero, stored as `f64` bits
+static ZERO_REL_TOL: AtomicU64 = AtomicU64::new(f64::EPSILON.to_bits());
+
+static ZERO_ABS_TOL: AtomicU64 = AtomicU64::new(f64::EPSILON.to_bits());
+
+/// Whether `denom` is zero at the scale of `scale`: `|denom| <= max(rel_tol * |scale|, abs_tol)`
+fn is_near_zero_with(denom: f64, scale: f64, rel_tol: f64, abs_tol: f64) -> bool {
+    let scale = if scale.is_finite() { scale.abs() } else { 0.0 };
+    denom.abs() <= (rel_tol * scale).max(abs_tol)
+}
+
+/// `is_near_zero_with` under the module-wide tolerances
+///
+/// Used for the pct_change, z-score and momentum denominators, where `scale` is the
+/// magnitude the denominator is compared against (the numerator or the window mean).
+fn is_near_zero(denom: f64, scale: f64) -> bool {
+    let rel_tol = f64::from_bits(ZERO_REL_TOL.load(Ordering::Relaxed));
+    let abs_tol = f64::from_bits(ZERO_ABS_TOL.load(Ordering::Relaxed));
+    is_near_zero_with(denom, scale, rel_tol, abs_tol)
+}
+
+/// Apply a 1D kernel independently down each column of a `(time, assets)` panel
+///
+/// Every column is copied out and computed on its own, so NaNs in one asset never reach
+/// another. The kernel must return one value per row.
+pub fn apply_columns<F>(data: &Array2<f64>, func: F) -> Result<Array2<f64>>
+where
+    F: Fn(&Array1<f64>) -> Result<Array1<f64>>,
+{
+    let mut result = Array2::zeros(data.dim());
+    for (j, column) in data.columns().into_iter().enumerate() {
+        let values = func(&column.to_owned())?;
+        if values.len() != column.len() {
+            return Err(ExprError::ComputationError(format!(
+                "column {} produced {} values for {} rows", j, values.len(), column.len()
+            )));
         }
+        result.column_mut(j).assign(&values);
+    }
+    Ok(result)
+}
 
-        queue.push_back(val);
-        sum += val;
-        sum_sq += val * val;
-        count += 1;
+/// `apply_columns` with the columns split across rayon threads
+///
+/// Each column is still computed by `func` on its own copy in the same order of
+/// operations, so the output is bit-identical to `apply_columns`; only the assembly of the
+/// finished columns is shared. Without the `parallel` feature this is `apply_columns`.
+#[cfg(feature = "parallel")]
+pub fn apply_columns_par<F>(data: &Array2<f64>, func: F) -> Result<Array2<f64>>
+where
+    F: Fn(&Array1<f64>) -> Result<Array1<f64>> + Sync,
+{
+    use rayon::prelude::*;
 
-        if count > window {
-            let old = queue.pop_front().unwrap();
-            sum -= old;
-            sum_sq -= old * old;
-            count -= 1;
+    let columns: Vec<Array1<f64>> = data.columns().into_iter().map(|c| c.to_owned()).collect();
+    let computed = columns.par_iter().map(&func).collect::<Result<Vec<_>>>()?;
+
+    let mut result = Array2::zeros(data.dim());
+    for (j, values) in computed.into_iter().enumerate() {
+        if values.len() != data.nrows() {
+            return Err(ExprError::ComputationError(format!(
+                "column {} produced {} values for {} rows", j, values.len(), data.nrows()
+            )));
         }
+        result.column_mut(j).assign(&values);
+    }
+    Ok(result)
+}
 
-        result[i] = if count < window {
-            f64::NAN
-        } else {
-            let mean = sum / count as f64;
-            let variance = (sum_sq / count as f64) - (mean * mean);
-            if variance <= 0.0 {
-                f64::NAN
-            } else {
-                variance.sqrt()
-            }
-        };
+#[cfg(not(feature = "parallel"))]
+pub fn apply_columns_par<F>(data: &Array2<f64>, func: F) -> Result<Array2<f64>>
+where
+    F: Fn(&Array1<f64>) -> Result<Array1<f64>> + Sync,
+{
+    apply_columns(data, func)
+}
+
+/// Apply `func` to every item, on rayon threads with the `parallel` feature and in order
+/// without it, stopping at the first error
+///
+/// Results keep the order of `items` either way.
+fn map_par<T, R, F>(items: &[T], func: F) -> Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(&func).collect()
     }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(func).collect()
+    }
+}
+
+/// `apply_columns_par` for a two-input kernel over matching columns of two panels
+///
+/// The panels must have the same shape. Column `j` of the result is `func(x[:, j], y[:, j])`,
+/// bit-identical to calling the 1D kernel per asset.
+pub fn apply_column_pairs_par<F>(x: &Array2<f64>, y: &Array2<f64>, func: F) -> Result<Array2<f64>>
+where
+    F: Fn(&Array1<f64>, &Array1<f64>) -> Result<Array1<f64>> + Sync,
+{
+    if x.dim() != y.dim() {
+        return Err(ExprError::LengthMismatch(format!(
+            "panels have shapes {:?} and {:?}", x.dim(), y.dim()
+        )));
+    }
+    let pairs: Vec<(Array1<f64>, Array1<f64>)> = x.columns().into_iter().zip(y.columns())
+        .map(|(a, b)| (a.to_owned(), b.to_owned()))
+        .collect();
+    let computed = map_par(&pairs, |(a, b)| func(a, b))?;
 
+    let mut result = Array2::zeros(x.dim());
+    for (j, values) in computed.into_iter().enumerate() {
+        if values.len() != x.nrows() {
+            return Err(ExprError::ComputationError(format!(
+                "column {} produced {} values for {} rows", j, values.len(), x.nrows()
+            )));
+        }
+        result.column_mut(j).assign(&values);
+    }
     Ok(result)
 }
 
-/// Calculate percentage change
-fn pct_change(data: &Array1<f64>, periods: usize) -> Result<Array1<f64>> {
-    if periods < 1 {
-        return Err(ExprError::InvalidPeriod("Period must be positive".into()));
+/// Welford mean and sum of squared deviations over a sliding window
+///
+/// Values enter with `add` and leave with `remove` (West's downdate), so the centred
+/// moment never passes through `sum_sq - n·mean²` and large offsets do not cancel.
+#[derive(Clone, Copy, Default)]
+struct SlidingVariance {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl SlidingVariance {
+    fn add(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
     }
 
-    let n = data.len();
-    let mut result = Array1::zeros(n);
+    fn remove(&mut self, x: f64) {
+        self.count -= 1;
+        if self.count == 0 {
+            *self = Self::default();
+            return;
+        }
+        let delta = x - self.mean;
+        self.mean -= delta / self.count as f64;
+        self.m2 -= delta * (x - self.mean);
+    }
 
-    for i in 0..n {
-        result[i] = if i < periods {
-            f64::NAN
-        } else {
-            let prev = data[i - periods];
-            if prev.is_nan() || prev.is_infinite() || prev == 0.0 {
-                f64::NAN
-            } else {
-                let curr = data[i];
-                if curr.is_nan() || curr.is_infinite() {
-                    f64::NAN
-                } else {
-                    (curr - prev) / prev
-                }
-            }
-        };
+    /// `m2 / count`; may be zero or slightly negative for (near-)constant windows
+    fn population(&self) -> f64 {
+        self.m2 / self.count as f64
     }
+}
 
-    Ok(result)
+/// Paired form of `SlidingVariance` that also tracks the co-moment of `x` and `y`
+///
+/// Like `StdAccumulator`, each side counts its trailing run of identical values; when a run
+/// covers the whole window that side's mean is reset to the value and its moment and the
+/// co-moment to exactly zero, so downdate residue cannot pass the `<= 0.0` guards.
+#[derive(Clone, Copy, Default)]
+struct SlidingCoMoments {
+    count: usize,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+    last: (f64, f64),
+    flat_run: (usize, usize),
 }
 
-/// Calculate rolling rank (percentile)
-fn rolling_rank(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
-    if window < 2 {
-        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+impl SlidingCoMoments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        let (dx, dy) = (x - self.mean_x, y - self.mean_y);
+        self.mean_x += dx / self.count as f64;
+        self.mean_y += dy / self.count as f64;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+
+        let repeats = self.count > 1;
+        self.flat_run.0 = if repeats && x == self.last.0 { self.flat_run.0 + 1 } else { 1 };
+        self.flat_run.1 = if repeats && y == self.last.1 { self.flat_run.1 + 1 } else { 1 };
+        self.last = (x, y);
+        self.snap_flat();
     }
 
-    let n = data.len();
-    let mut result = Array1::zeros(n);
+    /// Zero the moments of a side whose window holds one repeated value
+    fn snap_flat(&mut self) {
+        if self.flat_run.0 >= self.count {
+            (self.mean_x, self.m2_x, self.c_xy) = (self.last.0, 0.0, 0.0);
+        }
+        if self.flat_run.1 >= self.count {
+            (self.mean_y, self.m2_y, self.c_xy) = (self.last.1, 0.0, 0.0);
+        }
+    }
 
-    for i in 0..n {
-        result[i] = if i < window - 1 {
-            f64::NAN
-        } else {
-            let start = i.saturating_sub(window - 1);
-            let window_data: Vec<f64> = data.slice(s![start..=i])
-                .iter()
-                .filter(|&&x| !x.is_nan() && !x.is_infinite())
-                .copied()
-                .collect();
-            
-            if window_data.is_empty() {
-                f64::NAN
-            } else {
-                let current = data[i];
-                if current.is_nan() || current.is_infinite() {
-                    f64::NAN
-                } else {
-                    let rank = window_data.iter()
-                        .filter(|&&x| x <= current)
-                        .count() as f64;
-                    rank / window_data.len() as f64
-                }
-            }
-        };
+    fn remove(&mut self, x: f64, y: f64) {
+        self.count -= 1;
+        if self.count == 0 {
+            *self = Self::default();
+            return;
+        }
+        let (dx, dy) = (x - self.mean_x, y - self.mean_y);
+        self.mean_x -= dx / self.count as f64;
+        self.mean_y -= dy / self.count as f64;
+        self.m2_x -= dx * (x - self.mean_x);
+        self.m2_y -= dy * (y - self.mean_y);
+        self.c_xy -= dx * (y - self.mean_y);
+        self.snap_flat();
     }
+}
 
-    Ok(result)
+/// `SlidingVariance` extended to the third and fourth centred moments
+///
+/// `add` is Pébay's one-pass update and `remove` its exact inverse, so like the variance
+/// the higher moments are only ever updated through deviations from the running mean and
+/// stay accurate at price-level offsets.
+#[derive(Clone, Copy, Default)]
+struct SlidingHigherMoments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
 }
 
-/// Calculate rolling correlation
-fn rolling_correlation(x: &Array1<f64>, y: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
-    if window < 2 {
-        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+impl SlidingHigherMoments {
+    fn add(&mut self, x: f64) {
+        let n1 = self.count as f64;
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * n1;
+        self.mean += delta_n;
+        self.m4 += term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2 - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
     }
 
-    let n = x.len();
-    let mut result = Array1::zeros(n);
-    let mut queue_x = VecDeque::with_capacity(window);
-    let mut queue_y = VecDeque::with_capacity(window);
-    let mut sum_x = 0.0;
-    let mut sum_y = 0.0;
-    let mut sum_xy = 0.0;
-    let mut sum_xx = 0.0;
-    let mut sum_yy = 0.0;
-    let mut count = 0;
+    fn remove(&mut self, x: f64) {
+        if self.count == 1 {
+            *self = Self::default();
+            return;
+        }
+        let n = self.count as f64;
+        self.count -= 1;
+        let mean = (n * self.mean - x) / (n - 1.0);
+        let delta = x - mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * (n - 1.0);
+        self.mean = mean;
+        self.m2 -= term;
+        self.m3 -= term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m4 -= term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2 - 4.0 * delta_n * self.m3;
+    }
+}
 
-    for i in 0..n {
-        if x[i].is_nan() || x[i].is_infinite() || y[i].is_nan() || y[i].is_infinite() {
-            result[i] = f64::NAN;
-            continue;
+/// Sliding-window state behind `rolling_mean`
+///
+/// Kept separate so chunked and batch computations share one update rule.
+#[derive(Clone)]
+struct MeanAccumulator {
+    window: usize,
+    min_periods: usize,
+    queue: VecDeque<f64>,
+    sum: f64,
+    count: usize,
+}
+
+impl MeanAccumulator {
+    fn new(window: usize) -> Result<Self> {
+        Self::with_min_periods(window, window)
+    }
+
+    fn with_min_periods(window: usize, min_periods: usize) -> Result<Self> {
+        if window < 1 {
+            return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
         }
+        check_min_periods(window, min_periods)?;
+        Ok(MeanAccumulator { window, min_periods, queue: VecDeque::with_capacity(window), sum: 0.0, count: 0 })
+    }
 
-        queue_x.push_back(x[i]);
-        queue_y.push_back(y[i]);
-        sum_x += x[i];
-        sum_y += y[i];
-        sum_xy += x[i] * y[i];
-        sum_xx += x[i] * x[i];
-        sum_yy += y[i] * y[i];
-        count += 1;
+    /// Whether the last output came from fewer than `window` observations
+    fn is_partial(&self) -> bool {
+        self.count < self.window
+    }
 
-        if count > window {
-            let old_x = queue_x.pop_front().unwrap();
-            let old_y = queue_y.pop_front().unwrap();
-            sum_x -= old_x;
-            sum_y -= old_y;
-            sum_xy -= old_x * old_y;
-            sum_xx -= old_x * old_x;
-            sum_yy -= old_y * old_y;
-            count -= 1;
+    /// NaN/inf values are skipped like in `rolling_std`: they yield NaN and leave the
+    /// window untouched, so `count` is the number of valid values held.
+    fn push(&mut self, val: f64) -> f64 {
+        if !val.is_finite() {
+            return f64::NAN;
         }
+        self.queue.push_back(val);
+        self.sum += val;
+        self.count += 1;
 
-        result[i] = if count < window {
+        if self.count > self.window {
+            self.sum -= self.queue.pop_front().unwrap();
+            self.count -= 1;
+        }
+
+        if self.count < self.min_periods {
             f64::NAN
         } else {
-            let mean_x = sum_x / count as f64;
-            let mean_y = sum_y / count as f64;
-            let cov = (sum_xy / count as f64) - (mean_x * mean_y);
-            let var_x = (sum_xx / count as f64) - (mean_x * mean_x);
-            let var_y = (sum_yy / count as f64) - (mean_y * mean_y);
-            
-            if var_x <= 0.0 || var_y <= 0.0 {
-                f64::NAN
-            } else {
-                cov / (var_x.sqrt() * var_y.sqrt())
+            self.sum / self.count as f64
+        }
+    }
+}
+
+/// Check that `min_periods` lies in `1..=window`
+fn check_min_periods(window: usize, min_periods: usize) -> Result<()> {
+    if min_periods < 1 || min_periods > window {
+        return Err(ExprError::InvalidPeriod(format!(
+            "min_periods {} must be between 1 and the window size {}", min_periods, window
+        )));
+    }
+    Ok(())
+}
+
+/// Check that a factor's longest derived window fits in a series of length `n`
+///
+/// The rolling primitives accept any window and return NaN where it is not yet full; the
+/// factors call this instead so a lookback too long for the input fails loudly rather than
+/// producing an all-NaN result. A window equal to `n` is allowed.
+fn check_window_fits(name: &str, window: usize, n: usize) -> Result<()> {
+    if window > n {
+        return Err(ExprError::InvalidPeriod(format!(
+            "{} {} exceeds series length {}", name, window, n
+        )));
+    }
+    Ok(())
+}
+
+/// Calculate rolling mean over the last `window` valid values
+///
+/// NaN/inf inputs give NaN at their own index and are skipped, so a gap no longer
+/// poisons later windows and the windows line up with `rolling_std`.
+pub fn rolling_mean(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let mut acc = MeanAccumulator::new(window)?;
+    Ok(data.mapv(|val| acc.push(val)))
+}
+
+/// Rolling sum over the last `window` valid observations
+///
+/// The sliding sum behind `rolling_mean`, so windows and NaN/inf skipping are identical:
+/// NaN/inf inputs give NaN at their own index, and outputs are NaN until `window` finite
+/// values have been seen.
+pub fn rolling_sum(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let mut acc = MeanAccumulator::new(window)?;
+    Ok(data.mapv(|val| if acc.push(val).is_nan() { f64::NAN } else { acc.sum }))
+}
+
+/// Rolling product over the last `window` valid observations
+///
+/// Windows and NaN/inf skipping follow `rolling_sum`. The product of the non-zero values
+/// is updated multiplicatively and zeros are counted separately, so a zero leaving the
+/// window is never divided out; if the running product stops being a normal float
+/// (underflow, overflow) it is recomputed from the window.
+pub fn rolling_prod(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let (mut product, mut zeros) = (1.0, 0usize);
+    Ok(data.mapv(|val| {
+        if !val.is_finite() {
+            return f64::NAN;
+        }
+        queue.push_back(val);
+        if val == 0.0 { zeros += 1 } else { product *= val }
+        if queue.len() > window {
+            match queue.pop_front().unwrap() {
+                0.0 => zeros -= 1,
+                old => product /= old,
             }
-        };
+        }
+        if !product.is_normal() {
+            product = queue.iter().filter(|&&x| x != 0.0).product();
+        }
+
+        if queue.len() < window {
+            f64::NAN
+        } else if zeros > 0 {
+            0.0
+        } else {
+            product
+        }
+    }))
+}
+
+/// Rolling geometric mean `prod^(1 / window)` over the last `window` valid observations
+///
+/// For growth factors `1 + r` this is the per-bar compounded growth. A window containing a
+/// zero gives 0; a negative product gives NaN.
+pub fn rolling_geomean(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let exponent = 1.0 / window as f64;
+    Ok(rolling_prod(data, window)?.mapv(|p| if p < 0.0 { f64::NAN } else { p.powf(exponent) }))
+}
+
+/// Rolling mean emitted from `min_periods` observations, flagged while the window is partial
+///
+/// As in `rolling_std_partial`, `min_periods` counts valid observations.
+pub fn rolling_mean_partial(data: &Array1<f64>, window: usize, min_periods: usize) -> Result<(Array1<f64>, Array1<bool>)> {
+    let mut acc = MeanAccumulator::with_min_periods(window, min_periods)?;
+    let mut partial = Array1::from_elem(data.len(), false);
+    let values = Array1::from_iter(data.iter().enumerate().map(|(i, &val)| {
+        let out = acc.push(val);
+        partial[i] = acc.is_partial();
+        out
+    }));
+    Ok((values, partial))
+}
+
+/// `rolling_mean_partial` returning how many valid observations back each output
+///
+/// The count is the accumulator's `count`: the finite values held in the window, at most
+/// `window`. It is reported at every index, including NaN/inf inputs (whose output is NaN)
+/// and warm-up positions below `min_periods`. Because windows skip NaN/inf rather than
+/// shrinking, a gap lengthens the lookback instead of lowering the count once `window`
+/// valid values have been seen.
+pub fn rolling_mean_with_count(data: &Array1<f64>, window: usize, min_periods: usize) -> Result<(Array1<f64>, Array1<i64>)> {
+    let mut acc = MeanAccumulator::with_min_periods(window, min_periods)?;
+    let mut counts = Array1::zeros(data.len());
+    let values = Array1::from_iter(data.iter().enumerate().map(|(i, &val)| {
+        let out = acc.push(val);
+        counts[i] = acc.count as i64;
+        out
+    }));
+    Ok((values, counts))
+}
+
+/// Number of finite values among the last `window` bars
+///
+/// Unlike the valid-count windows of `rolling_mean` and `rolling_std`, this window is
+/// positional: it always spans `window` bars, so it measures how dense the data is rather
+/// than how many observations a kernel used. NaN during the first `window - 1` bars.
+pub fn rolling_count(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+    let mut count = 0usize;
+    Ok(Array1::from_iter((0..data.len()).map(|i| {
+        count += data[i].is_finite() as usize;
+        if i >= window {
+            count -= data[i - window].is_finite() as usize;
+        }
+        if i + 1 < window { f64::NAN } else { count as f64 }
+    })))
+}
+
+/// Fraction of NaN/inf values among the last `window` bars, `1 - rolling_count / window`
+pub fn rolling_nan_ratio(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    Ok(rolling_count(data, window)?.mapv(|c| 1.0 - c / window as f64))
+}
+
+/// Rolling mean whose window at position `i` is `windows[i]`
+///
+/// Each output averages `data[i + 1 - windows[i]..=i]`. The windows are positional, so
+/// one holding a NaN/inf gives NaN. The window edges move incrementally, so a slowly varying
+/// window costs O(1) per step.
+fn variable_window_mean(data: &Array1<f64>, windows: &[usize]) -> Result<Array1<f64>> {
+    if windows.len() != data.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "windows has length {}, data has length {}", windows.len(), data.len()
+        )));
+    }
+
+    // Running sum of the finite values in `data[lo..=i]` and a count of the others
+    fn update(val: f64, entering: bool, sum: &mut f64, invalid: &mut usize) {
+        match (val.is_finite(), entering) {
+            (true, true) => *sum += val,
+            (true, false) => *sum -= val,
+            (false, true) => *invalid += 1,
+            (false, false) => *invalid -= 1,
+        }
+    }
+
+    let mut result = Array1::zeros(data.len());
+    let (mut lo, mut sum, mut invalid) = (0usize, 0.0, 0usize);
+
+    for (i, &w) in windows.iter().enumerate() {
+        if w < 1 || w > i + 1 {
+            return Err(ExprError::InvalidPeriod(format!("window {} at index {} must be in 1..={}", w, i, i + 1)));
+        }
+        update(data[i], true, &mut sum, &mut invalid);
+        let start = i + 1 - w;
+        while lo < start {
+            update(data[lo], false, &mut sum, &mut invalid);
+            lo += 1;
+        }
+        while lo > start {
+            lo -= 1;
+            update(data[lo], true, &mut sum, &mut invalid);
+        }
+        result[i] = if invalid > 0 { f64::NAN } else { sum / w as f64 };
     }
 
     Ok(result)
 }
 
-/// Alpha101 Factor #42 calculation
-#[pyfunction]
-fn alpha101_factor_42<'py>(py: Python<'py>, high: &PyArray1<f64>, volume: &PyArray1<f64>) -> PyResult<&'py PyArray1<f64>> {
-    let high = high.readonly();
-    let volume = volume.readonly();
-    let high_arr = Array1::from_vec(high.as_array().to_vec());
-    let volume_arr = Array1::from_vec(volume.as_array().to_vec());
-    
-    // Calculate standard deviation of high prices
-    let high_std = rolling_std(&high_arr, 10)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-    
-    // Calculate rank of standard deviation
-    let vol_rank = rolling_rank(&high_std, 10)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-    
-    // Calculate correlation between high and volume
-    let vol_price_corr = rolling_correlation(&high_arr, &volume_arr, 10)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-    
-    // Combine components
-    let result = vol_rank.mapv(|x| if x.is_nan() || x.is_infinite() { f64::NAN } else { -x }) 
-        * vol_price_corr.mapv(|x| if x.is_nan() || x.is_infinite() { f64::NAN } else { x });
-    
-    Ok(result.into_pyarray(py))
-}
-
-/// Python module
-#[pymodule]
-fn rust_expr(_py: Python, m: &PyModule) -> PyResult<()> {
-    /// Momentum factor calculation
-    #[pyfunction]
-    fn momentum_factor<'py>(py: Python<'py>, prices: &PyArray1<f64>, lookback: usize) -> PyResult<&'py PyArray1<f64>> {
-        let prices = prices.readonly();
-        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
-        
-        // Calculate returns and momentum
-        let returns = pct_change(&prices_arr, 1).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-        let momentum = pct_change(&prices_arr, lookback).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-        let vol = rolling_std(&returns, lookback).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-        
-        // Calculate momentum/vol ratio with NaN handling
-        let result = momentum.iter()
-            .zip(vol.iter())
-            .map(|(&m, &v)| {
-                if m.is_nan() || m.is_infinite() || v.is_nan() || v.is_infinite() || v == 0.0 {
-                    f64::NAN
-                } else {
-                    m / v
-                }
-            })
-            .collect::<Vec<f64>>();
-        
-        Ok(Array1::from_vec(result).into_pyarray(py))
-    }
-
-    /// Mean reversion factor calculation
-    #[pyfunction]
-    fn mean_reversion_factor<'py>(py: Python<'py>, prices: &PyArray1<f64>, lookback: usize) -> PyResult<&'py PyArray1<f64>> {
-        let prices = prices.readonly();
-        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
-        
-        let ma = rolling_mean(&prices_arr, lookback).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-        let std = rolling_std(&prices_arr, lookback).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-        
-        // Calculate z-score with NaN handling
-        let result = prices_arr.iter()
-            .zip(ma.iter().zip(std.iter()))
-            .map(|(&x, (&m, &s))| {
-                if x.is_nan() || x.is_infinite() || m.is_nan() || m.is_infinite() || s.is_nan() || s.is_infinite() || s == 0.0 {
-                    f64::NAN
-                } else {
-                    -(x - m) / s
+/// Mean over a window that grows as `frac` of the elapsed history, capped at `max_window`
+///
+/// The window at position `i` is `min(max_window, floor(frac * (i + 1)))`, at least 1, so
+/// the output starts as a short expanding mean and settles into a `max_window` rolling mean.
+pub fn fractional_window_mean(data: &Array1<f64>, frac: f64, max_window: usize) -> Result<Array1<f64>> {
+    if !(frac > 0.0 && frac <= 1.0) {
+        return Err(ExprError::InvalidParameter("frac must be in (0, 1]".into()));
+    }
+    if max_window < 1 {
+        return Err(ExprError::InvalidPeriod("max_window must be at least 1".into()));
+    }
+
+    let windows: Vec<usize> = (0..data.len())
+        .map(|i| ((frac * (i + 1) as f64).floor() as usize).clamp(1, max_window))
+        .collect();
+    variable_window_mean(data, &windows)
+}
+
+/// Iterator over `rolling_mean` output in chunks of `chunk_size`
+///
+/// The window state carries across chunk boundaries, so concatenating the chunks gives
+/// exactly the all-at-once result.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct RollingMeanChunks {
+    data: Vec<f64>,
+    pos: usize,
+    chunk_size: usize,
+    acc: MeanAccumulator,
+}
+
+impl RollingMeanChunks {
+    pub fn with_data(data: Vec<f64>, window: usize, chunk_size: usize) -> Result<Self> {
+        if chunk_size < 1 {
+            return Err(ExprError::InvalidParameter("chunk_size must be positive".into()));
+        }
+        Ok(RollingMeanChunks { data, pos: 0, chunk_size, acc: MeanAccumulator::new(window)? })
+    }
+
+    pub fn next_chunk(&mut self) -> Option<Array1<f64>> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+
+        let end = (self.pos + self.chunk_size).min(self.data.len());
+        let chunk = Array1::from_iter(self.data[self.pos..end].iter().map(|&val| self.acc.push(val)));
+        self.pos = end;
+        Some(chunk)
+    }
+}
+
+/// Rolling mean over a time span rather than a bar count
+///
+/// The output at `i` averages the finite values whose timestamp lies in
+/// `(timestamps[i] - window, timestamps[i]]`, in the same units as `timestamps` (e.g. ns).
+/// Timestamps must be non-decreasing; equal stamps all enter together, so a bar sees
+/// later bars sharing its stamp. NaN/inf inputs give NaN at their own index and are
+/// skipped, as in `rolling_mean`. There is no warm-up: the first bar averages itself.
+pub fn rolling_mean_time(data: &Array1<f64>, timestamps: &[i64], window: i64) -> Result<Array1<f64>> {
+    if data.len() != timestamps.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "data has length {} but timestamps has length {}", data.len(), timestamps.len()
+        )));
+    }
+    if window <= 0 {
+        return Err(ExprError::InvalidPeriod(format!("time window {} must be positive", window)));
+    }
+    if let Some(i) = (1..timestamps.len()).find(|&i| timestamps[i] < timestamps[i - 1]) {
+        return Err(ExprError::InvalidParameter(format!(
+            "timestamps must be non-decreasing, but index {} goes back in time", i
+        )));
+    }
+
+    let n = data.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    let (mut sum, mut count) = (0.0, 0usize);
+    let (mut head, mut tail) = (0, 0);
+    for i in 0..n {
+        while head < n && timestamps[head] <= timestamps[i] {
+            if data[head].is_finite() {
+                sum += data[head];
+                count += 1;
+            }
+            head += 1;
+        }
+        // A window reaching before i64::MIN starts before every bar, so nothing leaves
+        if let Some(start) = timestamps[i].checked_sub(window) {
+            while tail < head && timestamps[tail] <= start {
+                if data[tail].is_finite() {
+                    sum -= data[tail];
+                    count -= 1;
                 }
-            })
-            .collect::<Vec<f64>>();
-        
-        Ok(Array1::from_vec(result).into_pyarray(py))
-    }
-
-    /// Relative strength factor calculation
-    #[pyfunction]
-    fn relative_strength_factor<'py>(py: Python<'py>, prices: &PyArray1<f64>, lookback: usize) -> PyResult<&'py PyArray1<f64>> {
-        let prices = prices.readonly();
-        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
-        
-        let timeframes = [lookback / 3, lookback, lookback * 2];
-        let weights = [0.5, 0.3, 0.2];
-        let mut result = Array1::zeros(prices_arr.len());
-        
-        for (&tf, &weight) in timeframes.iter().zip(weights.iter()) {
-            let mom = pct_change(&prices_arr, tf).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-            let rank = rolling_rank(&mom, lookback).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
-            result = result + rank.mapv(|x| if x.is_nan() || x.is_infinite() { 0.0 } else { x * weight });
-        }
-        
-        Ok(result.into_pyarray(py))
-    }
-
-    // Add functions to the module
-    m.add_function(wrap_pyfunction!(momentum_factor, m)?)?;
-    m.add_function(wrap_pyfunction!(mean_reversion_factor, m)?)?;
-    m.add_function(wrap_pyfunction!(relative_strength_factor, m)?)?;
-
-    // Add Alpha101 Factor #42
-    m.add_function(wrap_pyfunction!(alpha101_factor_42, m)?)?;
+                tail += 1;
+            }
+        }
+        if data[i].is_finite() && count > 0 {
+            result[i] = sum / count as f64;
+        }
+    }
+    Ok(result)
+}
 
-    Ok(())
+/// Calculate rolling standard deviation
+///
+/// `-0.0` behaves exactly like `0.0`. A flat window has a std of exactly 0.0, as does a
+/// window whose squared deviations underflow (|x| below ~1e-154). A variance that comes
+/// out slightly negative from rounding is clamped to 0.0; see `rolling_std_partial_with`
+/// to get NaN instead. The default accumulator is a sliding Welford update, so a large
+/// common offset does not cancel the way `sum_sq - n·mean²` would.
+///
+/// The variance is divided by `count - ddof`: `ddof = 0` is the population std, `ddof = 1`
+/// the sample std. Windows with `count <= ddof` are NaN.
+pub fn rolling_std(data: &Array1<f64>, window: usize, ddof: usize) -> Result<Array1<f64>> {
+    Ok(rolling_std_partial(data, window, window, ddof)?.0)
+}
+
+/// Rolling variance, the square of `rolling_std` computed without the square root
+///
+/// Same windows, `ddof` handling, flat-window zero and clamping as `rolling_std`.
+pub fn rolling_var(data: &Array1<f64>, window: usize, ddof: usize) -> Result<Array1<f64>> {
+    let mut acc = StdAccumulator::new(window, window, ddof, precision_mode(), true)?;
+    Ok(data.mapv(|val| acc.push_var(val)))
+}
+
+/// Rolling std emitted from `min_periods` valid observations, flagged while the window is partial
+pub fn rolling_std_partial(data: &Array1<f64>, window: usize, min_periods: usize, ddof: usize) -> Result<(Array1<f64>, Array1<bool>)> {
+    rolling_std_partial_with(data, window, min_periods, ddof, precision_mode(), true)
+}
+
+/// `rolling_std_partial` with an explicit accumulator choice
+///
+/// A window whose values are all equal gives exactly 0.0 in either mode, detected from the
+/// run of equal trailing values rather than from the accumulated variance. A negative
+/// variance, which only arises from rounding, is clamped to 0.0 when `clamp_negative` is
+/// set and gives NaN otherwise.
+pub fn rolling_std_partial_with(
+    data: &Array1<f64>,
+    window: usize,
+    min_periods: usize,
+    ddof: usize,
+    mode: PrecisionMode,
+    clamp_negative: bool,
+) -> Result<(Array1<f64>, Array1<bool>)> {
+    let mut acc = StdAccumulator::new(window, min_periods, ddof, mode, clamp_negative)?;
+    let mut partial = Array1::from_elem(data.len(), true);
+    let result = Array1::from_iter(data.iter().enumerate().map(|(i, &val)| {
+        let value = acc.push(val);
+        partial[i] = acc.is_partial();
+        value
+    }));
+    Ok((result, partial))
+}
+
+/// Sliding-window state behind `rolling_std_partial_with`
+///
+/// Shared with the streaming `RollingStd` so both apply one update rule.
+#[derive(Clone)]
+struct StdAccumulator {
+    window: usize,
+    min_periods: usize,
+    ddof: usize,
+    mode: PrecisionMode,
+    clamp_negative: bool,
+    queue: VecDeque<f64>,
+    moments: SlidingVariance,
+    flat_run: usize,
+}
+
+impl StdAccumulator {
+    fn new(window: usize, min_periods: usize, ddof: usize, mode: PrecisionMode, clamp_negative: bool) -> Result<Self> {
+        if window < 2 {
+            return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+        }
+        check_min_periods(window, min_periods)?;
+        Ok(StdAccumulator {
+            window,
+            min_periods,
+            ddof,
+            mode,
+            clamp_negative,
+            queue: VecDeque::with_capacity(window),
+            moments: SlidingVariance::default(),
+            flat_run: 0,
+        })
+    }
+
+    /// Forget every observation, keeping the configuration
+    fn reset(&mut self) {
+        self.queue.clear();
+        self.moments = SlidingVariance::default();
+        self.flat_run = 0;
+    }
+
+    /// Whether fewer than `window` valid observations are held
+    fn is_partial(&self) -> bool {
+        self.moments.count < self.window
+    }
+
+    /// Add a value and return the current std
+    fn push(&mut self, val: f64) -> f64 {
+        self.push_var(val).sqrt()
+    }
+
+    /// Add a value and return the current variance
+    ///
+    /// NaN/inf values yield NaN and leave the window untouched.
+    fn push_var(&mut self, val: f64) -> f64 {
+        if val.is_nan() || val.is_infinite() {
+            return f64::NAN;
+        }
+
+        self.flat_run = if self.queue.back() == Some(&val) { self.flat_run + 1 } else { 1 };
+        self.queue.push_back(val);
+        self.moments.add(val);
+        if self.moments.count > self.window {
+            self.moments.remove(self.queue.pop_front().unwrap());
+        }
+
+        let count = self.moments.count;
+        if count < self.min_periods || count <= self.ddof {
+            f64::NAN
+        } else if self.flat_run >= count {
+            0.0
+        } else {
+            let population = match self.mode {
+                PrecisionMode::Fast => self.moments.population(),
+                PrecisionMode::Accurate => {
+                    let mean = self.queue.iter().sum::<f64>() / count as f64;
+                    self.queue.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / count as f64
+                }
+            };
+            let variance = population * (count as f64 / (count - self.ddof) as f64);
+            if variance >= 0.0 {
+                variance
+            } else if self.clamp_negative {
+                0.0
+            } else {
+                f64::NAN
+            }
+        }
+    }
+}
+
+/// Streaming counterpart of `rolling_mean`
+///
+/// After the same sequence of updates, `push` returns exactly the value `rolling_mean`
+/// produces at the last index: NaN until `window` finite values have arrived, and NaN
+/// (leaving the window untouched) for a NaN/inf update.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct RollingMean {
+    acc: MeanAccumulator,
+}
+
+impl RollingMean {
+    pub fn with_window(window: usize) -> Result<Self> {
+        Ok(RollingMean { acc: MeanAccumulator::new(window)? })
+    }
+
+    pub fn push(&mut self, value: f64) -> f64 {
+        self.acc.push(value)
+    }
+
+    /// Forget every observation, keeping the window
+    pub fn reset(&mut self) {
+        self.acc = MeanAccumulator::new(self.acc.window).unwrap();
+    }
+}
+
+/// Streaming counterpart of `rolling_std` (fast precision mode, negative variances clamped)
+///
+/// After the same sequence of updates, `push` returns exactly the value
+/// `rolling_std(data, window, ddof)` produces at the last index.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct RollingStd {
+    acc: StdAccumulator,
+}
+
+impl RollingStd {
+    pub fn with_window(window: usize, ddof: usize) -> Result<Self> {
+        Ok(RollingStd { acc: StdAccumulator::new(window, window, ddof, PrecisionMode::Fast, true)? })
+    }
+
+    pub fn push(&mut self, value: f64) -> f64 {
+        self.acc.push(value)
+    }
+
+    /// Forget every observation, keeping the window and `ddof`
+    pub fn reset(&mut self) {
+        self.acc.reset();
+    }
+}
+
+/// Rolling mean absolute deviation from the window mean over the last `window` valid values
+///
+/// Windows follow `rolling_std`. The mean comes from a running sum, but `|x - mean|` has
+/// no sliding update, so each window is re-scanned: O(n * window).
+pub fn rolling_mean_abs_dev(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let n = data.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let mut sum = 0.0;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+
+        queue.push_back(val);
+        sum += val;
+        if queue.len() > window {
+            sum -= queue.pop_front().unwrap();
+        }
+
+        if queue.len() == window {
+            let mean = sum / window as f64;
+            result[i] = queue.iter().map(|x| (x - mean).abs()).sum::<f64>() / window as f64;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Median of an already sorted, non-empty slice
+fn sorted_median(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        0.5 * (sorted[mid - 1] + sorted[mid])
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Rolling median and median absolute deviation over the last `window` valid values
+///
+/// Windows follow `rolling_std`: NaN/inf inputs are skipped and give NaN at their own index,
+/// and output starts once `window` valid values have been seen. Each window is sorted, so
+/// this costs O(n * window * log(window)).
+fn rolling_median_mad(data: &Array1<f64>, window: usize) -> Result<(Array1<f64>, Array1<f64>)> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let n = data.len();
+    let mut median = Array1::from_elem(n, f64::NAN);
+    let mut mad = Array1::from_elem(n, f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let mut sorted = Vec::with_capacity(window);
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+
+        queue.push_back(val);
+        if queue.len() > window {
+            queue.pop_front();
+        }
+        if queue.len() < window {
+            continue;
+        }
+
+        sorted.clear();
+        sorted.extend(queue.iter().copied());
+        (median[i], mad[i]) = median_and_mad(&mut sorted);
+    }
+
+    Ok((median, mad))
+}
+
+/// Median of `values` and median absolute deviation from it; reorders `values`
+fn median_and_mad(values: &mut [f64]) -> (f64, f64) {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let center = sorted_median(values);
+    values.iter_mut().for_each(|x| *x = (*x - center).abs());
+    values.sort_by(|a, b| a.total_cmp(b));
+    (center, sorted_median(values))
+}
+
+/// Rolling median absolute deviation over the last `window` valid values
+///
+/// The median of `|x - median|` per window, from `rolling_median_mad`. With `normalized`
+/// it is multiplied by 1.4826, which makes it a consistent estimate of the std for normal
+/// data, so it can stand in for `rolling_std` as an outlier-resistant scale.
+pub fn rolling_mad(data: &Array1<f64>, window: usize, normalized: bool) -> Result<Array1<f64>> {
+    let scale = if normalized { 1.4826 } else { 1.0 };
+    Ok(rolling_median_mad(data, window)?.1 * scale)
+}
+
+/// Quantile of an already sorted, non-empty slice, linearly interpolated like numpy's default
+fn sorted_quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (pos - lo as f64) * (sorted[hi] - sorted[lo])
+}
+
+/// Rolling quantiles `qs` over the last `window` valid values, one output per quantile
+///
+/// Windows follow `rolling_median_mad`; each window is sorted once for all quantiles.
+fn rolling_quantiles(data: &Array1<f64>, window: usize, qs: &[f64]) -> Result<Vec<Array1<f64>>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+    if let Some(q) = qs.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+        return Err(ExprError::InvalidParameter(format!("quantile must be in [0, 1], got {}", q)));
+    }
+
+    let n = data.len();
+    let mut result = vec![Array1::from_elem(n, f64::NAN); qs.len()];
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let mut sorted = Vec::with_capacity(window);
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+
+        queue.push_back(val);
+        if queue.len() > window {
+            queue.pop_front();
+        }
+        if queue.len() < window {
+            continue;
+        }
+
+        sorted.clear();
+        sorted.extend(queue.iter().copied());
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        for (out, &q) in result.iter_mut().zip(qs) {
+            out[i] = sorted_quantile(&sorted, q);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rolling `q` quantile over the last `window` valid values, interpolated like numpy
+pub fn rolling_quantile(data: &Array1<f64>, window: usize, q: f64) -> Result<Array1<f64>> {
+    Ok(rolling_quantiles(data, window, &[q])?.remove(0))
+}
+
+/// Rolling median over the last `window` valid values
+pub fn rolling_median(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    rolling_quantile(data, window, 0.5)
+}
+
+/// Rolling interquartile range over the last `window` valid values
+///
+/// With `normalize` the IQR is divided by 1.349, the IQR of a unit normal, to give a
+/// std-comparable scale.
+pub fn rolling_iqr(data: &Array1<f64>, window: usize, normalize: bool) -> Result<Array1<f64>> {
+    let quartiles = rolling_quantiles(data, window, &[0.75, 0.25])?;
+    let scale = if normalize { 1.349 } else { 1.0 };
+    Ok((&quartiles[0] - &quartiles[1]) / scale)
+}
+
+/// Rolling Shannon entropy, in bits, of a histogram of the last `window` valid values
+///
+/// Each window is binned on its own range: `bins` equal-width buckets spanning
+/// `[min, max]`, closed on the right for the last bucket as in `numpy.histogram`. The result
+/// is `-sum(p * log2(p))` over the non-empty buckets, so it lies in `[0, log2(bins)]` and
+/// depends on `bins`; since the edges move with every window, it measures the shape of the
+/// window's spread rather than its scale. NaN during the warm-up, at NaN/inf inputs and where
+/// the range is zero (see `is_near_zero`). O(n * window).
+pub fn rolling_entropy(data: &Array1<f64>, window: usize, bins: usize) -> Result<Array1<f64>> {
+    if window == 0 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+    if bins == 0 {
+        return Err(ExprError::InvalidParameter("bins must be at least 1".into()));
+    }
+
+    let mut result = Array1::from_elem(data.len(), f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let mut counts = vec![0usize; bins];
+
+    for (i, &x) in data.iter().enumerate() {
+        if !x.is_finite() {
+            continue;
+        }
+        queue.push_back(x);
+        if queue.len() > window {
+            queue.pop_front();
+        }
+        if queue.len() < window {
+            continue;
+        }
+
+        let (lo, hi) = queue.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        let range = hi - lo;
+        if !range.is_finite() || is_near_zero(range, hi.abs().max(lo.abs())) {
+            continue;
+        }
+        counts.fill(0);
+        for &v in &queue {
+            counts[(((v - lo) / range * bins as f64) as usize).min(bins - 1)] += 1;
+        }
+        result[i] = -counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / window as f64;
+                p * p.log2()
+            })
+            .sum::<f64>();
+    }
+
+    Ok(result)
+}
+
+/// +1 above the rolling `upper_q` quantile, -1 below the `lower_q` quantile, 0 in between
+///
+/// The quantiles come from `rolling_quantiles`, so the window includes the current value
+/// and a breakout means it is strictly beyond the interpolated quantile. Warm-up and
+/// non-finite positions are NaN.
+pub fn percentile_breakout(data: &Array1<f64>, window: usize, upper_q: f64, lower_q: f64) -> Result<Array1<f64>> {
+    if lower_q >= upper_q {
+        return Err(ExprError::InvalidParameter(format!(
+            "lower_q ({}) must be below upper_q ({})",
+            lower_q, upper_q
+        )));
+    }
+
+    let bands = rolling_quantiles(data, window, &[upper_q, lower_q])?;
+    let (upper, lower) = (&bands[0], &bands[1]);
+    Ok(Array1::from_iter((0..data.len()).map(|i| {
+        if upper[i].is_nan() {
+            f64::NAN
+        } else if data[i] > upper[i] {
+            1.0
+        } else if data[i] < lower[i] {
+            -1.0
+        } else {
+            0.0
+        }
+    })))
+}
+
+/// Rolling extreme over the last `window` valid values via a monotonic deque
+///
+/// The deque holds `(valid_ordinal, value)` pairs with values strictly decreasing (for the
+/// max) from the front, so each value is pushed and popped once: O(n) overall. NaN/inf
+/// inputs are skipped and give NaN, and outputs are NaN until `window` valid values have
+/// been seen, as in `rolling_std`.
+fn rolling_extreme(data: &Array1<f64>, window: usize, is_max: bool) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let mut result = Array1::from_elem(data.len(), f64::NAN);
+    let mut deque = ExtremeDeque::new(window, is_max);
+    let mut count = 0usize;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+        let extreme = deque.push(count, val);
+        count += 1;
+        if count >= window {
+            result[i] = extreme;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Monotonic deque behind `rolling_extreme`, keyed by the ordinal of each valid value
+struct ExtremeDeque {
+    window: usize,
+    is_max: bool,
+    deque: VecDeque<(usize, f64)>,
+}
+
+impl ExtremeDeque {
+    fn new(window: usize, is_max: bool) -> Self {
+        ExtremeDeque { window, is_max, deque: VecDeque::with_capacity(window) }
+    }
+
+    /// Add the `ordinal`-th valid value and return the extreme of the last `window`
+    fn push(&mut self, ordinal: usize, val: f64) -> f64 {
+        let is_max = self.is_max;
+        while self.deque.back().is_some_and(|&(_, v)| if is_max { val >= v } else { val <= v }) {
+            self.deque.pop_back();
+        }
+        self.deque.push_back((ordinal, val));
+        if self.deque.front().is_some_and(|&(k, _)| k + self.window <= ordinal) {
+            self.deque.pop_front();
+        }
+        self.deque[0].1
+    }
+}
+
+/// Rolling minimum and maximum over the last `window` valid values in one pass
+///
+/// Identical to `rolling_min` and `rolling_max`, with both deques fed from a single scan.
+pub fn rolling_minmax(data: &Array1<f64>, window: usize) -> Result<(Array1<f64>, Array1<f64>)> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let mut min = Array1::from_elem(data.len(), f64::NAN);
+    let mut max = Array1::from_elem(data.len(), f64::NAN);
+    let (mut low, mut high) = (ExtremeDeque::new(window, false), ExtremeDeque::new(window, true));
+    let mut count = 0usize;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+        let (lo, hi) = (low.push(count, val), high.push(count, val));
+        count += 1;
+        if count >= window {
+            min[i] = lo;
+            max[i] = hi;
+        }
+    }
+
+    Ok((min, max))
+}
+
+/// Rolling `max - min` over the last `window` valid values
+pub fn rolling_range(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let (min, max) = rolling_minmax(data, window)?;
+    Ok(max - min)
+}
+
+/// Stochastic oscillator `(x - min) / (max - min)` over the last `window` valid values
+///
+/// In `[0, 1]`: 1 at a new high, 0 at a new low; Williams %R is `stochastic - 1`. NaN during
+/// the warm-up, at NaN/inf inputs and where the range is zero (see `is_near_zero`).
+pub fn stochastic(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let (min, max) = rolling_minmax(data, window)?;
+    Ok(Array1::from_iter(data.iter().zip(min.iter().zip(max.iter())).map(|(&x, (&lo, &hi))| {
+        let range = hi - lo;
+        if !range.is_finite() || is_near_zero(range, x) { f64::NAN } else { (x - lo) / range }
+    })))
+}
+
+/// Rolling mean and population std behind the Bollinger factors, after checking `k`
+fn bollinger_moments(data: &Array1<f64>, window: usize, k: f64) -> Result<(Array1<f64>, Array1<f64>)> {
+    if !k.is_finite() || k <= 0.0 {
+        return Err(ExprError::InvalidParameter(format!("k must be positive and finite, got {}", k)));
+    }
+    Ok((rolling_mean(data, window)?, rolling_std(data, window, 0)?))
+}
+
+/// Bollinger %B: where `x` sits between the `mean -/+ k * std` bands, `(x - lower) / (2 * k * std)`
+///
+/// 0 on the lower band, 1 on the upper band and 0.5 on the mean; values outside `[0, 1]` are
+/// outside the bands. Mean and std are `rolling_mean` and the population `rolling_std` over
+/// the last `window` valid values, so NaN/inf inputs give NaN and are skipped. NaN during the
+/// warm-up and where the std is zero (see `is_near_zero`).
+pub fn bollinger_percent_b(data: &Array1<f64>, window: usize, k: f64) -> Result<Array1<f64>> {
+    let (mean, std) = bollinger_moments(data, window, k)?;
+    Ok(Array1::from_iter(data.iter().zip(mean.iter().zip(std.iter())).map(|(&x, (&m, &s))| {
+        if s.is_nan() || is_near_zero(s, m) { f64::NAN } else { (x - (m - k * s)) / (2.0 * k * s) }
+    })))
+}
+
+/// Bollinger bandwidth: the band width relative to the mean, `2 * k * std / mean`
+///
+/// Windows and NaN handling follow `bollinger_percent_b`; NaN where the mean is zero.
+pub fn bollinger_bandwidth(data: &Array1<f64>, window: usize, k: f64) -> Result<Array1<f64>> {
+    let (mean, std) = bollinger_moments(data, window, k)?;
+    Ok(Array1::from_iter(mean.iter().zip(std.iter()).map(|(&m, &s)| {
+        if m.is_nan() || is_near_zero(m, s) { f64::NAN } else { 2.0 * k * s / m }
+    })))
+}
+
+/// Rolling minimum over the last `window` valid values
+pub fn rolling_min(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    rolling_extreme(data, window, false)
+}
+
+/// Rolling maximum over the last `window` valid values
+pub fn rolling_max(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    rolling_extreme(data, window, true)
+}
+
+/// Rolling maximum over the last `window` valid values and how many bars ago it occurred
+pub fn rolling_max_with_pos(data: &Array1<f64>, window: usize) -> Result<(Array1<f64>, Array1<f64>)> {
+    rolling_extreme_with_pos(data, window, true)
+}
+
+/// Rolling maximum (`is_max`) or minimum over the last `window` valid values and its age in bars
+///
+/// The window is the one of `rolling_max`/`rolling_min`, so the extreme output equals
+/// theirs: NaN/inf inputs are skipped and give NaN, and both outputs are NaN until `window`
+/// valid values have been seen. The age counts positional bars back to the extreme,
+/// skipped ones included. One pass with a monotonic deque of `(valid_ordinal, index)`
+/// pairs, so O(n) overall; on ties the most recent occurrence wins.
+pub fn rolling_extreme_with_pos(data: &Array1<f64>, window: usize, is_max: bool) -> Result<(Array1<f64>, Array1<f64>)> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let n = data.len();
+    let mut extreme = Array1::from_elem(n, f64::NAN);
+    let mut bars_since = Array1::from_elem(n, f64::NAN);
+    let mut deque: VecDeque<(usize, usize)> = VecDeque::with_capacity(window);
+    let mut count = 0usize;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            continue;
+        }
+        // Pop ties too, so the newer equal value supersedes the older one
+        while deque.back().is_some_and(|&(_, j)| if is_max { data[j] <= val } else { data[j] >= val }) {
+            deque.pop_back();
+        }
+        deque.push_back((count, i));
+        if deque.front().is_some_and(|&(k, _)| k + window <= count) {
+            deque.pop_front();
+        }
+        count += 1;
+
+        if count >= window {
+            let j = deque[0].1;
+            extreme[i] = data[j];
+            bars_since[i] = (i - j) as f64;
+        }
+    }
+
+    Ok((extreme, bars_since))
+}
+
+/// Replace values whose robust z-score exceeds `threshold` with the rolling median
+///
+/// The robust z-score is `(x - median) / (1.4826 * MAD)`, the MAD scaled to be consistent
+/// with a normal std. Positions without a rolling median (warm-up, non-finite input) or
+/// with a zero MAD are left unchanged. Returns the cleaned values and a replacement mask.
+pub fn rolling_robust_clean(data: &Array1<f64>, window: usize, threshold: f64) -> Result<(Array1<f64>, Array1<bool>)> {
+    if threshold.is_nan() || threshold <= 0.0 {
+        return Err(ExprError::InvalidParameter(format!("threshold must be positive, got {}", threshold)));
+    }
+
+    let (median, mad) = rolling_median_mad(data, window)?;
+    let mut cleaned = data.clone();
+    let mut replaced = Array1::from_elem(data.len(), false);
+
+    for i in 0..data.len() {
+        let scale = 1.4826 * mad[i];
+        if scale > 0.0 && ((data[i] - median[i]) / scale).abs() > threshold {
+            cleaned[i] = median[i];
+            replaced[i] = true;
+        }
+    }
+
+    Ok((cleaned, replaced))
+}
+
+/// Hampel filter: replace points more than `n_sigmas * 1.4826 * MAD` from the median of
+/// the window centred on them with that median
+///
+/// Unlike `rolling_robust_clean`, which compares each point with its trailing window, the
+/// window here is the `window` bars centred on the point, `window / 2` before it and
+/// `(window - 1) / 2` after (the alignment of `center_windows`), so the filter looks ahead
+/// and is for cleaning stored data, not for live signals. Median and MAD are taken over
+/// the finite values of that positional window, so NaN/inf bars thin it without shifting
+/// it. A zero MAD flags any point off the median, which is what removes a lone spike from
+/// an otherwise flat stretch. Points within half a window of either end, and NaN/inf
+/// inputs, are left unchanged. Returns the cleaned values and a replacement mask.
+pub fn hampel_filter(data: &Array1<f64>, window: usize, n_sigmas: f64) -> Result<(Array1<f64>, Array1<bool>)> {
+    if !n_sigmas.is_finite() || n_sigmas < 0.0 {
+        return Err(ExprError::InvalidParameter(format!("n_sigmas must be finite and non-negative, got {}", n_sigmas)));
+    }
+
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let n = data.len();
+    let (before, after) = (window / 2, (window - 1) / 2);
+    let mut cleaned = data.clone();
+    let mut replaced = Array1::from_elem(n, false);
+    let mut values = Vec::with_capacity(window);
+
+    for i in before..n.saturating_sub(after) {
+        if !data[i].is_finite() {
+            continue;
+        }
+        values.clear();
+        values.extend(data.slice(s![i - before..=i + after]).iter().copied().filter(|x| x.is_finite()));
+        let (median, mad) = median_and_mad(&mut values);
+        if (data[i] - median).abs() > n_sigmas * 1.4826 * mad {
+            cleaned[i] = median;
+            replaced[i] = true;
+        }
+    }
+
+    Ok((cleaned, replaced))
+}
+
+/// Rolling population central moments `(m2, m3, m4)` over the last `window` valid values
+///
+/// Maintained with `SlidingHigherMoments`, so large offsets do not cancel; a window of one
+/// repeated value is exactly zero. NaN/inf inputs are skipped like in `rolling_std` and
+/// produce NaN at their own index.
+fn rolling_central_moments(data: &Array1<f64>, window: usize) -> Result<(Array1<f64>, Array1<f64>, Array1<f64>)> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+
+    let n = data.len();
+    let mut m2 = Array1::from_elem(n, f64::NAN);
+    let mut m3 = Array1::from_elem(n, f64::NAN);
+    let mut m4 = Array1::from_elem(n, f64::NAN);
+    let mut queue = VecDeque::with_capacity(window);
+    let mut moments = SlidingHigherMoments::default();
+    let mut flat_run = 0;
+
+    for (i, &val) in data.iter().enumerate() {
+        if val.is_nan() || val.is_infinite() {
+            continue;
+        }
+
+        flat_run = if queue.back() == Some(&val) { flat_run + 1 } else { 1 };
+        queue.push_back(val);
+        moments.add(val);
+        if queue.len() > window {
+            moments.remove(queue.pop_front().unwrap());
+        }
+
+        if queue.len() == window {
+            if flat_run >= window {
+                (m2[i], m3[i], m4[i]) = (0.0, 0.0, 0.0);
+            } else {
+                let count = window as f64;
+                (m2[i], m3[i], m4[i]) = (moments.m2 / count, moments.m3 / count, moments.m4 / count);
+            }
+        }
+    }
+
+    Ok((m2, m3, m4))
+}
+
+/// Rolling Jarque-Bera statistic `n/6 * (S² + K²/4)`
+///
+/// `S` and `K` are the population skewness and excess kurtosis of each window, from the
+/// shift-safe moments of `rolling_central_moments`. Warm-up and zero-variance windows are NaN.
+pub fn rolling_jarque_bera(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let (m2, m3, m4) = rolling_central_moments(data, window)?;
+    let count = window as f64;
+
+    Ok(Array1::from_iter((0..data.len()).map(|i| {
+        if m2[i].is_nan() || m2[i] <= 0.0 {
+            return f64::NAN;
+        }
+        let skew = m3[i] / m2[i].powf(1.5);
+        let kurt = m4[i] / (m2[i] * m2[i]) - 3.0;
+        count / 6.0 * (skew * skew + kurt * kurt / 4.0)
+    })))
+}
+
+/// Rolling sample skewness (adjusted Fisher-Pearson `G1`, as in pandas) over the last `window` valid values
+///
+/// Built on the sliding moments of `rolling_central_moments`. Warm-up and zero-variance
+/// windows are NaN.
+pub fn rolling_skew(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 3 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 3 for skewness".into()));
+    }
+    let (m2, m3, _) = rolling_central_moments(data, window)?;
+    let n = window as f64;
+    let adjust = (n * (n - 1.0)).sqrt() / (n - 2.0);
+
+    Ok(Array1::from_iter(m2.iter().zip(m3.iter()).map(|(&m2, &m3)| {
+        if m2.is_nan() || m2 <= 0.0 { f64::NAN } else { adjust * m3 / m2.powf(1.5) }
+    })))
+}
+
+/// Rolling sample excess kurtosis (`G2`, as in pandas) over the last `window` valid values
+///
+/// Warm-up and zero-variance windows are NaN.
+pub fn rolling_kurt(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 4 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 4 for kurtosis".into()));
+    }
+    let (m2, _, m4) = rolling_central_moments(data, window)?;
+    let n = window as f64;
+
+    Ok(Array1::from_iter(m2.iter().zip(m4.iter()).map(|(&m2, &m4)| {
+        if m2.is_nan() || m2 <= 0.0 {
+            return f64::NAN;
+        }
+        let g2 = m4 / (m2 * m2) - 3.0;
+        ((n + 1.0) * g2 + 6.0) * (n - 1.0) / ((n - 2.0) * (n - 3.0))
+    })))
+}
+
+/// What `pct_change` returns when the previous value is a zero denominator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZeroPrev {
+    /// NaN, dropping the observation
+    Nan,
+    /// `+inf` or `-inf` by the sign of the current value, and 0.0 if it is zero too
+    Inf,
+    /// 0.0, treating the bar as a fresh start
+    Zero,
+}
+
+impl std::str::FromStr for ZeroPrev {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "nan" => Ok(ZeroPrev::Nan),
+            "inf" => Ok(ZeroPrev::Inf),
+            "zero" => Ok(ZeroPrev::Zero),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown zero_prev mode '{}', expected one of: nan, inf, zero", s
+            ))),
+        }
+    }
+}
+
+/// Calculate percentage change
+///
+/// A previous value that is subnormal or near zero relative to the current value (see
+/// `is_near_zero`) is treated as a zero denominator, since dividing by it only amplifies
+/// rounding noise; `zero_prev` picks the output for that case. A non-finite previous or
+/// current value always yields NaN.
+pub fn pct_change(data: &Array1<f64>, periods: usize, zero_prev: ZeroPrev) -> Result<Array1<f64>> {
+    if periods < 1 {
+        return Err(ExprError::InvalidPeriod("Period must be positive".into()));
+    }
+
+    let n = data.len();
+    let mut result = Array1::zeros(n);
+
+    for i in 0..n {
+        result[i] = if i < periods {
+            f64::NAN
+        } else {
+            let (prev, curr) = (data[i - periods], data[i]);
+            if !prev.is_finite() || !curr.is_finite() {
+                f64::NAN
+            } else if !prev.is_normal() || is_near_zero(prev, curr) {
+                match zero_prev {
+                    ZeroPrev::Nan => f64::NAN,
+                    ZeroPrev::Inf if curr.is_normal() => f64::INFINITY.copysign(curr),
+                    ZeroPrev::Inf | ZeroPrev::Zero => 0.0,
+                }
+            } else {
+                (curr - prev) / prev
+            }
+        };
+    }
+
+    Ok(result)
+}
+
+/// Value `periods` bars ago; the first `periods` outputs and NaN/inf inputs are NaN
+pub fn delay(data: &Array1<f64>, periods: usize) -> Result<Array1<f64>> {
+    if periods < 1 {
+        return Err(ExprError::InvalidPeriod("Period must be positive".into()));
+    }
+    Ok(shift(&data.mapv(|v| if v.is_finite() { v } else { f64::NAN }), periods))
+}
+
+/// Difference `data[i] - data[i - periods]`, NaN unless both values are finite
+pub fn delta(data: &Array1<f64>, periods: usize) -> Result<Array1<f64>> {
+    let prev = delay(data, periods)?;
+    Ok(Array1::from_iter(data.iter().zip(prev.iter()).map(|(&curr, &prev)| {
+        if curr.is_finite() { curr - prev } else { f64::NAN }
+    })))
+}
+
+/// Running fold of `step` over the finite values seen so far
+///
+/// Skip-and-carry: a NaN/inf input gives NaN at that index but leaves the running value
+/// untouched, so the next finite input continues from it. Outputs before the first finite
+/// input are NaN.
+fn cumulative(data: &Array1<f64>, step: impl Fn(f64, f64) -> f64) -> Array1<f64> {
+    let mut acc: Option<f64> = None;
+    data.mapv(|x| {
+        if !x.is_finite() {
+            return f64::NAN;
+        }
+        let next = acc.map_or(x, |a| step(a, x));
+        acc = Some(next);
+        next
+    })
+}
+
+/// Running sum, skipping NaN/inf inputs (see `cumulative`)
+pub fn cumsum(data: &Array1<f64>) -> Array1<f64> {
+    cumulative(data, |a, x| a + x)
+}
+
+/// Running product, skipping NaN/inf inputs (see `cumulative`)
+pub fn cumprod(data: &Array1<f64>) -> Array1<f64> {
+    cumulative(data, |a, x| a * x)
+}
+
+/// Running maximum, skipping NaN/inf inputs (see `cumulative`)
+pub fn cummax(data: &Array1<f64>) -> Array1<f64> {
+    cumulative(data, f64::max)
+}
+
+/// Running minimum, skipping NaN/inf inputs (see `cumulative`)
+pub fn cummin(data: &Array1<f64>) -> Array1<f64> {
+    cumulative(data, f64::min)
+}
+
+/// Fraction of all finite values so far, the current one included, that are <= the current value
+///
+/// The expanding counterpart of `rolling_rank` (ties ranked high), comparing each value
+/// with the whole history rather than a trailing window. NaN/inf inputs give NaN and are
+/// left out of later ranks. History is kept sorted, so each step is a binary search plus an
+/// insert.
+pub fn expanding_rank(data: &Array1<f64>) -> Array1<f64> {
+    let mut sorted: Vec<f64> = Vec::with_capacity(data.len());
+    data.mapv(|x| {
+        if !x.is_finite() {
+            return f64::NAN;
+        }
+        let at = sorted.partition_point(|&v| v <= x);
+        sorted.insert(at, x);
+        (at + 1) as f64 / sorted.len() as f64
+    })
+}
+
+/// Mean of all finite values so far; NaN/inf inputs give NaN (see `cumulative`)
+pub fn expanding_mean(data: &Array1<f64>) -> Array1<f64> {
+    let (mut sum, mut count) = (0.0, 0usize);
+    data.mapv(|x| {
+        if !x.is_finite() {
+            return f64::NAN;
+        }
+        sum += x;
+        count += 1;
+        sum / count as f64
+    })
+}
+
+/// Std of all finite values so far, divided by `count - ddof`
+///
+/// Welford's update, so the running moments never lose precision the way
+/// `sum(x^2) - n * mean^2` would on a long history. NaN at NaN/inf inputs and while
+/// `count <= ddof`.
+pub fn expanding_std(data: &Array1<f64>, ddof: usize) -> Array1<f64> {
+    let (mut mean, mut m2, mut count) = (0.0, 0.0, 0usize);
+    data.mapv(|x| {
+        if !x.is_finite() {
+            return f64::NAN;
+        }
+        count += 1;
+        let delta = x - mean;
+        mean += delta / count as f64;
+        m2 += delta * (x - mean);
+        if count > ddof { (m2.max(0.0) / (count - ddof) as f64).sqrt() } else { f64::NAN }
+    })
+}
+
+/// How ties with the current value are ranked, as in scipy's `rankdata`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RankMethod {
+    /// Mean of the tied positions
+    Average,
+    /// Lowest tied position, `count(x < current) + 1`
+    Min,
+    /// Highest tied position, `count(x <= current)`; the original convention and the default
+    Max,
+    /// Tied values ranked in order of appearance. The current bar of a rolling window is
+    /// the latest of its ties, so there this equals `Max`
+    First,
+}
+
+impl RankMethod {
+    /// Rank from the mass strictly below the current value, the mass equal to it and the
+    /// current observation's own mass (1, or its weight)
+    fn rank(self, below: f64, equal: f64, own: f64) -> f64 {
+        match self {
+            RankMethod::Average => below + (equal + own) / 2.0,
+            RankMethod::Min => below + own,
+            RankMethod::Max | RankMethod::First => below + equal,
+        }
+    }
+}
+
+impl std::str::FromStr for RankMethod {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "average" => Ok(RankMethod::Average),
+            "min" => Ok(RankMethod::Min),
+            "max" => Ok(RankMethod::Max),
+            "first" => Ok(RankMethod::First),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown rank method '{}', expected one of: average, min, max, first",
+                s
+            ))),
+        }
+    }
+}
+
+/// Percentile of `current` among the finite values of a window
+///
+/// The rank of `current` follows `method` and is divided by the number of finite values.
+/// Shared by the batch and streaming rank so both produce bit-identical results.
+fn window_percentile<'a>(window: impl Iterator<Item = &'a f64>, current: f64, method: RankMethod) -> f64 {
+    if current.is_nan() || current.is_infinite() {
+        return f64::NAN;
+    }
+
+    let mut valid = 0usize;
+    let (mut below, mut equal) = (0usize, 0usize);
+    for &x in window.filter(|&&x| !x.is_nan() && !x.is_infinite()) {
+        valid += 1;
+        if x < current {
+            below += 1;
+        } else if x == current {
+            equal += 1;
+        }
+    }
+
+    if valid == 0 {
+        f64::NAN
+    } else {
+        method.rank(below as f64, equal as f64, 1.0) / valid as f64
+    }
+}
+
+/// Calculate log returns `ln(x[t] / x[t - periods])`
+///
+/// NaN in the first `periods` slots and wherever either price is non-positive or non-finite.
+pub fn log_return(data: &Array1<f64>, periods: usize) -> Result<Array1<f64>> {
+    if periods < 1 {
+        return Err(ExprError::InvalidPeriod("Period must be positive".into()));
+    }
+
+    Ok(Array1::from_iter((0..data.len()).map(|i| {
+        if i < periods {
+            return f64::NAN;
+        }
+        let (prev, curr) = (data[i - periods], data[i]);
+        if prev.is_finite() && curr.is_finite() && prev > 0.0 && curr > 0.0 {
+            (curr / prev).ln()
+        } else {
+            f64::NAN
+        }
+    })))
+}
+
+/// Weighted fraction of the finite window values at or below `current`
+///
+/// `weights` aligns with the window (oldest first), so the current value, which ends the
+/// window, carries the last weight. NaN/inf observations get zero weight and the rest are
+/// normalized by their total, so equal weights give `window_percentile`.
+fn weighted_window_percentile<'a>(
+    window: impl Iterator<Item = &'a f64>,
+    weights: &ArrayView1<f64>,
+    current: f64,
+    method: RankMethod,
+) -> f64 {
+    if !current.is_finite() {
+        return f64::NAN;
+    }
+
+    let mut total = 0.0;
+    let (mut below, mut equal) = (0.0, 0.0);
+    for (&x, &w) in window.zip(weights.iter()).filter(|(x, _)| x.is_finite()) {
+        total += w;
+        if x < current {
+            below += w;
+        } else if x == current {
+            equal += w;
+        }
+    }
+    let own = weights[weights.len() - 1];
+
+    if total > 0.0 { method.rank(below, equal, own) / total } else { f64::NAN }
+}
+
+/// Calculate rolling rank (percentile)
+///
+/// With `weights` (length `window`, oldest observation first) the rank is the weighted
+/// fraction of observations at or below the current value, e.g. to let recent history
+/// count more; `None` weights every observation equally.
+pub fn rolling_rank(data: &Array1<f64>, window: usize, weights: Option<&Array1<f64>>) -> Result<Array1<f64>> {
+    rolling_rank_partial(data, window, window, weights)
+}
+
+/// Rolling rank emitted once `min_periods` elements are buffered
+///
+/// The window is positional, as in `rolling_rank`, so `min_periods` counts buffered
+/// elements; NaN/inf ones are then dropped from the rank. A partial window of length `k`
+/// uses the last `k` weights.
+pub fn rolling_rank_partial(data: &Array1<f64>, window: usize, min_periods: usize, weights: Option<&Array1<f64>>) -> Result<Array1<f64>> {
+    rolling_rank_with(data, window, min_periods, weights, RankMethod::Max)
+}
+
+/// `rolling_rank_partial` with an explicit tie-breaking method
+pub fn rolling_rank_with(
+    data: &Array1<f64>,
+    window: usize,
+    min_periods: usize,
+    weights: Option<&Array1<f64>>,
+    method: RankMethod,
+) -> Result<Array1<f64>> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    check_min_periods(window, min_periods)?;
+    if let Some(w) = weights {
+        if w.len() != window {
+            return Err(ExprError::LengthMismatch(format!(
+                "weights has length {}, expected the window size {}", w.len(), window
+            )));
+        }
+        if w.iter().any(|&v| !v.is_finite() || v < 0.0) || w.sum() <= 0.0 {
+            return Err(ExprError::InvalidParameter("weights must be finite, non-negative and not all zero".into()));
+        }
+    }
+
+    let n = data.len();
+    let mut result = Array1::zeros(n);
+
+    for i in 0..n {
+        result[i] = if i + 1 < min_periods {
+            f64::NAN
+        } else {
+            let start = i.saturating_sub(window - 1);
+            let values = data.slice(s![start..=i]);
+            match weights {
+                Some(w) => weighted_window_percentile(values.iter(), &w.slice(s![window - values.len()..]), data[i], method),
+                None => window_percentile(values.iter(), data[i], method),
+            }
+        };
+    }
+
+    Ok(result)
+}
+
+/// Streaming counterpart of `rolling_rank`
+///
+/// After the same sequence of values, `push` returns exactly the value `rolling_rank`
+/// produces at the last index, including tie handling and NaN rules.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct RollingRank {
+    window: usize,
+    buffer: VecDeque<f64>,
+}
+
+impl RollingRank {
+    pub fn with_window(window: usize) -> Result<Self> {
+        if window < 2 {
+            return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+        }
+        Ok(RollingRank { window, buffer: VecDeque::with_capacity(window) })
+    }
+
+    pub fn push(&mut self, value: f64) -> f64 {
+        if self.buffer.len() == self.window {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(value);
+
+        if self.buffer.len() < self.window {
+            f64::NAN
+        } else {
+            window_percentile(self.buffer.iter(), value, RankMethod::Max)
+        }
+    }
+}
+
+/// Constant-memory streaming quantile estimate using the P² algorithm (Jain & Chlamtac, 1985)
+///
+/// Five markers track the minimum, the `q/2`, `q` and `(1+q)/2` quantiles and the maximum,
+/// and are nudged towards their ideal positions with piecewise-parabolic interpolation, so
+/// no history is stored. Until five values have arrived the exact (interpolated) quantile
+/// is returned. The estimate is approximate: it is typically within a few tenths of a
+/// percentile for smooth, unimodal distributions once a few hundred values have been seen,
+/// but can lag after abrupt distribution shifts and is coarser for extreme `q` or heavily
+/// discrete data. NaN/inf updates are ignored.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct StreamingQuantile {
+    q: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl StreamingQuantile {
+    pub fn with_quantile(q: f64) -> Result<Self> {
+        if !(q > 0.0 && q < 1.0) {
+            return Err(ExprError::InvalidParameter(format!("quantile must be in (0, 1), got {}", q)));
+        }
+        Ok(StreamingQuantile {
+            q,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * q, 1.0 + 4.0 * q, 3.0 + 2.0 * q, 5.0],
+            increments: [0.0, q / 2.0, q, (1.0 + q) / 2.0, 1.0],
+        })
+    }
+
+    pub fn push(&mut self, value: f64) -> f64 {
+        if !value.is_finite() {
+            return self.current();
+        }
+
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.total_cmp(b));
+            }
+            return self.current();
+        }
+        self.count += 1;
+
+        // Locate the cell containing `value`, extending the extremes if needed
+        let h = &mut self.heights;
+        let k = if value < h[0] {
+            h[0] = value;
+            0
+        } else if value >= h[4] {
+            h[4] = value;
+            3
+        } else {
+            (0..4).find(|&i| value < h[i + 1]).unwrap()
+        };
+
+        for pos in self.positions.iter_mut().skip(k + 1) {
+            *pos += 1.0;
+        }
+        for (d, inc) in self.desired.iter_mut().zip(self.increments.iter()) {
+            *d += inc;
+        }
+
+        for i in 1..4 {
+            let (n, h) = (&mut self.positions, &mut self.heights);
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = h[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (h[i + 1] - h[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (h[i] - h[i - 1]) / (n[i] - n[i - 1]));
+                h[i] = if h[i - 1] < parabolic && parabolic < h[i + 1] {
+                    parabolic
+                } else {
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    h[i] + d * (h[j] - h[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+
+        self.current()
+    }
+
+    pub fn current(&self) -> f64 {
+        match self.count {
+            0 => f64::NAN,
+            1..=4 => {
+                let mut sorted = self.heights[..self.count].to_vec();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                sorted_quantile(&sorted, self.q)
+            }
+            _ => self.heights[2],
+        }
+    }
+}
+
+/// Calculate rolling correlation
+///
+/// Same edge-case rules as `rolling_std`: `-0.0` is ordinary zero, and windows whose
+/// squared deviations underflow are degenerate and yield NaN. The moments are population
+/// (ddof = 0) moments, but the ddof cancels in the ratio, so the result matches
+/// `numpy.corrcoef` and pandas whatever their ddof.
+pub fn rolling_correlation(x: &Array1<f64>, y: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    rolling_correlation_with(x, y, window, precision_mode())
+}
+
+/// Rolling autocorrelation: `rolling_correlation` of `x[i]` with `x[i - lag]`
+///
+/// The first `window + lag - 1` outputs are NaN; pairs with a NaN/inf side are skipped as
+/// in `rolling_correlation`. Requires `1 <= lag < window`.
+pub fn rolling_autocorr(data: &Array1<f64>, window: usize, lag: usize) -> Result<Array1<f64>> {
+    if lag < 1 || lag >= window {
+        return Err(ExprError::InvalidPeriod(format!(
+            "lag {} must be at least 1 and below the window size {}", lag, window
+        )));
+    }
+    rolling_correlation(data, &delay(data, lag)?, window)
+}
+
+/// Rolling OLS fit of each window's values on their bar index: `(slope, r2)`
+///
+/// The regressor is the position `i` of each bar, so the slope is the change per bar.
+/// Its moments come from the same sliding Welford update as `rolling_correlation`. NaN/inf
+/// bars give NaN at their own index and are skipped, so a window holds the last `window`
+/// finite values at their true spacing. A flat window, detected by the run of repeated
+/// values in `SlidingCoMoments` rather than by the size of `m2_y`, has slope exactly 0 and
+/// an undefined (NaN) R².
+fn rolling_trend(data: &Array1<f64>, window: usize) -> Result<(Array1<f64>, Array1<f64>)> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+
+    let n = data.len();
+    let mut slope = Array1::from_elem(n, f64::NAN);
+    let mut r2 = Array1::from_elem(n, f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let mut moments = SlidingCoMoments::default();
+
+    for (i, &y) in data.iter().enumerate() {
+        if !y.is_finite() {
+            continue;
+        }
+        queue.push_back((i as f64, y));
+        moments.add(i as f64, y);
+        if moments.count > window {
+            let (t, old) = queue.pop_front().unwrap();
+            moments.remove(t, old);
+        }
+        if moments.count < window {
+            continue;
+        }
+
+        slope[i] = moments.c_xy / moments.m2_x;
+        if moments.m2_y > 0.0 {
+            r2[i] = (moments.c_xy * moments.c_xy / (moments.m2_x * moments.m2_y)).min(1.0);
+        } else {
+            slope[i] = 0.0;
+        }
+    }
+
+    Ok((slope, r2))
+}
+
+/// Rolling slope per bar of a least-squares line through the last `window` valid values
+pub fn rolling_slope(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    Ok(rolling_trend(data, window)?.0)
+}
+
+/// Rolling R² of the `rolling_slope` fit; NaN for a flat window
+pub fn rolling_r2(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    Ok(rolling_trend(data, window)?.1)
+}
+
+/// `rolling_correlation` with an explicit accumulator choice
+pub fn rolling_correlation_with(x: &Array1<f64>, y: &Array1<f64>, window: usize, mode: PrecisionMode) -> Result<Array1<f64>> {
+    rolling_correlation_partial_with(x, y, window, window, mode)
+}
+
+/// Rolling correlation emitted once `min_periods` jointly finite pairs are in the window
+///
+/// Like `rolling_std_partial`, `min_periods` counts valid pairs; skipped NaN/inf pairs do
+/// not occupy the window.
+pub fn rolling_correlation_partial_with(
+    x: &Array1<f64>,
+    y: &Array1<f64>,
+    window: usize,
+    min_periods: usize,
+    mode: PrecisionMode,
+) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    check_min_periods(window, min_periods)?;
+
+    let n = x.len();
+    let mut result = Array1::zeros(n);
+    let mut queue_x = VecDeque::with_capacity(window);
+    let mut queue_y = VecDeque::with_capacity(window);
+    let mut moments = SlidingCoMoments::default();
+
+    for i in 0..n {
+        if x[i].is_nan() || x[i].is_infinite() || y[i].is_nan() || y[i].is_infinite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        queue_x.push_back(x[i]);
+        queue_y.push_back(y[i]);
+        moments.add(x[i], y[i]);
+        if moments.count > window {
+            moments.remove(queue_x.pop_front().unwrap(), queue_y.pop_front().unwrap());
+        }
+
+        let count = moments.count;
+        result[i] = if count < min_periods {
+            f64::NAN
+        } else {
+            let (cov, var_x, var_y) = match mode {
+                PrecisionMode::Fast => (
+                    moments.c_xy / count as f64,
+                    moments.m2_x / count as f64,
+                    moments.m2_y / count as f64,
+                ),
+                PrecisionMode::Accurate => {
+                    let mean_x = queue_x.iter().sum::<f64>() / count as f64;
+                    let mean_y = queue_y.iter().sum::<f64>() / count as f64;
+                    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+                    for (a, b) in queue_x.iter().zip(queue_y.iter()) {
+                        let (dx, dy) = (a - mean_x, b - mean_y);
+                        cov += dx * dy;
+                        var_x += dx * dx;
+                        var_y += dy * dy;
+                    }
+                    (cov / count as f64, var_x / count as f64, var_y / count as f64)
+                }
+            };
+
+            if var_x <= 0.0 || var_y <= 0.0 {
+                f64::NAN
+            } else {
+                cov / (var_x.sqrt() * var_y.sqrt())
+            }
+        };
+    }
+
+    Ok(result)
+}
+
+/// Rolling Spearman rank correlation over the last `window` jointly finite pairs
+///
+/// Within each window `x` and `y` are ranked separately with average ranks for ties, then
+/// Pearson-correlated. Pairs are filtered as in `rolling_correlation`: a NaN/inf in either
+/// series gives NaN there and is skipped. Warm-up windows and windows where either series
+/// is constant are NaN.
+pub fn rolling_spearman(x: &Array1<f64>, y: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+
+    let ones = vec![1.0; window];
+    let mut result = Array1::zeros(x.len());
+    let mut queue_x = VecDeque::with_capacity(window);
+    let mut queue_y = VecDeque::with_capacity(window);
+
+    for i in 0..x.len() {
+        if !x[i].is_finite() || !y[i].is_finite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        queue_x.push_back(x[i]);
+        queue_y.push_back(y[i]);
+        if queue_x.len() > window {
+            queue_x.pop_front();
+            queue_y.pop_front();
+        }
+
+        result[i] = if queue_x.len() < window {
+            f64::NAN
+        } else {
+            let rank_x = weighted_ranks(queue_x.make_contiguous(), &ones);
+            let rank_y = weighted_ranks(queue_y.make_contiguous(), &ones);
+            weighted_pearson(&rank_x, &rank_y, &ones)
+        };
+    }
+
+    Ok(result)
+}
+
+/// Rolling covariance over the last `window` jointly finite pairs, divided by `count - ddof`
+///
+/// `ddof = 1` reproduces both `numpy.cov` (whose default is `ddof=1`) and pandas
+/// `rolling().cov()`; `ddof = 0` gives the population covariance used inside
+/// `rolling_correlation` and `rolling_beta`. Windows with `count <= ddof` are NaN.
+pub fn rolling_cov(x: &Array1<f64>, y: &Array1<f64>, window: usize, ddof: usize, mode: PrecisionMode) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    if ddof >= window {
+        return Err(ExprError::InvalidParameter(format!("ddof {} must be smaller than the window size {}", ddof, window)));
+    }
+
+    let mut result = Array1::zeros(x.len());
+    let mut queue = VecDeque::with_capacity(window);
+    let mut moments = SlidingCoMoments::default();
+
+    for i in 0..x.len() {
+        let (a, b) = (x[i], y[i]);
+        if !a.is_finite() || !b.is_finite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        queue.push_back((a, b));
+        moments.add(a, b);
+        if queue.len() > window {
+            let (old_a, old_b) = queue.pop_front().unwrap();
+            moments.remove(old_a, old_b);
+        }
+
+        let count = queue.len();
+        result[i] = if count < window {
+            f64::NAN
+        } else {
+            let comoment = match mode {
+                PrecisionMode::Fast => moments.c_xy,
+                PrecisionMode::Accurate => {
+                    let mean_x = queue.iter().map(|(a, _)| a).sum::<f64>() / count as f64;
+                    let mean_y = queue.iter().map(|(_, b)| b).sum::<f64>() / count as f64;
+                    queue.iter().map(|(a, b)| (a - mean_x) * (b - mean_y)).sum()
+                }
+            };
+            comoment / (count - ddof) as f64
+        };
+    }
+
+    Ok(result)
+}
+
+/// Streaming correlation matrix over the last `window` jointly finite ticks of `n_assets` series
+///
+/// Every pair keeps its own queue of ticks where both assets are finite and a
+/// `SlidingCoMoments` over them, so a NaN in one asset is skipped rather than occupying
+/// the window, exactly as in `rolling_correlation`: a pair is NaN until `window` valid
+/// pairs have arrived and when either side is constant. Only the upper triangle is stored,
+/// `n_assets * (n_assets + 1) / 2` pairs, and each update costs O(n_assets²).
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub struct StreamingCorrMatrix {
+    n_assets: usize,
+    window: usize,
+    // Upper triangle `i <= j` only, see `pair_index`
+    pairs: Vec<VecDeque<(f64, f64)>>,
+    moments: Vec<SlidingCoMoments>,
+}
+
+impl StreamingCorrMatrix {
+    pub fn with_shape(n_assets: usize, window: usize) -> Result<Self> {
+        if window < 2 {
+            return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+        }
+        if n_assets < 1 {
+            return Err(ExprError::InvalidParameter("n_assets must be positive".into()));
+        }
+        Ok(StreamingCorrMatrix {
+            n_assets,
+            window,
+            pairs: vec![VecDeque::new(); n_assets * (n_assets + 1) / 2],
+            moments: vec![SlidingCoMoments::default(); n_assets * (n_assets + 1) / 2],
+        })
+    }
+
+    /// Position of pair `(i, j)`, `i <= j`, in the row-major upper triangle
+    fn pair_index(&self, i: usize, j: usize) -> usize {
+        i * self.n_assets - i * (i + 1) / 2 + j
+    }
+
+    pub fn push(&mut self, tick: Vec<f64>) -> Result<Array2<f64>> {
+        if tick.len() != self.n_assets {
+            return Err(ExprError::LengthMismatch(format!(
+                "values has length {} but the matrix tracks {} assets", tick.len(), self.n_assets
+            )));
+        }
+
+        let n = self.n_assets;
+        for i in 0..n {
+            if !tick[i].is_finite() {
+                continue;
+            }
+            for j in i..n {
+                if !tick[j].is_finite() {
+                    continue;
+                }
+                let k = self.pair_index(i, j);
+                let (queue, moments) = (&mut self.pairs[k], &mut self.moments[k]);
+                queue.push_back((tick[i], tick[j]));
+                moments.add(tick[i], tick[j]);
+                if queue.len() > self.window {
+                    let (old_x, old_y) = queue.pop_front().unwrap();
+                    moments.remove(old_x, old_y);
+                }
+            }
+        }
+
+        Ok(self.matrix())
+    }
+
+    pub fn matrix(&self) -> Array2<f64> {
+        let n = self.n_assets;
+        let mut result = Array2::from_elem((n, n), f64::NAN);
+        for i in 0..n {
+            for j in i..n {
+                let moments = &self.moments[self.pair_index(i, j)];
+                if moments.count < self.window || moments.m2_x <= 0.0 || moments.m2_y <= 0.0 {
+                    continue;
+                }
+                let count = moments.count as f64;
+                let (cov, var_x, var_y) = (moments.c_xy / count, moments.m2_x / count, moments.m2_y / count);
+                let corr = if i == j { 1.0 } else { cov / (var_x.sqrt() * var_y.sqrt()) };
+                result[[i, j]] = corr;
+                result[[j, i]] = corr;
+            }
+        }
+        result
+    }
+
+    /// Forget every tick, keeping the shape
+    pub fn clear(&mut self) {
+        self.pairs.iter_mut().for_each(VecDeque::clear);
+        self.moments.fill(SlidingCoMoments::default());
+    }
+}
+
+/// Calculate rolling OLS slope of `y` on `x`
+///
+/// With an intercept the slope is `cov(x, y) / var(x)` from `SlidingCoMoments`, so price
+/// levels do not cancel. With `fit_intercept` false the regression goes through the origin
+/// and the slope is `sum_xy / sum_xx` over the window, NaN when `sum_xx` is zero.
+pub fn rolling_beta(x: &Array1<f64>, y: &Array1<f64>, window: usize, fit_intercept: bool) -> Result<Array1<f64>> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    check_same_length("x", x, "y", y)?;
+
+    let n = x.len();
+    let mut result = Array1::zeros(n);
+    let mut queue_x = VecDeque::with_capacity(window);
+    let mut queue_y = VecDeque::with_capacity(window);
+    let mut moments = SlidingCoMoments::default();
+    let mut sum_xy = 0.0;
+    let mut sum_xx = 0.0;
+
+    for i in 0..n {
+        if !x[i].is_finite() || !y[i].is_finite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        queue_x.push_back(x[i]);
+        queue_y.push_back(y[i]);
+        if fit_intercept {
+            moments.add(x[i], y[i]);
+        } else {
+            sum_xy += x[i] * y[i];
+            sum_xx += x[i] * x[i];
+        }
+
+        if queue_x.len() > window {
+            let old_x = queue_x.pop_front().unwrap();
+            let old_y = queue_y.pop_front().unwrap();
+            if fit_intercept {
+                moments.remove(old_x, old_y);
+            } else {
+                sum_xy -= old_x * old_y;
+                sum_xx -= old_x * old_x;
+            }
+        }
+
+        result[i] = if queue_x.len() < window {
+            f64::NAN
+        } else if !fit_intercept {
+            if sum_xx <= 0.0 { f64::NAN } else { sum_xy / sum_xx }
+        } else if moments.m2_x <= 0.0 {
+            f64::NAN
+        } else {
+            moments.c_xy / moments.m2_x
+        };
+    }
+
+    Ok(result)
+}
+
+/// Least-squares fit of `target` on the columns of `design`
+struct OlsFit {
+    coef: Vec<f64>,
+    std_err: Vec<f64>,
+    rss: f64,
+}
+
+/// Solve the normal equations by Gauss-Jordan elimination with partial pivoting
+///
+/// Returns `None` for rank-deficient designs (a pivot below `1e-12` times the largest
+/// diagonal of `X'X`) and when there are no residual degrees of freedom.
+fn ols_fit(design: &Array2<f64>, target: &Array1<f64>) -> Option<OlsFit> {
+    let (n, k) = design.dim();
+    if n <= k {
+        return None;
+    }
+
+    let xtx = design.t().dot(design);
+    let xty = design.t().dot(target);
+    let tol = 1e-12 * xtx.diag().iter().fold(0.0_f64, |acc, v| acc.max(v.abs()));
+
+    // Augment [X'X | I] and reduce to [I | (X'X)^-1]
+    let mut aug = Array2::zeros((k, 2 * k));
+    aug.slice_mut(s![.., ..k]).assign(&xtx);
+    for i in 0..k {
+        aug[[i, k + i]] = 1.0;
+    }
+    for col in 0..k {
+        let pivot_row = (col..k).max_by(|&a, &b| aug[[a, col]].abs().total_cmp(&aug[[b, col]].abs()))?;
+        if aug[[pivot_row, col]].abs() <= tol {
+            return None;
+        }
+        for j in 0..2 * k {
+            aug.swap([col, j], [pivot_row, j]);
+        }
+        let pivot = aug[[col, col]];
+        aug.row_mut(col).mapv_inplace(|v| v / pivot);
+        for row in 0..k {
+            if row != col {
+                let factor = aug[[row, col]];
+                if factor != 0.0 {
+                    let pivot_vals = aug.row(col).to_owned();
+                    aug.row_mut(row).scaled_add(-factor, &pivot_vals);
+                }
+            }
+        }
+    }
+
+    let inv = aug.slice(s![.., k..]).to_owned();
+    let coef = inv.dot(&xty);
+    let resid = target - &design.dot(&coef);
+    let rss = resid.dot(&resid);
+    let sigma2 = rss / (n - k) as f64;
+    let std_err = inv.diag().iter().map(|v| (sigma2 * v).sqrt()).collect();
+
+    Some(OlsFit { coef: coef.to_vec(), std_err, rss })
+}
+
+/// Deterministic terms in the ADF regression
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdfRegression {
+    /// No constant or trend (`"n"`)
+    None,
+    /// Constant (`"c"`)
+    Constant,
+    /// Constant and linear time trend (`"ct"`)
+    ConstantTrend,
+}
+
+impl AdfRegression {
+    fn n_terms(self) -> usize {
+        match self {
+            AdfRegression::None => 0,
+            AdfRegression::Constant => 1,
+            AdfRegression::ConstantTrend => 2,
+        }
+    }
+}
+
+impl std::str::FromStr for AdfRegression {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "n" => Ok(AdfRegression::None),
+            "c" => Ok(AdfRegression::Constant),
+            "ct" => Ok(AdfRegression::ConstantTrend),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown regression '{}', expected one of: n, c, ct",
+                s
+            ))),
+        }
+    }
+}
+
+/// Fit the ADF regression with `lags` lagged differences, using the differences from
+/// `start` onward as the target (`start >= lags`) so several lag orders can share a sample
+fn adf_fit(series: &[f64], lags: usize, start: usize, regression: AdfRegression) -> Option<OlsFit> {
+    let diff: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    if diff.len() <= start {
+        return None;
+    }
+
+    let n_obs = diff.len() - start;
+    let n_det = regression.n_terms();
+    let mut design = Array2::zeros((n_obs, n_det + 1 + lags));
+    let mut target = Array1::zeros(n_obs);
+    for r in 0..n_obs {
+        let t = r + start;
+        target[r] = diff[t];
+        if n_det > 0 {
+            design[[r, 0]] = 1.0;
+        }
+        if n_det > 1 {
+            design[[r, 1]] = (t + 1) as f64;
+        }
+        design[[r, n_det]] = series[t];
+        for i in 1..=lags {
+            design[[r, n_det + i]] = diff[t - i];
+        }
+    }
+
+    ols_fit(&design, &target)
+}
+
+/// ADF regression `Δy_t = [deterministic] + γ y_{t-1} + Σ_{i=1..lags} δ_i Δy_{t-i} + ε_t`
+///
+/// Returns `(t_stat, γ)` with `t_stat = γ / se(γ)`, or `None` when the regression is
+/// degenerate or has no residual degrees of freedom.
+fn adf_regression(series: &[f64], lags: usize, regression: AdfRegression) -> Option<(f64, f64)> {
+    let fit = adf_fit(series, lags, lags, regression)?;
+    let n_det = regression.n_terms();
+    let (gamma, se) = (fit.coef[n_det], fit.std_err[n_det]);
+    if se.is_finite() && se > 0.0 {
+        Some((gamma / se, gamma))
+    } else {
+        None
+    }
+}
+
+/// Full-sample Augmented Dickey-Fuller test returning `(t_stat, γ)`
+///
+/// The lag order is chosen from `0..=max_lag` by minimum AIC (`n ln(RSS / n) + 2k`), with
+/// every candidate fitted on the same sample that drops the first `max_lag` differences.
+/// The chosen order is then refitted on all available observations. Compare the statistic
+/// against Dickey-Fuller critical values for the chosen `regression`. The series must be
+/// entirely finite.
+pub fn adf_test(data: &Array1<f64>, max_lag: usize, regression: AdfRegression) -> Result<(f64, f64)> {
+    if data.iter().any(|v| !v.is_finite()) {
+        return Err(ExprError::InvalidParameter("adf_test requires a series without NaN/inf".into()));
+    }
+    let series = data.to_vec();
+
+    let mut best: Option<(f64, usize)> = None;
+    for lags in 0..=max_lag {
+        if let Some(fit) = adf_fit(&series, lags, max_lag, regression) {
+            let n_obs = (series.len() - 1 - max_lag) as f64;
+            let aic = n_obs * (fit.rss / n_obs).ln() + 2.0 * fit.coef.len() as f64;
+            if best.is_none_or(|(best_aic, _)| aic < best_aic) {
+                best = Some((aic, lags));
+            }
+        }
+    }
+
+    best.and_then(|(_, lags)| adf_regression(&series, lags, regression))
+        .ok_or_else(|| ExprError::ComputationError("ADF regression is degenerate or the series is too short for max_lag".into()))
+}
+
+/// Rolling Engle-Granger cointegration statistic of `y` on `x`
+///
+/// Over the last `window` bars where both legs are finite, `y` is regressed on `x` with an
+/// intercept and the residuals are given an ADF test with no deterministic terms and
+/// `lags` fixed augmentation lags (no lag selection). More negative values are stronger
+/// evidence of cointegration; compare against Engle-Granger rather than Dickey-Fuller
+/// critical values. Non-finite inputs give NaN at their index, as do warm-up and
+/// degenerate windows. Costs O(n * window * lags²).
+pub fn rolling_coint_stat(y: &Array1<f64>, x: &Array1<f64>, window: usize, lags: usize) -> Result<Array1<f64>> {
+    check_same_length("y", y, "x", x)?;
+    if window < lags + 4 {
+        return Err(ExprError::InvalidPeriod(format!(
+            "Window size must be at least lags + 4 = {}, got {}",
+            lags + 4,
+            window
+        )));
+    }
+
+    let n = y.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    let mut queue: VecDeque<(f64, f64)> = VecDeque::with_capacity(window + 1);
+    let mut resid = vec![0.0; window];
+
+    for i in 0..n {
+        if !y[i].is_finite() || !x[i].is_finite() {
+            continue;
+        }
+        queue.push_back((y[i], x[i]));
+        if queue.len() > window {
+            queue.pop_front();
+        }
+        if queue.len() < window {
+            continue;
+        }
+
+        let count = window as f64;
+        let mean_y = queue.iter().map(|p| p.0).sum::<f64>() / count;
+        let mean_x = queue.iter().map(|p| p.1).sum::<f64>() / count;
+        let cov = queue.iter().map(|p| (p.0 - mean_y) * (p.1 - mean_x)).sum::<f64>();
+        let var_x = queue.iter().map(|p| (p.1 - mean_x) * (p.1 - mean_x)).sum::<f64>();
+        if var_x <= 0.0 {
+            continue;
+        }
+        let beta = cov / var_x;
+        for (r, &(yv, xv)) in resid.iter_mut().zip(queue.iter()) {
+            *r = (yv - mean_y) - beta * (xv - mean_x);
+        }
+
+        if let Some((stat, _)) = adf_regression(&resid, lags, AdfRegression::None) {
+            result[i] = stat;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reject a negative or non-finite z-score `std_floor`
+fn check_std_floor(std_floor: f64) -> Result<()> {
+    if !std_floor.is_finite() || std_floor < 0.0 {
+        return Err(ExprError::InvalidParameter("std_floor must be finite and non-negative".into()));
+    }
+    Ok(())
+}
+
+/// Z-score denominator with an optional floor
+///
+/// A std that is near zero relative to the window mean `scale` (see `is_near_zero`) counts
+/// as zero. With `std_floor = 0.0` a zero or non-finite std gives NaN. A positive floor
+/// clamps the denominator to at least `std_floor`, and a NaN or zero std (a flat,
+/// zero-variance window) is replaced by the floor, so the caller must already have ruled
+/// out an invalid window.
+fn floored_std(std: f64, scale: f64, std_floor: f64) -> f64 {
+    let degenerate = std.is_nan() || is_near_zero(std, scale);
+    if std_floor > 0.0 {
+        if degenerate { std_floor } else { std.max(std_floor) }
+    } else if std.is_finite() && !degenerate {
+        std
+    } else {
+        f64::NAN
+    }
+}
+
+/// Calculate rolling z-score `(x - rolling_mean) / rolling_std` over the last `window` valid values
+///
+/// The std divides by `count - ddof`. The denominator is clamped to at least `std_floor`;
+/// the default of 0.0 gives NaN on a zero std. NaN until the window is full and at NaN/inf
+/// inputs. `mean_reversion` is this z-score negated.
+pub fn rolling_zscore(data: &Array1<f64>, window: usize, ddof: usize, std_floor: f64) -> Result<Array1<f64>> {
+    check_std_floor(std_floor)?;
+    let mean = rolling_mean(data, window)?;
+    let std = rolling_std(data, window, ddof)?;
+
+    let mut result = Array1::zeros(data.len());
+    for i in 0..data.len() {
+        let (x, m) = (data[i], mean[i]);
+        let s = floored_std(std[i], m, std_floor);
+        result[i] = if !x.is_finite() || !m.is_finite() || !s.is_finite() {
+            f64::NAN
+        } else {
+            (x - m) / s
+        };
+    }
+
+    Ok(result)
+}
+
+/// Smoothing factor `2 / (span + 1)` for a span of at least 1
+pub fn span_to_alpha(span: f64) -> Result<f64> {
+    if span.is_nan() || span < 1.0 {
+        return Err(ExprError::InvalidParameter("span must be at least 1".into()));
+    }
+    Ok(2.0 / (span + 1.0))
+}
+
+/// Smoothing factor `1 - exp(ln(0.5) / halflife)`, so a weight halves every `halflife` bars
+///
+/// As pandas' `ewm(halflife=...)`; `halflife` must be finite and positive.
+pub fn halflife_to_alpha(halflife: f64) -> Result<f64> {
+    if !(halflife.is_finite() && halflife > 0.0) {
+        return Err(ExprError::InvalidParameter("halflife must be finite and positive".into()));
+    }
+    Ok(1.0 - (0.5f64.ln() / halflife).exp())
+}
+
+/// Exponential moving average with smoothing factor `alpha`
+///
+/// Without `adjust` this is the recursion `ema = alpha * x + (1 - alpha) * ema`, seeded
+/// with the first valid value. With `adjust` it is pandas' bias-corrected form, the
+/// weighted mean of all valid values so far with weights `(1 - alpha)^k`. Weights decay
+/// per valid observation (pandas' `ignore_na=True`): a NaN/inf input carries the previous
+/// EMA forward and leaves the state untouched. Output is NaN before the first valid value.
+pub fn ema(data: &Array1<f64>, alpha: f64, adjust: bool) -> Result<Array1<f64>> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err(ExprError::InvalidParameter("alpha must be in (0, 1]".into()));
+    }
+
+    let decay = 1.0 - alpha;
+    let (mut num, mut den) = (0.0, 0.0);
+    let mut current = f64::NAN;
+    Ok(data.mapv(|x| {
+        if x.is_finite() {
+            current = if adjust {
+                num = x + decay * num;
+                den = 1.0 + decay * den;
+                num / den
+            } else if current.is_nan() {
+                x
+            } else {
+                alpha * x + decay * current
+            };
+        }
+        current
+    }))
+}
+
+/// `ema`, `ema(ema)`, ... up to `depth` stages, each with the same `alpha` and `adjust`
+///
+/// Later stages are fed NaN wherever `data` is NaN/inf, so the value an earlier stage
+/// carried over a gap is not counted as a new observation.
+fn ema_stages(data: &Array1<f64>, alpha: f64, adjust: bool, depth: usize) -> Result<Vec<Array1<f64>>> {
+    let mut stages: Vec<Array1<f64>> = vec![ema(data, alpha, adjust)?];
+    while stages.len() < depth {
+        let last = stages.last().unwrap();
+        let input = Array1::from_iter(data.iter().zip(last.iter()).map(|(&x, &e)| if x.is_finite() { e } else { f64::NAN }));
+        stages.push(ema(&input, alpha, adjust)?);
+    }
+    Ok(stages)
+}
+
+/// Double EMA `2 * ema(x) - ema(ema(x))`, cancelling most of the EMA's lag on a trend
+///
+/// NaN handling is the base EMA's: NaN before the first valid value and the previous output
+/// carried over NaN/inf inputs, which no stage counts as an observation.
+pub fn dema(data: &Array1<f64>, alpha: f64, adjust: bool) -> Result<Array1<f64>> {
+    let e = ema_stages(data, alpha, adjust, 2)?;
+    Ok(2.0 * &e[0] - &e[1])
+}
+
+/// Triple EMA `3 * ema(x) - 3 * ema(ema(x)) + ema(ema(ema(x)))`; stages follow `dema`
+pub fn tema(data: &Array1<f64>, alpha: f64, adjust: bool) -> Result<Array1<f64>> {
+    let e = ema_stages(data, alpha, adjust, 3)?;
+    Ok(3.0 * (&e[0] - &e[1]) + &e[2])
+}
+
+/// Exponentially weighted variance with smoothing factor `alpha` (pandas `ewm(...).var()`)
+///
+/// Uses the adjusted weights `(1 - alpha)^k` of `ema(adjust=True)`, updating the weighted
+/// mean and variance recursively. `bias=false` applies pandas' correction
+/// `(Σw)² / ((Σw)² - Σw²)`, so the first observation gives NaN; `bias=true` gives the plain
+/// weighted variance, 0.0 at the first observation. As in `ema`, weights decay per valid
+/// observation and a NaN/inf input repeats the previous output with the state untouched.
+pub fn ewm_var(data: &Array1<f64>, alpha: f64, bias: bool) -> Result<Array1<f64>> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err(ExprError::InvalidParameter("alpha must be in (0, 1]".into()));
+    }
+
+    let decay = 1.0 - alpha;
+    let (mut sum_wt, mut sum_wt2) = (0.0, 0.0);
+    let (mut mean, mut var) = (f64::NAN, 0.0);
+    let mut current = f64::NAN;
+    Ok(data.mapv(|x| {
+        if x.is_finite() {
+            if mean.is_nan() {
+                (mean, var, sum_wt, sum_wt2) = (x, 0.0, 1.0, 1.0);
+            } else {
+                let old_wt = decay * sum_wt;
+                let old_mean = mean;
+                sum_wt = old_wt + 1.0;
+                sum_wt2 = decay * decay * sum_wt2 + 1.0;
+                mean = (old_wt * old_mean + x) / sum_wt;
+                var = (old_wt * (var + (old_mean - mean).powi(2)) + (x - mean).powi(2)) / sum_wt;
+            }
+            current = if bias {
+                var
+            } else {
+                let denom = sum_wt * sum_wt - sum_wt2;
+                if denom > 0.0 { var * sum_wt * sum_wt / denom } else { f64::NAN }
+            };
+        }
+        current
+    }))
+}
+
+/// Exponentially weighted std, the square root of `ewm_var`
+pub fn ewm_std(data: &Array1<f64>, alpha: f64, bias: bool) -> Result<Array1<f64>> {
+    Ok(ewm_var(data, alpha, bias)?.mapv(f64::sqrt))
+}
+
+/// Linearly decaying weighted average over the last `window` bars (WorldQuant `decay_linear`)
+///
+/// The bar `k` steps back gets weight `window - k`, so the current bar weighs `window` and
+/// the oldest 1. NaN/inf values are dropped and the remaining weights renormalized; a
+/// window with no valid value is NaN, as are the first `window - 1` outputs.
+pub fn decay_linear(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+    weighted_rolling_mean(data, &Array1::from_iter((1..=window).map(|k| k as f64)))
+}
+
+/// Weighted average over the last `weights.len()` bars with an arbitrary kernel
+///
+/// `weights` is the window shape, oldest bar first and the current bar last, so
+/// `decay_linear` is the kernel `1..=window` and `rolling_mean` a flat one (ignoring how
+/// each skips NaNs). The window is positional: NaN/inf values are dropped and the weights
+/// of the remaining bars renormalized to sum to 1. A window whose valid bars all have zero
+/// weight is NaN, as are the first `window - 1` outputs. Weights must be finite,
+/// non-negative and not all zero. O(n * window).
+pub fn weighted_rolling_mean(data: &Array1<f64>, weights: &Array1<f64>) -> Result<Array1<f64>> {
+    let window = weights.len();
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Weights must not be empty".into()));
+    }
+    if weights.iter().any(|&w| !w.is_finite() || w < 0.0) || weights.sum() <= 0.0 {
+        return Err(ExprError::InvalidParameter("weights must be finite, non-negative and not all zero".into()));
+    }
+
+    let mut result = Array1::from_elem(data.len(), f64::NAN);
+    for i in window - 1..data.len() {
+        let (mut weighted, mut total) = (0.0, 0.0);
+        for (&x, &w) in data.slice(s![i + 1 - window..=i]).iter().zip(weights.iter()) {
+            if x.is_finite() {
+                weighted += w * x;
+                total += w;
+            }
+        }
+        if total > 0.0 {
+            result[i] = weighted / total;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Calculate exponentially weighted z-score
+///
+/// The EW mean and variance are updated recursively with smoothing factor `alpha`;
+/// NaN/inf inputs produce NaN and leave the state untouched. The std is clamped to at least
+/// `std_floor`, as in `rolling_zscore`.
+pub fn ew_zscore(data: &Array1<f64>, alpha: f64, std_floor: f64) -> Result<Array1<f64>> {
+    check_std_floor(std_floor)?;
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err(ExprError::InvalidParameter("alpha must be in (0, 1]".into()));
+    }
+
+    let n = data.len();
+    let mut result = Array1::zeros(n);
+    let mut mean = f64::NAN;
+    let mut var = 0.0;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        if mean.is_nan() {
+            mean = val;
+        } else {
+            let diff = val - mean;
+            mean += alpha * diff;
+            var = (1.0 - alpha) * (var + alpha * diff * diff);
+        }
+
+        let sd = floored_std(if var > 0.0 { var.sqrt() } else { f64::NAN }, mean, std_floor);
+        result[i] = if sd.is_nan() { f64::NAN } else { (val - mean) / sd };
+    }
+
+    Ok(result)
+}
+
+/// RiskMetrics exponentially weighted correlation with decay `lambda`
+///
+/// Following RiskMetrics, the moments are taken about zero (inputs are assumed to be
+/// mean-zero returns): `cov_t = λ cov_{t-1} + (1 - λ) x_t y_t`, and likewise for each
+/// variance. The recursion is seeded by the first pair where both inputs are finite;
+/// non-finite pairs yield NaN and leave the state untouched, and zero variances yield NaN.
+pub fn ewm_correlation(x: &Array1<f64>, y: &Array1<f64>, lambda: f64) -> Result<Array1<f64>> {
+    if !(lambda > 0.0 && lambda < 1.0) {
+        return Err(ExprError::InvalidParameter(format!("lambda_ must be in (0, 1), got {}", lambda)));
+    }
+    check_same_length("x", x, "y", y)?;
+
+    let mut result = Array1::from_elem(x.len(), f64::NAN);
+    let mut seeded = false;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+
+    for i in 0..x.len() {
+        let (a, b) = (x[i], y[i]);
+        if !a.is_finite() || !b.is_finite() {
+            continue;
+        }
+
+        if seeded {
+            cov = lambda * cov + (1.0 - lambda) * a * b;
+            var_x = lambda * var_x + (1.0 - lambda) * a * a;
+            var_y = lambda * var_y + (1.0 - lambda) * b * b;
+        } else {
+            (cov, var_x, var_y) = (a * b, a * a, b * b);
+            seeded = true;
+        }
+
+        if var_x > 0.0 && var_y > 0.0 {
+            result[i] = cov / (var_x.sqrt() * var_y.sqrt());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Kish's effective sample size `(sum w)^2 / sum w^2`
+fn kish_ess(sum_w: f64, sum_w2: f64) -> f64 {
+    if sum_w2 <= 0.0 {
+        f64::NAN
+    } else {
+        sum_w * sum_w / sum_w2
+    }
+}
+
+/// Effective sample size behind each `ew_zscore` output
+///
+/// Observation weights decay by `1 - alpha` per valid observation, matching the state
+/// updates in `ew_zscore`. NaN/inf inputs yield NaN and leave the state untouched.
+pub fn ew_ess(data: &Array1<f64>, alpha: f64) -> Result<Array1<f64>> {
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err(ExprError::InvalidParameter("alpha must be in (0, 1]".into()));
+    }
+
+    let decay = 1.0 - alpha;
+    let mut result = Array1::zeros(data.len());
+    let mut sum_w = 0.0;
+    let mut sum_w2 = 0.0;
+
+    for (i, &val) in data.iter().enumerate() {
+        if !val.is_finite() {
+            result[i] = f64::NAN;
+            continue;
+        }
+
+        sum_w = decay * sum_w + 1.0;
+        sum_w2 = decay * decay * sum_w2 + 1.0;
+        result[i] = kish_ess(sum_w, sum_w2);
+    }
+
+    Ok(result)
+}
+
+/// Effective sample size of a trailing weight kernel over the valid observations
+///
+/// `weights` defines the window (most recent last); weights on NaN/inf observations are
+/// dropped before computing Kish's ESS. The first `weights.len() - 1` outputs are NaN.
+pub fn rolling_ess(data: &Array1<f64>, weights: &Array1<f64>) -> Result<Array1<f64>> {
+    let window = weights.len();
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Weights must not be empty".into()));
+    }
+    if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+        return Err(ExprError::InvalidParameter("weights must be finite and non-negative".into()));
+    }
+
+    let n = data.len();
+    let mut result = Array1::zeros(n);
+
+    for i in 0..n {
+        result[i] = if i < window - 1 {
+            f64::NAN
+        } else {
+            let start = i + 1 - window;
+            let (sum_w, sum_w2) = data.slice(s![start..=i])
+                .iter()
+                .zip(weights.iter())
+                .filter(|(x, _)| x.is_finite())
+                .fold((0.0, 0.0), |(a, b), (_, &w)| (a + w, b + w * w));
+            kish_ess(sum_w, sum_w2)
+        };
+    }
+
+    Ok(result)
+}
+
+/// Infinite-horizon discounted sum `s[t] = decay * s[t-1] + x[t]`
+///
+/// Unlike an EWMA the sum is not normalized. NaN/inf inputs carry the previous sum forward
+/// without decaying it; outputs before the first finite input are NaN.
+pub fn discounted_sum(data: &Array1<f64>, decay: f64) -> Result<Array1<f64>> {
+    if !(0.0..1.0).contains(&decay) {
+        return Err(ExprError::InvalidParameter("decay must be in [0, 1)".into()));
+    }
+
+    let mut sum = f64::NAN;
+    Ok(data.mapv(|val| {
+        if val.is_finite() {
+            sum = if sum.is_nan() { val } else { decay * sum + val };
+        }
+        sum
+    }))
+}
+
+/// Blend of the equal-weight and exponentially weighted z-scores
+///
+/// A component with zero weight is ignored, so `blend = 1.0` reproduces `rolling_zscore`
+/// and `blend = 0.0` reproduces `ew_zscore`. `std_floor` is applied to both components.
+pub fn hybrid_zscore(data: &Array1<f64>, window: usize, alpha: f64, blend: f64, std_floor: f64) -> Result<Array1<f64>> {
+    if !(0.0..=1.0).contains(&blend) {
+        return Err(ExprError::InvalidParameter("blend must be in [0, 1]".into()));
+    }
+
+    let eq = rolling_zscore(data, window, 0, std_floor)?;
+    let ew = ew_zscore(data, alpha, std_floor)?;
+
+    let result = eq.iter()
+        .zip(ew.iter())
+        .map(|(&a, &b)| {
+            if blend == 1.0 {
+                a
+            } else if blend == 0.0 {
+                b
+            } else if a.is_nan() || b.is_nan() {
+                f64::NAN
+            } else {
+                blend * a + (1.0 - blend) * b
+            }
+        })
+        .collect::<Vec<f64>>();
+
+    Ok(Array1::from_vec(result))
+}
+
+/// Shrink a beta series towards `target`: `weight * beta + (1 - weight) * target`
+///
+/// Blume's coefficients are `weight = 0.67` and `target = 1.0`, i.e. `0.67 * beta + 0.33`.
+/// NaN betas stay NaN.
+pub fn apply_blume(beta: &Array1<f64>, weight: f64, target: f64) -> Array1<f64> {
+    beta.mapv(|b| weight * b + (1.0 - weight) * target)
+}
+
+/// Split off the leading NaN warm-up of a rolling output
+///
+/// Returns the output from its first non-NaN value onward together with that start index,
+/// so `trimmed[k]` aligns with input position `start + k`. Only the leading region is
+/// dropped; NaNs from gaps later in the series are kept. An all-NaN output trims to empty
+/// with `start == len`.
+pub fn drop_warmup(result: &Array1<f64>) -> (Array1<f64>, usize) {
+    let start = result.iter().position(|v| !v.is_nan()).unwrap_or(result.len());
+    (result.slice(s![start..]).to_owned(), start)
+}
+
+/// How the leading NaN warm-up of a rolling output is presented
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarmupFill {
+    /// Leave the warm-up as NaN
+    Nan,
+    /// Back-fill with the first computed value
+    FirstValid,
+    /// Fill with zero
+    Zero,
+}
+
+impl std::str::FromStr for WarmupFill {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "nan" => Ok(WarmupFill::Nan),
+            "first_valid" => Ok(WarmupFill::FirstValid),
+            "zero" => Ok(WarmupFill::Zero),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown warmup fill '{}', expected one of: nan, first_valid, zero",
+                s
+            ))),
+        }
+    }
+}
+
+/// Fill the leading NaN region of a rolling output, as located by `drop_warmup`
+///
+/// Later NaNs are left alone, and an all-NaN output is unchanged under `FirstValid`.
+pub fn fill_warmup(mut result: Array1<f64>, fill: WarmupFill) -> Array1<f64> {
+    let start = result.iter().position(|v| !v.is_nan()).unwrap_or(result.len());
+    let value = match fill {
+        WarmupFill::Nan => return result,
+        WarmupFill::FirstValid if start == result.len() => return result,
+        WarmupFill::FirstValid => result[start],
+        WarmupFill::Zero => 0.0,
+    };
+    result.slice_mut(s![..start]).fill(value);
+    result
+}
+
+/// Replace every NaN/inf of a final output with `fill_value`, when one is given
+///
+/// This only touches the returned array: how a window treats missing inputs is decided
+/// beforehand (see `apply_skipna`), and filling never feeds back into a computation.
+/// `None` returns the output unchanged.
+pub fn fill_non_finite<D: Dimension>(mut result: Array<f64, D>, fill_value: Option<f64>) -> Array<f64, D> {
+    if let Some(fill) = fill_value {
+        result.mapv_inplace(|v| if v.is_finite() { v } else { fill });
+    }
+    result
+}
+
+/// Carry the last non-NaN value forward over NaN gaps of at most `limit` bars each
+///
+/// `None` fills gaps of any length. Only the first `limit` bars of a longer gap are filled,
+/// and leading NaNs (nothing to carry yet) stay NaN. Infinities count as values.
+pub fn forward_fill(data: &Array1<f64>, limit: Option<usize>) -> Array1<f64> {
+    let mut out = data.clone();
+    fill_gaps(out.iter_mut(), limit);
+    out
+}
+
+/// Mirror of `forward_fill`: carry the next non-NaN value backward over NaN gaps
+pub fn backward_fill(data: &Array1<f64>, limit: Option<usize>) -> Array1<f64> {
+    let mut out = data.clone();
+    fill_gaps(out.iter_mut().rev(), limit);
+    out
+}
+
+fn fill_gaps<'a>(values: impl Iterator<Item = &'a mut f64>, limit: Option<usize>) {
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut last = f64::NAN;
+    let mut gap = 0usize;
+    for v in values {
+        if v.is_nan() {
+            gap += 1;
+            if gap <= limit {
+                *v = last;
+            }
+        } else {
+            last = *v;
+            gap = 0;
+        }
+    }
+}
+
+/// Apply a `skipna` toggle: unless `skipna`, NaN any output whose trailing `window` bars hold a
+/// NaN or inf in any input
+///
+/// The window is positional, so with `skipna=false` a single missing bar blanks the next
+/// `window` outputs. `min_periods` then only matters during the initial warm-up: once a full
+/// window of finite bars is available every valid-count window is complete.
+pub fn apply_skipna(mut result: Array1<f64>, skipna: bool, inputs: &[&Array1<f64>], window: usize) -> Array1<f64> {
+    if skipna {
+        return result;
+    }
+    let mut last_bad: Option<usize> = None;
+    for i in 0..result.len() {
+        if inputs.iter().any(|x| !x[i].is_finite()) {
+            last_bad = Some(i);
+        }
+        if last_bad.is_some_and(|j| i - j < window) {
+            result[i] = f64::NAN;
+        }
+    }
+    result
+}
+
+/// Re-align a trailing rolling output so each value sits at the centre of its window
+///
+/// As pandas' `center=True`: the output at `i` is the window ending at
+/// `i + (window - 1) / 2`, i.e. `[i - window / 2, i + (window - 1) / 2]`, so the last
+/// `(window - 1) / 2` outputs are NaN as well as the leading warm-up. Valid-count windows
+/// are shifted by that bar offset too. The result looks ahead into future bars: it is for
+/// smoothing and offline analysis, never for signals traded in real time.
+pub fn center_windows(values: &Array1<f64>, window: usize) -> Array1<f64> {
+    let offset = window.saturating_sub(1) / 2;
+    let n = values.len();
+    Array1::from_iter((0..n).map(|i| if i + offset < n { values[i + offset] } else { f64::NAN }))
+}
+
+/// Keep a rolling output only at the ends of blocks of `window` bars spaced `step` apart
+///
+/// Block `k` ends at position `window - 1 + k * step`, so `step == window` gives disjoint
+/// (non-overlapping) blocks and `step == 1` keeps every sliding window. The output keeps
+/// the input length with NaN between block ends, or with `compact` holds one value per
+/// block: `(n - window) / step + 1` values, or none when `n < window`. Blocks are counted in
+/// bars; kernels with valid-count windows still skip NaNs inside a block.
+pub fn stride_windows(values: Array1<f64>, window: usize, step: usize, compact: bool) -> Result<Array1<f64>> {
+    if step == 0 {
+        return Err(ExprError::InvalidParameter("step must be at least 1".into()));
+    }
+    let n = values.len();
+    let ends = (window.max(1) - 1..n).step_by(step);
+    if compact {
+        return Ok(Array1::from_iter(ends.map(|i| values[i])));
+    }
+    if step == 1 {
+        return Ok(values);
+    }
+    let mut result = Array1::from_elem(n, f64::NAN);
+    for i in ends {
+        result[i] = values[i];
+    }
+    Ok(result)
+}
+
+/// Run `compute` on the span between the first and last positions where every input is finite
+///
+/// With `trim` the inputs are cut to that span before computing and the edge NaNs are
+/// restored in the output, so leading NaNs (e.g. before listing) don't consume the warm-up.
+/// Inner NaNs are passed through. Inputs of unequal length are computed untrimmed so the
+/// kernel can report the mismatch.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
+fn compute_trimmed<F>(inputs: &[Array1<f64>], trim: bool, compute: F) -> Result<Array1<f64>>
+where
+    F: FnOnce(&[Array1<f64>]) -> Result<Array1<f64>>,
+{
+    let n = inputs[0].len();
+    if !trim || inputs.iter().any(|x| x.len() != n) {
+        return compute(inputs);
+    }
+
+    let finite = |i: &usize| inputs.iter().all(|x| x[*i].is_finite());
+    let start = (0..n).find(finite).unwrap_or(n);
+    let end = (start..n).rev().find(finite).map_or(start, |i| i + 1);
+
+    let trimmed: Vec<Array1<f64>> = inputs.iter().map(|x| x.slice(s![start..end]).to_owned()).collect();
+    let inner = compute(&trimmed)?;
+    let mut result = Array1::from_elem(n, f64::NAN);
+    result.slice_mut(s![start..end]).assign(&inner);
+    Ok(result)
+}
+
+/// The values a rolling kernel sees for output `index`
+///
+/// With `skipna` this is the last `window` finite values up to and including `index`, as
+/// used by the valid-count kernels (`rolling_mean`, `rolling_std`, `rolling_correlation`,
+/// `rolling_sum`, ...). Without it, it is the positional slice ending at `index`, as used
+/// by `rolling_rank`. Near the start the returned window may be shorter.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
+fn window_at(data: &Array1<f64>, window: usize, index: usize, skipna: bool) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+    if index >= data.len() {
+        return Err(ExprError::InvalidParameter(format!(
+            "index {} is out of bounds for length {}",
+            index,
+            data.len()
+        )));
+    }
+
+    if skipna {
+        let mut values: Vec<f64> = data.slice(s![..=index]).iter().rev().copied().filter(|v| v.is_finite()).take(window).collect();
+        values.reverse();
+        Ok(Array1::from_vec(values))
+    } else {
+        let start = (index + 1).saturating_sub(window);
+        Ok(data.slice(s![start..=index]).to_owned())
+    }
+}
+
+/// How the spread between two legs of a pair is formed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpreadMode {
+    /// `ln(y) - ln(x)`
+    Ratio,
+    /// `y - x`
+    Diff,
+    /// `y - beta * x` with `beta` the trailing rolling OLS slope
+    Ols,
+}
+
+impl std::str::FromStr for SpreadMode {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ratio" => Ok(SpreadMode::Ratio),
+            "diff" => Ok(SpreadMode::Diff),
+            "ols" => Ok(SpreadMode::Ols),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown spread mode '{}', expected one of: ratio, diff, ols", s
+            ))),
+        }
+    }
+}
+
+/// Rolling z-score of a pair spread
+pub fn pair_zscore(y: &Array1<f64>, x: &Array1<f64>, window: usize, mode: SpreadMode) -> Result<Array1<f64>> {
+    check_same_length("y", y, "x", x)?;
+
+    let spread = match mode {
+        SpreadMode::Ratio => Array1::from_iter(y.iter().zip(x.iter()).map(|(&a, &b)| {
+            if a > 0.0 && b > 0.0 { a.ln() - b.ln() } else { f64::NAN }
+        })),
+        SpreadMode::Diff => y - x,
+        SpreadMode::Ols => {
+            let beta = rolling_beta(x, y, window, true)?;
+            Array1::from_iter((0..y.len()).map(|i| y[i] - beta[i] * x[i]))
+        }
+    };
+
+    // The z-score windows skip the NaN OLS warm-up
+    rolling_zscore(&spread, window, 0, 0.0)
+}
+
+/// Shift a series forward by `periods`, filling the leading positions with NaN
+fn shift(data: &Array1<f64>, periods: usize) -> Array1<f64> {
+    let n = data.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    if periods < n {
+        result.slice_mut(s![periods..]).assign(&data.slice(s![..n - periods]));
+    }
+    result
+}
+
+/// Rolling correlation of `x` against `y` at each lag in `-max_lag..=max_lag`
+///
+/// Column `k` holds lag `k - max_lag`. A positive lag pairs `x[t]` with `y[t - lag]`
+/// (y leads), a negative lag pairs `x[t + lag]` with `y[t]` (x leads), so no column
+/// looks ahead of `t`.
+pub fn rolling_lead_lag(x: &Array1<f64>, y: &Array1<f64>, window: usize, max_lag: usize) -> Result<Array2<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if max_lag >= window {
+        return Err(ExprError::InvalidParameter(format!(
+            "max_lag {} must be smaller than window {}", max_lag, window
+        )));
+    }
+
+    let mut result = Array2::from_elem((x.len(), 2 * max_lag + 1), f64::NAN);
+    for k in 0..=2 * max_lag {
+        let corr = if k >= max_lag {
+            rolling_correlation(x, &shift(y, k - max_lag), window)?
+        } else {
+            rolling_correlation(&shift(x, max_lag - k), y, window)?
+        };
+        result.column_mut(k).assign(&corr);
+    }
+
+    Ok(result)
+}
+
+/// Rolling correlation of the log returns of two price series
+pub fn rolling_logret_corr(
+    price_x: &Array1<f64>,
+    price_y: &Array1<f64>,
+    window: usize,
+    return_periods: usize,
+    mode: PrecisionMode,
+) -> Result<Array1<f64>> {
+    check_same_length("price_x", price_x, "price_y", price_y)?;
+    rolling_correlation_with(&log_return(price_x, return_periods)?, &log_return(price_y, return_periods)?, window, mode)
+}
+
+/// Rolling predictive R² of `forward_ret` on `factor`: the squared rolling correlation
+pub fn rolling_predictive_r2(factor: &Array1<f64>, forward_ret: &Array1<f64>, window: usize, mode: PrecisionMode) -> Result<Array1<f64>> {
+    check_same_length("factor", factor, "forward_ret", forward_ret)?;
+    Ok(rolling_correlation_with(factor, forward_ret, window, mode)?.mapv(|r| r * r))
+}
+
+/// `rolling_beta` of every column of a `(time, assets)` return panel on one shared `market`
+///
+/// Each asset skips the bars where its own return or the market's is NaN/inf, so a gap in
+/// one asset never shortens another's window. Columns run in parallel via
+/// `apply_columns_par` and match `rolling_beta(market, column, ...)` exactly.
+pub fn panel_beta(returns: &Array2<f64>, market: &Array1<f64>, window: usize, fit_intercept: bool) -> Result<Array2<f64>> {
+    if market.len() != returns.nrows() {
+        return Err(ExprError::LengthMismatch(format!(
+            "market has {} bars but returns has {} rows", market.len(), returns.nrows()
+        )));
+    }
+    apply_columns_par(returns, |asset| rolling_beta(market, asset, window, fit_intercept))
+}
+
+/// Rolling coefficient of variation of the rolling beta of `asset` on `market`
+///
+/// The beta series from `rolling_beta` (with intercept) is summarised over the last
+/// `stability_window` defined betas as `std / |mean|`, using the valid-count windows of
+/// `rolling_std` and `rolling_sum` so the beta warm-up is skipped rather than propagated.
+/// The first `beta_window + stability_window - 2` outputs are NaN, as are windows with a
+/// zero mean beta.
+pub fn rolling_beta_stability(asset: &Array1<f64>, market: &Array1<f64>, beta_window: usize, stability_window: usize) -> Result<Array1<f64>> {
+    check_same_length("asset", asset, "market", market)?;
+    let beta = rolling_beta(market, asset, beta_window, true)?;
+    let mean = rolling_sum(&beta, stability_window)? / stability_window as f64;
+    let std = rolling_std(&beta, stability_window, 0)?;
+    Ok(Array1::from_iter(std.iter().zip(mean.iter()).map(|(&s, &m)| {
+        if m == 0.0 { f64::NAN } else { s / m.abs() }
+    })))
+}
+
+/// Idiosyncratic return `asset_ret[t] - beta * market_ret[t]` with a lagged rolling beta
+///
+/// `beta` is the `rolling_beta` (with intercept) of the asset on the market from the
+/// latest window ending before `t`, so no return at `t` enters its own hedge. A NaN pair
+/// leaves the beta window unchanged, so the last defined beta carries over it. The output
+/// is NaN until a beta exists and wherever either return is non-finite.
+pub fn rolling_residualize(asset_ret: &Array1<f64>, market_ret: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    check_same_length("asset_ret", asset_ret, "market_ret", market_ret)?;
+    let beta = rolling_beta(market_ret, asset_ret, window, true)?;
+
+    let mut result = Array1::from_elem(asset_ret.len(), f64::NAN);
+    let mut prev_beta = f64::NAN;
+    for i in 0..asset_ret.len() {
+        let (a, m) = (asset_ret[i], market_ret[i]);
+        if a.is_finite() && m.is_finite() {
+            result[i] = a - prev_beta * m;
+        }
+        if !beta[i].is_nan() {
+            prev_beta = beta[i];
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rolling time-series IC: the Pearson correlation of `factor` with `forward_ret`
+fn rolling_ic(factor: &Array1<f64>, forward_ret: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    check_same_length("factor", factor, "forward_ret", forward_ret)?;
+    rolling_correlation(factor, forward_ret, window)
+}
+
+/// True where the recent rolling IC sits more than `drop_threshold` below the baseline IC
+///
+/// Both ICs come from `rolling_ic`; positions where either is undefined are false.
+pub fn factor_decay_flag(
+    factor: &Array1<f64>,
+    forward_ret: &Array1<f64>,
+    recent_window: usize,
+    baseline_window: usize,
+    drop_threshold: f64,
+) -> Result<Array1<bool>> {
+    if recent_window >= baseline_window {
+        return Err(ExprError::InvalidParameter(format!(
+            "recent_window ({}) must be smaller than baseline_window ({})",
+            recent_window, baseline_window
+        )));
+    }
+    if !drop_threshold.is_finite() || drop_threshold < 0.0 {
+        return Err(ExprError::InvalidParameter(format!("drop_threshold must be finite and non-negative, got {}", drop_threshold)));
+    }
+
+    let recent = rolling_ic(factor, forward_ret, recent_window)?;
+    let baseline = rolling_ic(factor, forward_ret, baseline_window)?;
+    // NaN on either side compares false
+    Ok(Array1::from_iter(recent.iter().zip(baseline.iter()).map(|(&r, &b)| b - r > drop_threshold)))
+}
+
+/// Two-sided p-value of the rolling Pearson correlation under zero correlation
+///
+/// Uses `t = r sqrt((n - 2) / (1 - r²))` with `n = window` and a Student-t CDF with
+/// `n - 2` degrees of freedom; `|r| = 1` gives 0. Warm-up and degenerate windows are NaN.
+pub fn rolling_corr_pvalue(x: &Array1<f64>, y: &Array1<f64>, window: usize, mode: PrecisionMode) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if window < 3 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 3".into()));
+    }
+
+    let df = (window - 2) as f64;
+    let t_dist = StudentsT::new(0.0, 1.0, df).map_err(|e| ExprError::ComputationError(e.to_string()))?;
+    Ok(rolling_correlation_with(x, y, window, mode)?.mapv(|r| {
+        if r.is_nan() {
+            return f64::NAN;
+        }
+        let denom = 1.0 - r * r;
+        if denom <= 0.0 {
+            return 0.0;
+        }
+        let t = r.abs() * (df / denom).sqrt();
+        2.0 * t_dist.sf(t)
+    }))
+}
+
+/// Trimmed Pearson correlation of one window of finite pairs
+///
+/// Each pair's squared Mahalanobis distance under the window's own means, variances and
+/// correlation is `(zx² - 2·r·zx·zy + zy²) / (1 - r²)`; the `floor(trim_frac · count)`
+/// farthest pairs are dropped and Pearson is recomputed on the rest. A perfectly correlated
+/// window keeps `r = ±1`.
+fn trimmed_pearson(pairs: &[(f64, f64)], trim_frac: f64) -> f64 {
+    let (xs, ys): (Vec<f64>, Vec<f64>) = pairs.iter().copied().unzip();
+    let ones = vec![1.0; pairs.len()];
+    let r = weighted_pearson(&xs, &ys, &ones);
+    let drop = (trim_frac * pairs.len() as f64).floor() as usize;
+    if r.is_nan() || drop == 0 || r * r >= 1.0 {
+        return r;
+    }
+
+    let count = pairs.len() as f64;
+    let (mx, my) = (xs.iter().sum::<f64>() / count, ys.iter().sum::<f64>() / count);
+    let sx = (xs.iter().map(|a| (a - mx) * (a - mx)).sum::<f64>() / count).sqrt();
+    let sy = (ys.iter().map(|b| (b - my) * (b - my)).sum::<f64>() / count).sqrt();
+    let mut scored: Vec<(f64, usize)> = pairs
+        .iter()
+        .enumerate()
+        .map(|(k, &(a, b))| {
+            let (zx, zy) = ((a - mx) / sx, (b - my) / sy);
+            ((zx * zx - 2.0 * r * zx * zy + zy * zy) / (1.0 - r * r), k)
+        })
+        .collect();
+    let keep = pairs.len() - drop;
+    scored.select_nth_unstable_by(keep - 1, |a, b| a.0.total_cmp(&b.0));
+
+    let (kx, ky): (Vec<f64>, Vec<f64>) = scored[..keep].iter().map(|&(_, k)| pairs[k]).unzip();
+    weighted_pearson(&kx, &ky, &ones[..keep])
+}
+
+/// Rolling Pearson correlation after dropping the most outlying `trim_frac` of pairs
+///
+/// Windows hold the last `window` jointly finite pairs, like `rolling_correlation`, and
+/// are NaN until full. Outliers are ranked by Mahalanobis distance (see `trimmed_pearson`),
+/// so a pair far off the window's regression ellipse is dropped even if each coordinate is
+/// unremarkable. The per-window selection costs O(window) on top of the moment passes,
+/// O(n · window) overall. `trim_frac` must lie in `[0, 0.5)`; 0 gives plain Pearson.
+pub fn rolling_robust_corr(x: &Array1<f64>, y: &Array1<f64>, window: usize, trim_frac: f64) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    if !(0.0..0.5).contains(&trim_frac) {
+        return Err(ExprError::InvalidParameter(format!("trim_frac must be in [0, 0.5), got {}", trim_frac)));
+    }
+
+    let mut result = Array1::from_elem(x.len(), f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    for i in 0..x.len() {
+        if !x[i].is_finite() || !y[i].is_finite() {
+            continue;
+        }
+        queue.push_back((x[i], y[i]));
+        if queue.len() > window {
+            queue.pop_front();
+        }
+        if queue.len() == window {
+            result[i] = trimmed_pearson(queue.make_contiguous(), trim_frac);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rolling partial correlation of `x` and `y` controlling for `z`
+///
+/// `(r_xy - r_xz·r_yz) / sqrt((1 - r_xz²)(1 - r_yz²))` from three `rolling_correlation`
+/// passes. A bar where any of the three series is NaN/inf is dropped from all three passes
+/// first, so every correlation sees the same windows of `window` jointly finite triples.
+/// NaN during warm-up and where `z` is (nearly) perfectly correlated with `x` or `y`.
+pub fn rolling_partial_corr(x: &Array1<f64>, y: &Array1<f64>, z: &Array1<f64>, window: usize, mode: PrecisionMode) -> Result<Array1<f64>> {
+    check_same_length("x", x, "y", y)?;
+    check_same_length("x", x, "z", z)?;
+
+    let joint = |v: &Array1<f64>| {
+        Array1::from_iter((0..v.len()).map(|i| {
+            if x[i].is_finite() && y[i].is_finite() && z[i].is_finite() { v[i] } else { f64::NAN }
+        }))
+    };
+    let (x, y, z) = (joint(x), joint(y), joint(z));
+    let r_xy = rolling_correlation_with(&x, &y, window, mode)?;
+    let r_xz = rolling_correlation_with(&x, &z, window, mode)?;
+    let r_yz = rolling_correlation_with(&y, &z, window, mode)?;
+
+    // A perfect correlation still leaves `1 - r²` a few ulps above zero, so each factor is
+    // compared against that rounding budget rather than against 1
+    let degenerate = |r: f64| r.is_nan() || is_near_zero(1.0 - r * r, 4.0);
+    Ok(Array1::from_iter((0..x.len()).map(|i| {
+        if r_xy[i].is_nan() || degenerate(r_xz[i]) || degenerate(r_yz[i]) {
+            f64::NAN
+        } else {
+            (r_xy[i] - r_xz[i] * r_yz[i]) / ((1.0 - r_xz[i] * r_xz[i]) * (1.0 - r_yz[i] * r_yz[i])).sqrt()
+        }
+    })))
+}
+
+/// How missing components are treated when combining weighted factor components
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NanPolicy {
+    /// Missing components contribute 0.0 to the weighted sum
+    Zero,
+    /// Weights are renormalized over the components that are present
+    SkipReweight,
+    /// Any missing component makes the output NaN
+    Propagate,
+}
+
+impl std::str::FromStr for NanPolicy {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "zero" => Ok(NanPolicy::Zero),
+            "skip_reweight" => Ok(NanPolicy::SkipReweight),
+            "propagate" => Ok(NanPolicy::Propagate),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown nan_policy '{}', expected one of: zero, skip_reweight, propagate", s
+            ))),
+        }
+    }
+}
+
+/// Weighted sum of components under a NaN policy
+fn combine_weighted(components: &[Array1<f64>], weights: &[f64], policy: NanPolicy) -> Array1<f64> {
+    let n = components.first().map_or(0, |c| c.len());
+    let mut result = Array1::zeros(n);
+
+    for i in 0..n {
+        let mut sum = 0.0;
+        let mut weight_present = 0.0;
+        let mut missing = false;
+
+        for (component, &weight) in components.iter().zip(weights.iter()) {
+            let x = component[i];
+            if x.is_nan() || x.is_infinite() {
+                missing = true;
+            } else {
+                sum += x * weight;
+                weight_present += weight;
+            }
+        }
+
+        result[i] = match policy {
+            NanPolicy::Zero => sum,
+            NanPolicy::SkipReweight if weight_present > 0.0 => sum / weight_present,
+            NanPolicy::SkipReweight => f64::NAN,
+            NanPolicy::Propagate if missing => f64::NAN,
+            NanPolicy::Propagate => sum,
+        };
+    }
+
+    result
+}
+
+/// Rolling mean kept separately per regime label
+///
+/// Each label has its own window of its last `window` finite observations; the output at a
+/// bar is the mean of the window for that bar's label. NaN/inf values are skipped and give
+/// NaN, as does a label whose window isn't full yet.
+pub fn regime_rolling_mean(data: &Array1<f64>, regime: &Array1<i64>, window: usize) -> Result<Array1<f64>> {
+    if data.len() != regime.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "data has length {} but regime has length {}", data.len(), regime.len()
+        )));
+    }
+    let empty = MeanAccumulator::new(window)?;
+
+    let mut windows: HashMap<i64, MeanAccumulator> = HashMap::new();
+    let mut result = Array1::zeros(data.len());
+
+    for i in 0..data.len() {
+        let val = data[i];
+        result[i] = if val.is_nan() || val.is_infinite() {
+            f64::NAN
+        } else {
+            windows
+                .entry(regime[i])
+                .or_insert_with(|| empty.clone())
+                .push(val)
+        };
+    }
+
+    Ok(result)
+}
+
+/// Rolling kernel selectable by name in `groupby_rolling`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GroupFunc {
+    Mean,
+    Std,
+    Sum,
+    Min,
+    Max,
+    Median,
+    Quantile,
+    Rank,
+    Zscore,
+    Skew,
+    Kurt,
+    DecayLinear,
+}
+
+impl std::str::FromStr for GroupFunc {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "mean" => Ok(GroupFunc::Mean),
+            "std" => Ok(GroupFunc::Std),
+            "sum" => Ok(GroupFunc::Sum),
+            "min" => Ok(GroupFunc::Min),
+            "max" => Ok(GroupFunc::Max),
+            "median" => Ok(GroupFunc::Median),
+            "quantile" => Ok(GroupFunc::Quantile),
+            "rank" => Ok(GroupFunc::Rank),
+            "zscore" => Ok(GroupFunc::Zscore),
+            "skew" => Ok(GroupFunc::Skew),
+            "kurt" => Ok(GroupFunc::Kurt),
+            "decay_linear" => Ok(GroupFunc::DecayLinear),
+            other => Err(ExprError::InvalidParameter(format!(
+                "unknown func_name '{}', expected one of: mean, std, sum, min, max, median, quantile, rank, zscore, skew, kurt, decay_linear",
+                other
+            ))),
+        }
+    }
+}
+
+/// Extra arguments for `GroupFunc` kernels; each kernel reads only the fields it uses
+#[derive(Clone, Copy, Debug)]
+pub struct GroupParams {
+    /// Delta degrees of freedom for `std`
+    pub ddof: usize,
+    /// Quantile level for `quantile`
+    pub q: f64,
+    /// Minimum std for `zscore`
+    pub std_floor: f64,
+}
+
+impl Default for GroupParams {
+    fn default() -> Self {
+        GroupParams { ddof: 0, q: 0.5, std_floor: 0.0 }
+    }
+}
+
+impl GroupFunc {
+    fn apply(self, data: &Array1<f64>, window: usize, params: &GroupParams) -> Result<Array1<f64>> {
+        match self {
+            GroupFunc::Mean => rolling_mean(data, window),
+            GroupFunc::Std => rolling_std(data, window, params.ddof),
+            GroupFunc::Sum => rolling_sum(data, window),
+            GroupFunc::Min => rolling_min(data, window),
+            GroupFunc::Max => rolling_max(data, window),
+            GroupFunc::Median => rolling_median(data, window),
+            GroupFunc::Quantile => rolling_quantile(data, window, params.q),
+            GroupFunc::Rank => rolling_rank(data, window, None),
+            GroupFunc::Zscore => rolling_zscore(data, window, 0, params.std_floor),
+            GroupFunc::Skew => rolling_skew(data, window),
+            GroupFunc::Kurt => rolling_kurt(data, window),
+            GroupFunc::DecayLinear => decay_linear(data, window),
+        }
+    }
+}
+
+/// Apply `func` separately to the rows of each group, written back at the original rows
+///
+/// Rows of a group need not be contiguous: each group's rows are gathered in their original
+/// order (a stable partition), so interleaved long-format data behaves as if every symbol
+/// had been pivoted into its own column. Windows therefore count a group's own
+/// observations, not bars of the combined array, and rolling state resets per group. With
+/// `require_contiguous`, an id reappearing after another group has started is an error
+/// instead, for callers that promise rows sorted by group then time.
+fn group_apply<F>(data: &Array1<f64>, group_ids: &Array1<i64>, require_contiguous: bool, func: F) -> Result<Array1<f64>>
+where
+    F: Fn(&Array1<f64>) -> Result<Array1<f64>>,
+{
+    if data.len() != group_ids.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "data has length {} but group_ids has length {}", data.len(), group_ids.len()
+        )));
+    }
+
+    let mut order: Vec<i64> = Vec::new();
+    let mut rows: HashMap<i64, Vec<usize>> = HashMap::new();
+    for (i, &g) in group_ids.iter().enumerate() {
+        if require_contiguous && i > 0 && group_ids[i - 1] != g && rows.contains_key(&g) {
+            return Err(ExprError::InvalidParameter(format!(
+                "group {} is not contiguous; sort rows by group then time", g
+            )));
+        }
+        rows.entry(g).or_insert_with(|| {
+            order.push(g);
+            Vec::new()
+        }).push(i);
+    }
+
+    let mut result = Array1::from_elem(data.len(), f64::NAN);
+    for g in order {
+        let idx = &rows[&g];
+        let values = func(&idx.iter().map(|&i| data[i]).collect())?;
+        if values.len() != idx.len() {
+            return Err(ExprError::ComputationError(format!(
+                "group {} produced {} values for {} rows", g, values.len(), idx.len()
+            )));
+        }
+        for (&i, &v) in idx.iter().zip(values.iter()) {
+            result[i] = v;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Named rolling kernel applied within each group of a long-format array
+pub fn groupby_rolling(data: &Array1<f64>, group_ids: &Array1<i64>, func: GroupFunc, window: usize, params: &GroupParams) -> Result<Array1<f64>> {
+    group_apply(data, group_ids, false, |values| func.apply(values, window, params))
+}
+
+/// Label used by `regime_label` where trend or volatility is still unknown
+const REGIME_UNKNOWN: i64 = -1;
+
+/// Combined trend/volatility regime per bar
+///
+/// Encoding: `label = 2 * down + high_vol`, i.e.
+/// - `0`: trend up, normal vol
+/// - `1`: trend up, high vol
+/// - `2`: trend down, normal vol
+/// - `3`: trend down, high vol
+/// - `-1` (`REGIME_UNKNOWN`): warm-up or missing data
+///
+/// The trend is the sign of the rolling OLS slope of price on time over `trend_window`
+/// (a non-positive slope counts as down). Volatility is the rolling std of log returns over
+/// `vol_window`, and it is high when its `rolling_rank` over the last `vol_window` values
+/// exceeds `vol_high_q`; the rank starts once the vol warm-up is over, so it never ranks
+/// against an empty history.
+pub fn regime_label(prices: &Array1<f64>, trend_window: usize, vol_window: usize, vol_high_q: f64) -> Result<Array1<i64>> {
+    if !(vol_high_q > 0.0 && vol_high_q < 1.0) {
+        return Err(ExprError::InvalidParameter("vol_high_q must be in (0, 1)".into()));
+    }
+
+    let time = Array1::from_iter((0..prices.len()).map(|i| i as f64));
+    let slope = rolling_beta(&time, prices, trend_window, true)?;
+    let vol = rolling_std(&log_return(prices, 1)?, vol_window, 0)?;
+    let (vol, start) = drop_warmup(&vol);
+    let mut vol_pct = Array1::from_elem(prices.len(), f64::NAN);
+    vol_pct.slice_mut(s![start..]).assign(&rolling_rank(&vol, vol_window, None)?);
+
+    Ok(Array1::from_iter(slope.iter().zip(vol_pct.iter()).map(|(&b, &p)| {
+        if b.is_nan() || p.is_nan() {
+            REGIME_UNKNOWN
+        } else {
+            2 * i64::from(b <= 0.0) + i64::from(p > vol_high_q)
+        }
+    })))
+}
+
+/// Number of distinct finite values in each trailing window of `window` bars
+///
+/// Values are compared exactly (`-0.0` equals `0.0`), so continuous data should be
+/// discretized first. NaN/inf are not counted; windows without finite values and the first
+/// `window - 1` outputs are NaN. A sliding count map keeps updates O(1) on average.
+pub fn rolling_nunique(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    // `+ 0.0` maps -0.0 to 0.0 so both share a key
+    let key = |x: f64| (x + 0.0).to_bits();
+    let n = data.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+
+    for i in 0..n {
+        if data[i].is_finite() {
+            *counts.entry(key(data[i])).or_insert(0) += 1;
+        }
+        if i >= window && data[i - window].is_finite() {
+            let k = key(data[i - window]);
+            let c = counts.get_mut(&k).unwrap();
+            *c -= 1;
+            if *c == 0 {
+                counts.remove(&k);
+            }
+        }
+
+        if i + 1 >= window && !counts.is_empty() {
+            result[i] = counts.len() as f64;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rolling std of the rolling std of `returns`
+///
+/// Both stages are `rolling_std`, whose valid-count windows skip the first stage's NaN
+/// warm-up, so the second window holds `vov_window` consecutive vol estimates. The first
+/// defined output is at index `vol_window + vov_window - 2`.
+pub fn rolling_vol_of_vol(returns: &Array1<f64>, vol_window: usize, vov_window: usize) -> Result<Array1<f64>> {
+    let vol = rolling_std(returns, vol_window, 0)?;
+    rolling_std(&vol, vov_window, 0)
+}
+
+/// Longest time underwater within each trailing window of `window` bars
+///
+/// Inside each window the running peak starts at the window's first valid price; a bar is
+/// underwater while its price is below that running peak, and the output is the longest
+/// run of consecutive underwater bars (0 when prices never fall below a prior peak). NaN/inf
+/// prices are excluded without breaking a run. Costs O(n * window); the first `window - 1`
+/// outputs are NaN.
+pub fn rolling_drawdown_duration(prices: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let n = prices.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+
+    for i in (window - 1)..n {
+        let mut peak = f64::NAN;
+        let mut run = 0usize;
+        let mut longest = 0usize;
+
+        for &p in prices.slice(s![i + 1 - window..=i]).iter().filter(|p| p.is_finite()) {
+            if peak.is_nan() || p >= peak {
+                peak = p;
+                run = 0;
+            } else {
+                run += 1;
+                longest = longest.max(run);
+            }
+        }
+
+        result[i] = longest as f64;
+    }
+
+    Ok(result)
+}
+
+/// Rolling sum of volume signed by the close-to-close direction
+///
+/// Up bars add their volume, down bars subtract it and unchanged bars add zero. The first
+/// bar and bars where either close or volume is NaN/inf have no signed volume and are
+/// skipped, like NaNs in `rolling_std`.
+pub fn rolling_volume_delta(close: &Array1<f64>, volume: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    check_same_length("close", close, "volume", volume)?;
+
+    let signed = Array1::from_iter((0..close.len()).map(|i| {
+        if i == 0 || !close[i].is_finite() || !close[i - 1].is_finite() || !volume[i].is_finite() {
+            f64::NAN
+        } else if close[i] > close[i - 1] {
+            volume[i]
+        } else if close[i] < close[i - 1] {
+            -volume[i]
+        } else {
+            0.0
+        }
+    }));
+
+    rolling_sum(&signed, window)
+}
+
+/// Rolling VWAP: window sum of `price * volume` over the window sum of `volume`
+///
+/// Bars where either price or volume is NaN/inf are skipped together, giving NaN at their
+/// own index, so the window holds the last `window` bars with both present. Outputs are NaN
+/// until the window is full and while every bar in it has zero volume; that case is
+/// detected by counting the non-zero volumes rather than by testing the sliding sum, which
+/// can keep a rounding residue after large volumes leave the window.
+pub fn rolling_vwap(price: &Array1<f64>, volume: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    check_same_length("price", price, "volume", volume)?;
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let mut result = Array1::from_elem(price.len(), f64::NAN);
+    let mut queue = VecDeque::with_capacity(window + 1);
+    let (mut sum_pv, mut sum_v, mut traded) = (0.0, 0.0, 0usize);
+
+    for (i, (&p, &v)) in price.iter().zip(volume.iter()).enumerate() {
+        if !p.is_finite() || !v.is_finite() {
+            continue;
+        }
+        queue.push_back((p * v, v));
+        sum_pv += p * v;
+        sum_v += v;
+        traded += (v != 0.0) as usize;
+        if queue.len() > window {
+            let (old_pv, old_v) = queue.pop_front().unwrap();
+            sum_pv -= old_pv;
+            sum_v -= old_v;
+            traded -= (old_v != 0.0) as usize;
+        }
+        if queue.len() == window && traded > 0 && sum_v != 0.0 {
+            result[i] = sum_pv / sum_v;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rolling Kelly fraction `fraction * mean / variance` of per-period returns
+///
+/// This is the continuous-time Kelly bet for a single asset: it assumes returns are i.i.d.
+/// with small moments, so the window mean and population variance (the `rolling_mean` and
+/// squared `rolling_std` of the last `window` finite returns) stand in for the true drift
+/// and variance. Estimation error makes full Kelly aggressive, and `fraction` scales it
+/// down (e.g. `0.5` for half Kelly). `cap`, if given, clamps the result to `[-cap, cap]`.
+/// Warm-up windows and windows with zero variance are NaN.
+pub fn rolling_kelly(returns: &Array1<f64>, window: usize, fraction: f64, cap: Option<f64>) -> Result<Array1<f64>> {
+    if fraction.is_nan() || fraction <= 0.0 {
+        return Err(ExprError::InvalidParameter(format!("fraction must be positive, got {}", fraction)));
+    }
+    if let Some(c) = cap {
+        if c.is_nan() || c <= 0.0 {
+            return Err(ExprError::InvalidParameter(format!("cap must be positive, got {}", c)));
+        }
+    }
+
+    let mean = rolling_mean(returns, window)?;
+    let std = rolling_std(returns, window, 0)?;
+    Ok(Array1::from_iter(mean.iter().zip(std.iter()).map(|(&m, &s)| {
+        let variance = s * s;
+        if m.is_nan() || variance.is_nan() || variance <= 0.0 {
+            return f64::NAN;
+        }
+        let f = fraction * m / variance;
+        match cap {
+            Some(c) => f.clamp(-c, c),
+            None => f,
+        }
+    })))
+}
+
+/// Rolling signal-to-noise ratio `|mean| / std` over the last `window` finite values
+///
+/// Uses `rolling_mean` and the population `rolling_std`, so it is the window t-statistic
+/// without the `sqrt(window)` factor. NaN during warm-up and where the std is zero relative
+/// to the mean (see `is_near_zero`), as for the z-scores.
+pub fn rolling_snr(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let mean = rolling_mean(data, window)?;
+    let std = rolling_std(data, window, 0)?;
+    Ok(Array1::from_iter(mean.iter().zip(std.iter()).map(|(&m, &s)| {
+        if m.is_nan() || s.is_nan() || is_near_zero(s, m) { f64::NAN } else { m.abs() / s }
+    })))
+}
+
+/// Current drawdown `price / peak - 1` from the `rolling_max` of the last `window` finite prices
+///
+/// Always `<= 0`, and 0 at a new window high. Unlike `rolling_drawdown_duration` this is the
+/// state at each bar, not a summary of the window. NaN during warm-up, at NaN/inf prices
+/// and where the peak is not positive.
+pub fn rolling_current_drawdown(prices: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let peak = rolling_max(prices, window)?;
+    Ok(Array1::from_iter(prices.iter().zip(peak.iter()).map(|(&p, &m)| {
+        if p.is_finite() && m > 0.0 { p / m - 1.0 } else { f64::NAN }
+    })))
+}
+
+/// Worst peak-to-trough decline `price / running peak - 1` over the whole series
+///
+/// Always `<= 0`: a series that never falls below its running `cummax` gives 0. NaN/inf
+/// prices, including any leading ones, are skipped, as are bars whose peak is not
+/// positive. NaN when no bar qualifies.
+pub fn max_drawdown(prices: &Array1<f64>) -> f64 {
+    let peak = cummax(prices);
+    prices
+        .iter()
+        .zip(peak.iter())
+        .filter(|(p, m)| p.is_finite() && **m > 0.0)
+        .map(|(&p, &m)| p / m - 1.0)
+        .fold(f64::NAN, f64::min)
+}
+
+/// Risk measure in the denominator of `rolling_risk_adjusted`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RiskDenominator {
+    /// Population std of returns (Sharpe)
+    Std,
+    /// Downside deviation below `mar` (Sortino)
+    Downside,
+    /// Median absolute deviation scaled by 1.4826 to be comparable with a std
+    Mad,
+}
+
+impl std::str::FromStr for RiskDenominator {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "std" => Ok(RiskDenominator::Std),
+            "downside" => Ok(RiskDenominator::Downside),
+            "mad" => Ok(RiskDenominator::Mad),
+            _ => Err(ExprError::InvalidParameter(format!(
+                "unknown risk denominator '{}', expected one of: std, downside, mad",
+                s
+            ))),
+        }
+    }
+}
+
+/// Annualized rolling mean excess return over a configurable risk measure
+///
+/// Every term uses the last `window` finite returns, like `rolling_sum`: the mean excess
+/// return (`returns - rf`, with `rf` per period), the std, the downside deviation
+/// `sqrt(mean(min(r - mar, 0)²))` or the scaled MAD. The ratio is scaled by
+/// `sqrt(ann_factor)`. Windows with a zero denominator are NaN.
+pub fn rolling_risk_adjusted(
+    returns: &Array1<f64>,
+    window: usize,
+    denom: RiskDenominator,
+    ann_factor: f64,
+    rf: f64,
+    mar: f64,
+) -> Result<Array1<f64>> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+    if ann_factor.is_nan() || ann_factor <= 0.0 {
+        return Err(ExprError::InvalidParameter(format!("ann_factor must be positive, got {}", ann_factor)));
+    }
+
+    let mean_excess = rolling_sum(&returns.mapv(|r| r - rf), window)? / window as f64;
+    let risk = match denom {
+        RiskDenominator::Std => rolling_std(returns, window, 0)?,
+        RiskDenominator::Downside => rolling_downside_dev(returns, window, mar)?,
+        RiskDenominator::Mad => rolling_median_mad(returns, window)?.1 * 1.4826,
+    };
+
+    let scale = ann_factor.sqrt();
+    Ok(Array1::from_iter(mean_excess.iter().zip(risk.iter()).map(|(&m, &d)| {
+        if d > 0.0 { m / d * scale } else { f64::NAN }
+    })))
+}
+
+/// Rolling downside deviation `sqrt(sum(min(r - mar, 0)²) / window)` below a minimum return
+///
+/// Returns at or above `mar` contribute zero, but still count in the denominator, which is
+/// always the full window of the last `window` finite returns (the conventional Sortino
+/// definition). NaN in warm-up; a window with no shortfall gives 0.0.
+pub fn rolling_downside_dev(returns: &Array1<f64>, window: usize, mar: f64) -> Result<Array1<f64>> {
+    let shortfall = returns.mapv(|r| if r.is_finite() { (r - mar).min(0.0).powi(2) } else { f64::NAN });
+    Ok((rolling_sum(&shortfall, window)? / window as f64).mapv(f64::sqrt))
+}
+
+/// Annualized rolling Sortino ratio `mean(r - mar) / downside_dev * sqrt(periods_per_year)`
+///
+/// `rolling_risk_adjusted` with the downside denominator and `rf = mar`. NaN in warm-up and
+/// where the window has no shortfall below `mar`.
+pub fn rolling_sortino(returns: &Array1<f64>, window: usize, mar: f64, periods_per_year: f64) -> Result<Array1<f64>> {
+    rolling_risk_adjusted(returns, window, RiskDenominator::Downside, periods_per_year, mar, mar)
+}
+
+/// Annualized rolling Sharpe ratio `mean / std * sqrt(periods_per_year)` with zero risk-free rate
+///
+/// `rolling_risk_adjusted` with the population std: the last `window` finite returns, NaN
+/// in warm-up and where the window std is zero.
+pub fn rolling_sharpe(returns: &Array1<f64>, window: usize, periods_per_year: f64) -> Result<Array1<f64>> {
+    rolling_risk_adjusted(returns, window, RiskDenominator::Std, periods_per_year, 0.0, 0.0)
+}
+
+/// Fraction of strictly positive values in each trailing window of `window` bars
+///
+/// NaN/inf values are ignored, and zeros are ignored too unless `include_zeros` is set, in
+/// which case they count as non-positive observations. Windows with no eligible values and
+/// the first `window - 1` outputs are NaN.
+pub fn rolling_up_fraction(data: &Array1<f64>, window: usize, include_zeros: bool) -> Result<Array1<f64>> {
+    if window < 1 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()));
+    }
+
+    let classify = |x: f64| -> (usize, usize) {
+        if !x.is_finite() || (x == 0.0 && !include_zeros) {
+            (0, 0)
+        } else {
+            (usize::from(x > 0.0), 1)
+        }
+    };
+
+    let n = data.len();
+    let mut result = Array1::zeros(n);
+    let mut up = 0usize;
+    let mut eligible = 0usize;
+
+    for i in 0..n {
+        let (u, e) = classify(data[i]);
+        up += u;
+        eligible += e;
+
+        if i >= window {
+            let (u, e) = classify(data[i - window]);
+            up -= u;
+            eligible -= e;
+        }
+
+        result[i] = if i + 1 < window || eligible == 0 {
+            f64::NAN
+        } else {
+            up as f64 / eligible as f64
+        };
+    }
+
+    Ok(result)
+}
+
+/// Sign changes within each trailing window of `window` bars
+///
+/// NaN/inf values and exact zeros are skipped, so a crossing is a sign flip between
+/// consecutive nonzero finite values, counted when both lie in the window. With `rate` the
+/// count is divided by the number of such consecutive pairs in the window (NaN when there
+/// are none). The first `window - 1` outputs are NaN. Updates are O(1) per step.
+pub fn rolling_zero_crossings(data: &Array1<f64>, window: usize, rate: bool) -> Result<Array1<f64>> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+
+    let n = data.len();
+    let mut result = Array1::from_elem(n, f64::NAN);
+    // (index, previous nonzero index, is a crossing) for each consecutive nonzero pair
+    let mut pairs: VecDeque<(usize, usize, bool)> = VecDeque::new();
+    let mut crossings = 0usize;
+    let mut prev: Option<usize> = None;
+
+    for i in 0..n {
+        let x = data[i];
+        if x.is_finite() && x != 0.0 {
+            if let Some(j) = prev {
+                let crossed = (x > 0.0) != (data[j] > 0.0);
+                crossings += usize::from(crossed);
+                pairs.push_back((i, j, crossed));
+            }
+            prev = Some(i);
+        }
+
+        let start = (i + 1).saturating_sub(window);
+        // Pairs are ordered by both indices, so only the front can straddle the window start
+        while let Some(&(_, j, crossed)) = pairs.front() {
+            if j >= start {
+                break;
+            }
+            crossings -= usize::from(crossed);
+            pairs.pop_front();
+        }
+
+        if i + 1 >= window {
+            result[i] = if !rate {
+                crossings as f64
+            } else if pairs.is_empty() {
+                f64::NAN
+            } else {
+                crossings as f64 / pairs.len() as f64
+            };
+        }
+    }
+
+    Ok(result)
+}
+
+/// Cross-sectional (per-row) population std of a `(time, assets)` panel, ignoring NaN/inf
+///
+/// Rows without any finite value are NaN. With `smooth_window`, the dispersion series is
+/// passed through `rolling_mean`.
+pub fn cs_dispersion(data: &Array2<f64>, smooth_window: Option<usize>) -> Result<Array1<f64>> {
+    if data.ncols() == 0 {
+        return Err(ExprError::InvalidParameter("panel must have at least one column".into()));
+    }
+
+    let dispersion = Array1::from_iter(data.rows().into_iter().map(|row| {
+        let valid: Vec<f64> = row.iter().copied().filter(|x| x.is_finite()).collect();
+        if valid.is_empty() {
+            return f64::NAN;
+        }
+        let count = valid.len() as f64;
+        let mean = valid.iter().sum::<f64>() / count;
+        (valid.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / count).sqrt()
+    }));
+
+    match smooth_window {
+        Some(window) => rolling_mean(&dispersion, window),
+        None => Ok(dispersion),
+    }
+}
+
+/// Weighted mid-ranks: the weight of all smaller values plus half the weight of the ties
+///
+/// With equal weights this is an affine map of the ordinary average ranks, so a weighted
+/// Pearson correlation of these ranks reduces to Spearman's.
+fn weighted_ranks(values: &[f64], weights: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut below = 0.0;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let tied: f64 = order[start..end].iter().map(|&j| weights[j]).sum();
+        for &j in &order[start..end] {
+            ranks[j] = below + 0.5 * tied;
+        }
+        below += tied;
+        start = end;
+    }
+    ranks
+}
+
+/// Per-row rank across the columns of a `(time, assets)` panel, scaled to `[0, 1]`
+///
+/// Within each row the finite values get average ranks `1..=k` (ties share their mean
+/// rank), mapped by `(rank - 1) / (k - 1)` so the lowest is 0 and the highest 1; a row with
+/// a single finite value gives it 0.5. NaN/inf entries, and rows without finite values,
+/// stay NaN.
+pub fn cs_rank(data: &Array2<f64>) -> Array2<f64> {
+    cs_rank_with(data, RankMethod::Average)
+}
+
+/// `cs_rank` with an explicit tie-breaking method
+///
+/// `Min`/`Max` give every tied value the lowest/highest of their positions, and `First`
+/// breaks ties by column order, so tied entries get distinct ranks.
+pub fn cs_rank_with(data: &Array2<f64>, method: RankMethod) -> Array2<f64> {
+    let mut result = Array2::from_elem(data.dim(), f64::NAN);
+    for (i, row) in data.rows().into_iter().enumerate() {
+        let mut cols: Vec<usize> = (0..row.len()).filter(|&j| row[j].is_finite()).collect();
+        if cols.len() == 1 {
+            result[[i, cols[0]]] = 0.5;
+            continue;
+        }
+        // Stable, so equal values stay in column order for `First`
+        cols.sort_by(|&a, &b| row[a].total_cmp(&row[b]));
+        let scale = cols.len() as f64 - 1.0;
+        let mut start = 0;
+        while start < cols.len() {
+            let end = start + cols[start..].iter().take_while(|&&j| row[j] == row[cols[start]]).count();
+            for (offset, &j) in cols[start..end].iter().enumerate() {
+                // 0-based positions `start..end` of the tie block
+                let position = match method {
+                    RankMethod::Average => (start + end - 1) as f64 / 2.0,
+                    RankMethod::Min => start as f64,
+                    RankMethod::Max => (end - 1) as f64,
+                    RankMethod::First => (start + offset) as f64,
+                };
+                result[[i, j]] = position / scale;
+            }
+            start = end;
+        }
+    }
+    result
+}
+
+/// Assign each row's finite entries of a `(time, assets)` panel to `q` rank buckets `0..q`
+///
+/// With `k` finite values in a row, the value at 0-based sorted position `p` goes to bucket
+/// `floor(p * q / k)`, so bucket sizes differ by at most one and bucket `q - 1` holds the
+/// largest values. Tied values use the mean position of their tie block, as in `cs_rank`,
+/// so they always share a bucket. NaN/inf entries get -1.
+pub fn cs_bucket(data: &Array2<f64>, q: usize) -> Result<Array2<i64>> {
+    if q == 0 {
+        return Err(ExprError::InvalidParameter("q must be at least 1".into()));
+    }
+    let mut result = Array2::from_elem(data.dim(), -1i64);
+    for (i, row) in data.rows().into_iter().enumerate() {
+        let mut cols: Vec<usize> = (0..row.len()).filter(|&j| row[j].is_finite()).collect();
+        cols.sort_by(|&a, &b| row[a].total_cmp(&row[b]));
+        let k = cols.len() as f64;
+        let mut start = 0;
+        while start < cols.len() {
+            let end = start + cols[start..].iter().take_while(|&&j| row[j] == row[cols[start]]).count();
+            let position = (start + end - 1) as f64 / 2.0;
+            let bucket = ((position * q as f64 / k) as i64).min(q as i64 - 1);
+            for &j in &cols[start..end] {
+                result[[i, j]] = bucket;
+            }
+            start = end;
+        }
+    }
+    Ok(result)
+}
+
+/// Rescale a cross-section so its finite entries have gross exposure `sum(|x|) = a`
+///
+/// NaN/inf entries are excluded from the sum and come out NaN. If the absolute sum is zero
+/// (including when nothing is finite) every output is NaN.
+pub fn scale_to_gross(data: &Array1<f64>, a: f64) -> Result<Array1<f64>> {
+    if !a.is_finite() {
+        return Err(ExprError::InvalidParameter(format!("a must be finite, got {}", a)));
+    }
+    let gross: f64 = data.iter().filter(|v| v.is_finite()).map(|v| v.abs()).sum();
+    Ok(data.mapv(|v| if v.is_finite() && gross > 0.0 { v / gross * a } else { f64::NAN }))
+}
+
+/// Subtract each row's mean of finite values across the columns of a `(time, assets)` panel
+///
+/// NaN/inf entries are left out of the mean and come out NaN; rows without finite values
+/// stay NaN.
+pub fn cs_demean(data: &Array2<f64>) -> Array2<f64> {
+    let mut result = data.mapv(|v| if v.is_finite() { v } else { f64::NAN });
+    for mut row in result.rows_mut() {
+        let (sum, count) = row.iter().filter(|v| v.is_finite()).fold((0.0, 0usize), |(s, c), &v| (s + v, c + 1));
+        if count > 0 {
+            let mean = sum / count as f64;
+            row.mapv_inplace(|v| v - mean);
+        }
+    }
+    result
+}
+
+/// Turn a raw `(time, assets)` factor panel into per-row weights: `cs_rank`, then
+/// `cs_demean`, then `scale_to_gross` to unit gross exposure, each step optional
+///
+/// Steps run in that order and each ignores NaN/inf entries, which come out NaN; rows
+/// without finite values stay NaN. Scaling a row whose finite entries are all zero, such as
+/// a single-asset row after ranking and demeaning, also gives NaN.
+pub fn cs_normalize(data: &Array2<f64>, rank: bool, demean: bool, scale: bool) -> Result<Array2<f64>> {
+    let mut result = if rank { cs_rank(data) } else { data.clone() };
+    if demean {
+        result = cs_demean(&result);
+    }
+    if scale {
+        for mut row in result.rows_mut() {
+            let scaled = scale_to_gross(&row.to_owned(), 1.0)?;
+            row.assign(&scaled);
+        }
+    }
+    Ok(result)
+}
+
+/// Sorted copy of the finite values of `data`
+fn sorted_finite(data: &Array1<f64>) -> Vec<f64> {
+    let mut sorted: Vec<f64> = data.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted
+}
+
+/// Clamp `data` to `[lower_q, upper_q]` quantiles of its finite values
+///
+/// Quantiles interpolate like numpy's default. ±inf count as outliers and are clamped to
+/// the bounds; NaN passes through. Without finite values the input is returned unchanged.
+pub fn winsorize(data: &Array1<f64>, lower_q: f64, upper_q: f64) -> Result<Array1<f64>> {
+    if !(0.0..=1.0).contains(&lower_q) || !(0.0..=1.0).contains(&upper_q) || lower_q > upper_q {
+        return Err(ExprError::InvalidParameter(format!(
+            "quantiles must satisfy 0 <= lower_q <= upper_q <= 1, got {} and {}", lower_q, upper_q
+        )));
+    }
+    let sorted = sorted_finite(data);
+    if sorted.is_empty() {
+        return Ok(data.clone());
+    }
+    let (lo, hi) = (sorted_quantile(&sorted, lower_q), sorted_quantile(&sorted, upper_q));
+    Ok(data.mapv(|v| if v.is_nan() { v } else { v.clamp(lo, hi) }))
+}
+
+/// Clamp `data` to `median ± k * MAD` of its finite values
+///
+/// The MAD is unscaled (multiply `k` by 1.4826 for normal-std units). Like
+/// `rolling_robust_clean`, a zero MAD leaves the input unchanged rather than collapsing
+/// everything onto the median. ±inf are clamped and NaN passes through.
+pub fn winsorize_mad(data: &Array1<f64>, k: f64) -> Result<Array1<f64>> {
+    if k.is_nan() || k <= 0.0 {
+        return Err(ExprError::InvalidParameter(format!("k must be positive, got {}", k)));
+    }
+    let mut sorted = sorted_finite(data);
+    if sorted.is_empty() {
+        return Ok(data.clone());
+    }
+    let center = sorted_median(&sorted);
+    sorted.iter_mut().for_each(|x| *x = (*x - center).abs());
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mad = sorted_median(&sorted);
+    if mad == 0.0 {
+        return Ok(data.clone());
+    }
+    Ok(data.mapv(|v| if v.is_nan() { v } else { v.clamp(center - k * mad, center + k * mad) }))
+}
+
+/// Residual of a cross-sectional OLS of `factor` on an intercept plus the columns of `exposures`
+///
+/// Rows where the factor or any exposure is NaN/inf are left out of the fit and come out
+/// NaN. The fit solves the normal equations with `ols_fit`, which is accurate for the handful
+/// of style columns this is meant for but squares the condition number, so near-collinear
+/// exposures lose precision. When the usable rows cannot identify the coefficients (no more
+/// rows than regressors, or collinear columns including a constant exposure) every output is
+/// NaN rather than an arbitrary least-squares solution.
+pub fn neutralize(factor: &Array1<f64>, exposures: &Array2<f64>) -> Result<Array1<f64>> {
+    let (n, k) = exposures.dim();
+    if n != factor.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "factor has length {} but exposures has {} rows",
+            factor.len(),
+            n
+        )));
+    }
+
+    let rows: Vec<usize> = (0..n)
+        .filter(|&i| factor[i].is_finite() && exposures.row(i).iter().all(|v| v.is_finite()))
+        .collect();
+    let mut design = Array2::ones((rows.len(), k + 1));
+    for (r, &i) in rows.iter().enumerate() {
+        design.slice_mut(s![r, 1..]).assign(&exposures.row(i));
+    }
+    let target = Array1::from_iter(rows.iter().map(|&i| factor[i]));
+
+    let mut result = Array1::from_elem(n, f64::NAN);
+    if let Some(fit) = ols_fit(&design, &target) {
+        let fitted = design.dot(&Array1::from_vec(fit.coef));
+        for (r, &i) in rows.iter().enumerate() {
+            result[i] = target[r] - fitted[r];
+        }
+    }
+    Ok(result)
+}
+
+/// Alpha101 `signedpower(x, a) = sign(x) * |x|^a`, elementwise
+///
+/// NaN and ±inf pass through unchanged, and zero maps to zero (`sign(0) = 0`), so a
+/// negative `a` does not turn zeros into infinities.
+pub fn signed_power(data: &Array1<f64>, a: f64) -> Array1<f64> {
+    data.mapv(|v| if !v.is_finite() || v == 0.0 { v } else { v.signum() * v.abs().powf(a) })
+}
+
+/// Alpha101 `sign(x)`: -1, 0 or +1 elementwise
+///
+/// As with `signed_power`, ±inf keep their sign and NaN passes through; both zeros give 0.
+pub fn sign(data: &Array1<f64>) -> Array1<f64> {
+    data.mapv(|v| if v.is_nan() || v == 0.0 { v.abs() } else { v.signum() })
+}
+
+/// +1 where the series turns positive after being negative, -1 where it turns negative
+/// after being positive, 0 otherwise
+///
+/// The comparison is against the last non-zero sign, so `-1, 0, 2` is an up-cross at the
+/// `2` and `1, 0, 3` is no cross; zeros and NaN inputs never cross. NaN inputs give NaN
+/// and leave the remembered sign untouched.
+pub fn sign_change(data: &Array1<f64>) -> Array1<f64> {
+    let mut last = 0.0;
+    sign(data).mapv(|s| {
+        if s.is_nan() {
+            return f64::NAN;
+        }
+        let cross = if s != 0.0 && last != 0.0 && s != last { s } else { 0.0 };
+        if s != 0.0 {
+            last = s;
+        }
+        cross
+    })
+}
+
+/// A series threaded through a chain of operators, each step returning a new `Factor`
+///
+/// An ergonomics layer rather than new math: every method calls the matching free
+/// function (`rolling_mean`, `rolling_rank`, `rolling_zscore`, `clip`, ...) on the wrapped
+/// values, so the data stays in Rust between steps and results are exactly those of the
+/// function calls in sequence.
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+#[derive(Clone, Debug)]
+pub struct Factor {
+    values: Array1<f64>,
+}
+
+impl Factor {
+    pub fn new(values: Array1<f64>) -> Self {
+        Factor { values }
+    }
+
+    pub fn values(&self) -> &Array1<f64> {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Array1<f64> {
+        self.values
+    }
+
+    pub fn rolling_mean(&self, window: usize) -> Result<Factor> {
+        Ok(Factor::new(rolling_mean(&self.values, window)?))
+    }
+
+    pub fn rolling_std(&self, window: usize, ddof: usize) -> Result<Factor> {
+        Ok(Factor::new(rolling_std(&self.values, window, ddof)?))
+    }
+
+    /// `rolling_rank` percentile over the last `window` bars
+    pub fn rank(&self, window: usize) -> Result<Factor> {
+        Ok(Factor::new(rolling_rank(&self.values, window, None)?))
+    }
+
+    pub fn zscore(&self, window: usize, ddof: usize, std_floor: f64) -> Result<Factor> {
+        Ok(Factor::new(rolling_zscore(&self.values, window, ddof, std_floor)?))
+    }
+
+    pub fn delta(&self, periods: usize) -> Result<Factor> {
+        Ok(Factor::new(delta(&self.values, periods)?))
+    }
+
+    pub fn clip(&self, lo: f64, hi: f64) -> Result<Factor> {
+        Ok(Factor::new(clip(&self.values, lo, hi)?))
+    }
+
+    pub fn sign(&self) -> Factor {
+        Factor::new(sign(&self.values))
+    }
+
+    pub fn fill_nan(&self, value: f64) -> Factor {
+        Factor::new(fill_non_finite(self.values.clone(), Some(value)))
+    }
+}
+
+/// Elementwise `a[i]` where `cond[i]` is true, else `b[i]`
+///
+/// NaNs in the chosen branch pass through; the other branch is never looked at.
+pub fn where_(cond: &Array1<bool>, a: &Array1<f64>, b: &Array1<f64>) -> Result<Array1<f64>> {
+    check_same_length("a", a, "b", b)?;
+    if cond.len() != a.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "cond has length {} but a has length {}", cond.len(), a.len()
+        )));
+    }
+    Ok(Array1::from_iter(cond.iter().zip(a.iter().zip(b.iter())).map(|(&c, (&x, &y))| if c { x } else { y })))
+}
+
+/// Elementwise comparison producing a mask for `where_`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Gt,
+    Lt,
+    Ge,
+    Le,
+}
+
+/// Compare `a` and `b` elementwise; any comparison involving NaN is false
+pub fn compare(a: &Array1<f64>, b: &Array1<f64>, op: Comparison) -> Result<Array1<bool>> {
+    check_same_length("a", a, "b", b)?;
+    Ok(Array1::from_iter(a.iter().zip(b.iter()).map(|(&x, &y)| match op {
+        Comparison::Gt => x > y,
+        Comparison::Lt => x < y,
+        Comparison::Ge => x >= y,
+        Comparison::Le => x <= y,
+    })))
+}
+
+/// Map finite values through `f` and every NaN/inf to NaN
+fn map_finite(data: &Array1<f64>, f: impl Fn(f64) -> f64) -> Array1<f64> {
+    data.mapv(|v| if v.is_finite() { f(v) } else { f64::NAN })
+}
+
+/// Clamp each value to `[lo, hi]`; NaN/inf inputs give NaN
+pub fn clip(data: &Array1<f64>, lo: f64, hi: f64) -> Result<Array1<f64>> {
+    if lo.is_nan() || hi.is_nan() || lo > hi {
+        return Err(ExprError::InvalidParameter(format!("clip bounds [{}, {}] must satisfy lo <= hi", lo, hi)));
+    }
+    Ok(map_finite(data, |v| v.clamp(lo, hi)))
+}
+
+/// Logistic `1 / (1 + e^-x)` into `(0, 1)`, written to avoid overflow for large `|x|`
+pub fn sigmoid(data: &Array1<f64>) -> Array1<f64> {
+    map_finite(data, |v| if v >= 0.0 { 1.0 / (1.0 + (-v).exp()) } else { let e = v.exp(); e / (1.0 + e) })
+}
+
+/// `tanh(x / scale)`: near-linear for `|x| << scale`, saturating at ±1 beyond it
+pub fn tanh_squash(data: &Array1<f64>, scale: f64) -> Result<Array1<f64>> {
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(ExprError::InvalidParameter(format!("tanh scale {} must be finite and positive", scale)));
+    }
+    Ok(map_finite(data, |v| (v / scale).tanh()))
+}
+
+/// Weighted Pearson correlation; NaN when either weighted variance is not positive
+fn weighted_pearson(x: &[f64], y: &[f64], weights: &[f64]) -> f64 {
+    let total: f64 = weights.iter().sum();
+    let mean = |v: &[f64]| v.iter().zip(weights).map(|(a, w)| a * w).sum::<f64>() / total;
+    let (mx, my) = (mean(x), mean(y));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for ((a, b), w) in x.iter().zip(y).zip(weights) {
+        cov += w * (a - mx) * (b - my);
+        var_x += w * (a - mx) * (a - mx);
+        var_y += w * (b - my) * (b - my);
+    }
+    if var_x <= 0.0 || var_y <= 0.0 {
+        f64::NAN
+    } else {
+        cov / (var_x.sqrt() * var_y.sqrt())
+    }
+}
+
+/// Per-row weighted Spearman IC between a factor and forward returns, `(time, assets)` panels
+///
+/// Assets with a NaN/inf factor, return or weight, or a zero weight, are excluded. Ranks
+/// are weighted mid-ranks (`weighted_ranks`) and are correlated with the same weights, so
+/// equal weights give the ordinary Spearman IC. Rows with fewer than two usable assets or
+/// no rank dispersion are NaN.
+pub fn weighted_cs_rank_ic(factor: &Array2<f64>, forward_ret: &Array2<f64>, weights: &Array2<f64>) -> Result<Array1<f64>> {
+    for (name, other) in [("forward_ret", forward_ret), ("weights", weights)] {
+        if other.dim() != factor.dim() {
+            return Err(ExprError::LengthMismatch(format!(
+                "factor has shape {:?} but {} has shape {:?}", factor.dim(), name, other.dim()
+            )));
+        }
+    }
+    if weights.iter().any(|&w| w < 0.0) {
+        return Err(ExprError::InvalidParameter("weights must be non-negative".into()));
+    }
+
+    Ok(Array1::from_iter((0..factor.nrows()).map(|t| {
+        let (mut f, mut r, mut w) = (Vec::new(), Vec::new(), Vec::new());
+        for j in 0..factor.ncols() {
+            let (fv, rv, wv) = (factor[[t, j]], forward_ret[[t, j]], weights[[t, j]]);
+            if fv.is_finite() && rv.is_finite() && wv.is_finite() && wv > 0.0 {
+                f.push(fv);
+                r.push(rv);
+                w.push(wv);
+            }
+        }
+        if f.len() < 2 {
+            return f64::NAN;
+        }
+        weighted_pearson(&weighted_ranks(&f, &w), &weighted_ranks(&r, &w), &w)
+    })))
+}
+
+/// Per-row Spearman rank IC between a factor and forward returns, `(time, assets)` panels
+///
+/// `weighted_cs_rank_ic` with equal weights: ties get average ranks, assets with a NaN/inf
+/// factor or return are dropped, and rows with fewer than two valid pairs are NaN.
+pub fn rank_ic(factor: &Array2<f64>, forward_ret: &Array2<f64>) -> Result<Array1<f64>> {
+    weighted_cs_rank_ic(factor, forward_ret, &Array2::ones(factor.dim()))
+}
+
+/// Randomly permute the values within each row of a `(time, assets)` panel, for
+/// permutation tests of cross-sectional statistics such as `rank_ic`
+///
+/// Rows are shuffled independently, in order, from one `StdRng` seeded with `seed`, so the
+/// same seed always gives the same permutations (for a given `rand` release). NaN/inf
+/// entries are shuffled like any other value: each row keeps its count of NaNs but not
+/// their positions.
+pub fn shuffle_rows(data: &Array2<f64>, seed: u64) -> Array2<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut result = data.clone();
+    for mut row in result.rows_mut() {
+        let mut values = row.to_vec();
+        values.shuffle(&mut rng);
+        row.assign(&Array1::from_vec(values));
+    }
+    result
+}
+
+/// Largest eigenvalue of a symmetric positive semi-definite matrix by power iteration
+///
+/// Stops once the Rayleigh quotient changes by less than `1e-12` relative, or after 500
+/// iterations.
+fn top_eigenvalue(matrix: &Array2<f64>) -> f64 {
+    let k = matrix.nrows();
+    // A non-uniform start avoids being orthogonal to the top eigenvector of common structures
+    let mut v = Array1::from_iter((0..k).map(|j| 1.0 + j as f64 / k as f64));
+    v /= v.dot(&v).sqrt();
+    let mut lambda = 0.0;
+
+    for _ in 0..500 {
+        let w = matrix.dot(&v);
+        let norm = w.dot(&w).sqrt();
+        if norm == 0.0 {
+            return 0.0;
+        }
+        let next = v.dot(&w);
+        v = w / norm;
+        if (next - lambda).abs() <= 1e-12 * next.abs() {
+            return next;
+        }
+        lambda = next;
+    }
+
+    lambda
+}
+
+/// Rolling share of total variance explained by the first principal component
+///
+/// For each trailing window of `window` rows of a `(time, assets)` panel, assets with any
+/// NaN/inf in the window are dropped and the population covariance matrix of the rest is
+/// formed; the output is its top eigenvalue (power iteration) over its trace. Windows with
+/// fewer than two usable assets or zero total variance are NaN, as are the first
+/// `window - 1` rows. Costs O(n * window * assets²) plus the eigen solve per row.
+pub fn rolling_pc1_share(data: &Array2<f64>, window: usize) -> Result<Array1<f64>> {
+    if window < 2 {
+        return Err(ExprError::InvalidPeriod("Window size must be at least 2".into()));
+    }
+
+    let n = data.nrows();
+    let mut result = Array1::from_elem(n, f64::NAN);
+
+    for i in (window - 1)..n {
+        let block = data.slice(s![i + 1 - window..=i, ..]);
+        let usable: Vec<usize> = (0..data.ncols()).filter(|&j| block.column(j).iter().all(|v| v.is_finite())).collect();
+        if usable.len() < 2 {
+            continue;
+        }
+
+        let mut centered = block.select(Axis(1), &usable);
+        let means = centered.mean_axis(Axis(0)).unwrap();
+        centered -= &means;
+        let cov = centered.t().dot(&centered) / window as f64;
+
+        let trace = cov.diag().sum();
+        if trace > 0.0 {
+            result[i] = top_eigenvalue(&cov) / trace;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Cap on the column count of `rolling_pairwise_corr`, whose output holds `n * k²` values
+const MAX_PAIRWISE_COLUMNS: usize = 64;
+
+/// Every pairwise rolling correlation of a `(time, assets)` panel as a `(time, k, k)` array
+///
+/// Each pair runs its own `rolling_correlation` accumulators, so NaN handling and warm-up
+/// match the 1-D version exactly; the matrix is filled symmetrically. The diagonal is
+/// exactly 1.0 wherever the asset's own window is non-degenerate. Costs O(n * k²) time and
+/// `8 * n * k²` bytes, hence the `MAX_PAIRWISE_COLUMNS` limit.
+pub fn rolling_pairwise_corr(data: &Array2<f64>, window: usize, mode: PrecisionMode) -> Result<Array3<f64>> {
+    let (n, k) = data.dim();
+    if k > MAX_PAIRWISE_COLUMNS {
+        return Err(ExprError::InvalidParameter(format!(
+            "rolling_pairwise_corr supports at most {} columns, got {}",
+            MAX_PAIRWISE_COLUMNS, k
+        )));
+    }
+
+    let columns: Vec<Array1<f64>> = data.columns().into_iter().map(|c| c.to_owned()).collect();
+    let mut result = Array3::from_elem((n, k, k), f64::NAN);
+    for i in 0..k {
+        let own = rolling_correlation_with(&columns[i], &columns[i], window, mode)?;
+        result.slice_mut(s![.., i, i]).assign(&own.mapv(|r| if r.is_nan() { f64::NAN } else { 1.0 }));
+        for j in (i + 1)..k {
+            let corr = rolling_correlation_with(&columns[i], &columns[j], window, mode)?;
+            result.slice_mut(s![.., i, j]).assign(&corr);
+            result.slice_mut(s![.., j, i]).assign(&corr);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Per-column missingness summary of a `(time, assets)` panel
+///
+/// First/last valid indices are -1 for columns that are entirely NaN.
+#[derive(Debug, PartialEq)]
+pub struct NanReport {
+    pub nan_count: Array1<i64>,
+    pub longest_nan_run: Array1<i64>,
+    pub first_valid: Array1<i64>,
+    pub last_valid: Array1<i64>,
+}
+
+/// Single pass over each column counting NaNs, the longest consecutive NaN run and the
+/// first/last non-NaN observation
+pub fn nan_report(data: &Array2<f64>) -> NanReport {
+    let ncols = data.ncols();
+    let mut report = NanReport {
+        nan_count: Array1::zeros(ncols),
+        longest_nan_run: Array1::zeros(ncols),
+        first_valid: Array1::from_elem(ncols, -1),
+        last_valid: Array1::from_elem(ncols, -1),
+    };
+
+    for (j, column) in data.columns().into_iter().enumerate() {
+        let mut run = 0i64;
+        for (i, &v) in column.iter().enumerate() {
+            if v.is_nan() {
+                run += 1;
+                report.nan_count[j] += 1;
+                report.longest_nan_run[j] = report.longest_nan_run[j].max(run);
+            } else {
+                run = 0;
+                if report.first_valid[j] < 0 {
+                    report.first_valid[j] = i as i64;
+                }
+                report.last_valid[j] = i as i64;
+            }
+        }
+    }
+
+    report
+}
+
+/// Slew-limit a factor so it moves at most `max_change_per_bar` per bar
+///
+/// Each output steps from the previous output towards the raw value, clamped to
+/// `±max_change_per_bar`, which caps signal turnover. A NaN/inf input gives NaN and resets
+/// the limiter, so the next finite value is taken as is.
+pub fn cost_aware_smooth(factor: &Array1<f64>, max_change_per_bar: f64) -> Result<Array1<f64>> {
+    if max_change_per_bar.is_nan() || max_change_per_bar <= 0.0 {
+        return Err(ExprError::InvalidParameter("max_change_per_bar must be positive".into()));
+    }
+
+    let mut prev: Option<f64> = None;
+    Ok(factor.mapv(|x| {
+        if !x.is_finite() {
+            prev = None;
+            return f64::NAN;
+        }
+        let out = match prev {
+            Some(p) => p + (x - p).clamp(-max_change_per_bar, max_change_per_bar),
+            None => x,
+        };
+        prev = Some(out);
+        out
+    }))
+}
+
+/// Momentum over `lookback` scaled by the rolling volatility (with `ddof`) of one-period returns
+pub fn momentum(prices: &Array1<f64>, lookback: usize, invert: bool, ddof: usize) -> Result<Array1<f64>> {
+    check_window_fits("lookback", lookback, prices.len())?;
+    let returns = pct_change(prices, 1, ZeroPrev::Nan)?;
+    let momentum = pct_change(prices, lookback, ZeroPrev::Nan)?;
+    let vol = rolling_std(&returns, lookback, ddof)?;
+
+    // Calculate momentum/vol ratio with NaN handling
+    let sign = if invert { -1.0 } else { 1.0 };
+    Ok(Array1::from_iter(momentum.iter().zip(vol.iter()).map(|(&m, &v)| {
+        if m.is_nan() || m.is_infinite() || v.is_nan() || v.is_infinite() || is_near_zero(v, m) {
+            f64::NAN
+        } else {
+            sign * m / v
+        }
+    })))
+}
+
+/// Negated z-score of price against its rolling mean and std (with `ddof`, floored at `std_floor`)
+pub fn mean_reversion(prices: &Array1<f64>, lookback: usize, invert: bool, std_floor: f64, ddof: usize) -> Result<Array1<f64>> {
+    check_window_fits("lookback", lookback, prices.len())?;
+    let sign = if invert { 1.0 } else { -1.0 };
+    Ok(rolling_zscore(prices, lookback, ddof, std_floor)?.mapv(|z| sign * z))
+}
+
+/// Relative strength factor and its `(n, 3)` per-timeframe rank components
+///
+/// The timeframes are `max(lookback / 3, 1)`, `lookback` and `2 * lookback`, each ranked
+/// over `lookback` bars, so `lookback` must be at least 2.
+pub fn relative_strength(prices: &Array1<f64>, lookback: usize, invert: bool, nan_policy: NanPolicy) -> Result<(Array1<f64>, Array2<f64>)> {
+    if lookback < 2 {
+        return Err(ExprError::InvalidPeriod(format!(
+            "relative strength lookback {} is below the minimum of 2 (the rank window)", lookback
+        )));
+    }
+    check_window_fits("relative strength window (2 * lookback)", lookback * 2, prices.len())?;
+    let timeframes = [(lookback / 3).max(1), lookback, lookback * 2];
+    relative_strength_with(prices, lookback, &timeframes, &[0.5, 0.3, 0.2], invert, nan_policy)
+}
+
+/// `relative_strength` with custom momentum `timeframes` and their blend `weights`
+///
+/// Each timeframe's return is ranked over `lookback` bars as before. The weights must be
+/// finite with a positive sum and are normalized to sum to 1; the longest timeframe must
+/// fit in the series.
+pub fn relative_strength_with(
+    prices: &Array1<f64>,
+    lookback: usize,
+    timeframes: &[usize],
+    weights: &[f64],
+    invert: bool,
+    nan_policy: NanPolicy,
+) -> Result<(Array1<f64>, Array2<f64>)> {
+    if timeframes.len() != weights.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "timeframes has length {} but weights has length {}", timeframes.len(), weights.len()
+        )));
+    }
+    if timeframes.is_empty() || timeframes.contains(&0) {
+        return Err(ExprError::InvalidPeriod("timeframes must be non-empty and at least 1 bar".into()));
+    }
+    let total: f64 = weights.iter().sum();
+    if weights.iter().any(|w| !w.is_finite()) || total <= 0.0 {
+        return Err(ExprError::InvalidParameter("weights must be finite with a positive sum".into()));
+    }
+    check_window_fits("relative strength timeframe", *timeframes.iter().max().unwrap(), prices.len())?;
+    let weights: Vec<f64> = if total == 1.0 { weights.to_vec() } else { weights.iter().map(|w| w / total).collect() };
+    let mut ranks = Vec::with_capacity(timeframes.len());
+
+    for &tf in timeframes.iter() {
+        let mom = pct_change(prices, tf, ZeroPrev::Nan)?;
+        ranks.push(rolling_rank(&mom, lookback, None)?);
+    }
+
+    let mut result = combine_weighted(&ranks, &weights, nan_policy);
+
+    if invert {
+        result.mapv_inplace(|x: f64| -x);
+    }
+
+    let mut components = Array2::zeros((prices.len(), ranks.len()));
+    for (k, rank) in ranks.iter().enumerate() {
+        components.column_mut(k).assign(rank);
+    }
+
+    Ok((result, components))
+}
+
+/// Momentum and mean reversion factors for one price series
+///
+/// The two factors share no rolling statistic (momentum scales by the std of returns,
+/// reversion by the std of prices), so each is computed as by its own function; the
+/// saving is a single input copy and Python call. With the `parallel` feature the two
+/// kernels run on separate rayon threads.
+pub fn momentum_and_reversion(
+    prices: &Array1<f64>,
+    lookback: usize,
+    invert: bool,
+    std_floor: f64,
+    ddof: usize,
+) -> Result<(Array1<f64>, Array1<f64>)> {
+    #[cfg(feature = "parallel")]
+    let (mom, rev) = rayon::join(
+        || momentum(prices, lookback, invert, ddof),
+        || mean_reversion(prices, lookback, invert, std_floor, ddof),
+    );
+    #[cfg(not(feature = "parallel"))]
+    let (mom, rev) = (
+        momentum(prices, lookback, invert, ddof),
+        mean_reversion(prices, lookback, invert, std_floor, ddof),
+    );
+    Ok((mom?, rev?))
+}
+
+/// Alpha101 `ts_rank(x, d)` as in the reference implementation
+///
+/// `rankdata(window)[-1] / d`: the 1-based rank of the current value among the last `d`
+/// bars, ties averaged, divided by `d`, so outputs lie in `[1/d, 1]`. Any NaN/inf in the
+/// window gives NaN, as a pandas `rolling(d).apply` would. `rolling_rank` differs in two
+/// ways: ties take the highest position (`count(x <= current)`), and NaN/inf bars are
+/// dropped from the window, dividing by the finite count instead of `d`.
+pub fn alpha101_ts_rank(data: &Array1<f64>, window: usize) -> Result<Array1<f64>> {
+    let ranks = rolling_rank_with(data, window, window, None, RankMethod::Average)?;
+    Ok(apply_skipna(ranks, false, &[data], window))
+}
+
+/// Alpha101 factor #42: `-rank(std(high, 10)) * corr(high, volume, 10)`
+///
+/// The rank is the rolling 10-bar percentile of the std. NaN wherever either component is.
+pub fn alpha101_42(high: &Array1<f64>, volume: &Array1<f64>) -> Result<Array1<f64>> {
+    alpha101_42_with(high, volume, 10, 10, 10)
+}
+
+/// `alpha101_42` with the std, rank and correlation lookbacks as parameters
+///
+/// Each window must fit in the series, otherwise the result is `InvalidPeriod` rather
+/// than an all-NaN factor.
+pub fn alpha101_42_with(
+    high: &Array1<f64>,
+    volume: &Array1<f64>,
+    std_window: usize,
+    rank_window: usize,
+    corr_window: usize,
+) -> Result<Array1<f64>> {
+    check_same_length("high", high, "volume", volume)?;
+    for (name, window) in [("std_window", std_window), ("rank_window", rank_window), ("corr_window", corr_window)] {
+        check_window_fits(name, window, high.len())?;
+    }
+
+    let high_std = rolling_std(high, std_window, 0)?;
+    let vol_rank = rolling_rank(&high_std, rank_window, None)?;
+    let vol_price_corr = rolling_correlation(high, volume, corr_window)?;
+
+    Ok(Array1::from_iter(vol_rank.iter().zip(vol_price_corr.iter()).map(|(&r, &c)| {
+        if r.is_finite() && c.is_finite() { -r * c } else { f64::NAN }
+    })))
+}
+
+/// Alpha101 ids `compute_alphas` can dispatch to
+pub const ALPHA101_IDS: [u32; 1] = [42];
+
+/// Compute several Alpha101 factors from one set of OHLCV bars
+///
+/// Column `j` of the `(n, ids.len())` result is factor `ids[j]`. All ids are checked
+/// against `ALPHA101_IDS` before anything is computed, and the five inputs must have the
+/// same length. With the `parallel` feature the factors run on rayon threads.
+pub fn compute_alphas(
+    open: &Array1<f64>,
+    high: &Array1<f64>,
+    low: &Array1<f64>,
+    close: &Array1<f64>,
+    volume: &Array1<f64>,
+    ids: &[u32],
+) -> Result<Array2<f64>> {
+    for (name, x) in [("open", open), ("low", low), ("close", close), ("volume", volume)] {
+        check_same_length("high", high, name, x)?;
+    }
+    if let Some(id) = ids.iter().find(|id| !ALPHA101_IDS.contains(id)) {
+        return Err(ExprError::InvalidParameter(format!(
+            "alpha101 #{} is not implemented, supported ids: {:?}",
+            id, ALPHA101_IDS
+        )));
+    }
+
+    let columns = map_par(ids, |&id| match id {
+        42 => alpha101_42(high, volume),
+        _ => unreachable!("ids are validated against ALPHA101_IDS"),
+    })?;
+
+    let mut result = Array2::zeros((high.len(), ids.len()));
+    for (j, column) in columns.into_iter().enumerate() {
+        result.column_mut(j).assign(&column);
+    }
+    Ok(result)
+}
+
+/// Token of the factor formula language
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Symbol(char),
+}
+
+/// Split a formula into tokens, each tagged with its character offset for error messages
+fn tokenize(formula: &str) -> Result<Vec<(usize, Token)>> {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse()
+                .map_err(|_| ExprError::ParseError(format!("invalid number '{}' at position {}", text, start)))?;
+            tokens.push((start, Token::Number(value)));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((start, Token::Ident(chars[start..i].iter().collect())));
+        } else if "+-*/(),".contains(c) {
+            tokens.push((start, Token::Symbol(c)));
+            i += 1;
+        } else {
+            return Err(ExprError::ParseError(format!("unexpected character '{}' at position {}", c, start)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parsed factor formula
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    Input(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Recursive-descent parser over the grammar
+///
+/// ```text
+/// expr    := term (('+' | '-') term)*
+/// term    := unary (('*' | '/') unary)*
+/// unary   := '-' unary | primary
+/// primary := number | ident | ident '(' expr (',' expr)* ')' | '(' expr ')'
+/// ```
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+}
+
+impl Parser {
+    fn parse(formula: &str) -> Result<Expr> {
+        let mut parser = Parser { tokens: tokenize(formula)?, pos: 0, len: formula.chars().count() };
+        let expr = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some((at, token)) => Err(ExprError::ParseError(format!("unexpected {:?} at position {}", token, at))),
+        }
+    }
+
+    fn peek_symbol(&self) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Symbol(c))) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        if self.peek_symbol() == Some(symbol) {
+            self.pos += 1;
+            return Ok(());
+        }
+        let at = self.tokens.get(self.pos).map_or(self.len, |(at, _)| *at);
+        Err(ExprError::ParseError(format!("expected '{}' at position {}", symbol, at)))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut lhs = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek_symbol() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(op @ ('*' | '/')) = self.peek_symbol() {
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek_symbol() == Some('-') {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let (at, token) = match self.tokens.get(self.pos) {
+            Some(entry) => entry.clone(),
+            None => return Err(ExprError::ParseError(format!("unexpected end of formula at position {}", self.len))),
+        };
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Expr::Number(value)),
+            Token::Ident(name) if self.peek_symbol() == Some('(') => {
+                self.pos += 1;
+                let mut args = vec![self.expr()?];
+                while self.peek_symbol() == Some(',') {
+                    self.pos += 1;
+                    args.push(self.expr()?);
+                }
+                self.expect(')')?;
+                Ok(Expr::Call(name, args))
+            }
+            Token::Ident(name) => Ok(Expr::Input(name)),
+            Token::Symbol('(') => {
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Token::Symbol(c) => Err(ExprError::ParseError(format!("unexpected '{}' at position {}", c, at))),
+        }
+    }
+}
+
+/// Intermediate result of `eval_formula`: a constant or a series aligned with the inputs
+enum Value {
+    Scalar(f64),
+    Series(Array1<f64>),
+}
+
+impl Value {
+    fn into_series(self, n: usize) -> Array1<f64> {
+        match self {
+            Value::Scalar(v) => Array1::from_elem(n, v),
+            Value::Series(x) => x,
+        }
+    }
+}
+
+/// Functions callable from formulas, as `(name, arity)`
+const FORMULA_FUNCTIONS: [(&str, usize); 12] = [
+    ("rank", 1),
+    ("ts_rank", 2),
+    ("mean", 2),
+    ("ts_mean", 2),
+    ("std", 2),
+    ("ts_std", 2),
+    ("correlation", 3),
+    ("ts_corr", 3),
+    ("delay", 2),
+    ("ts_delay", 2),
+    ("delta", 2),
+    ("ts_delta", 2),
+];
+
+/// Window argument of a formula function: a constant positive integer
+fn window_arg(name: &str, value: Value) -> Result<usize> {
+    match value {
+        Value::Scalar(v) if v >= 1.0 && v.fract() == 0.0 => Ok(v as usize),
+        _ => Err(ExprError::ParseError(format!("{}: window must be a positive integer constant", name))),
+    }
+}
+
+/// Evaluate a parsed formula against named inputs that all have length `n`
+fn eval_formula(expr: &Expr, inputs: &HashMap<String, Array1<f64>>, n: usize) -> Result<Value> {
+    match expr {
+        Expr::Number(v) => Ok(Value::Scalar(*v)),
+        Expr::Input(name) => match inputs.get(name) {
+            Some(x) => Ok(Value::Series(x.clone())),
+            None => {
+                let mut known: Vec<&str> = inputs.keys().map(String::as_str).collect();
+                known.sort_unstable();
+                Err(ExprError::ParseError(format!("unknown input '{}', available: {}", name, known.join(", "))))
+            }
+        },
+        Expr::Neg(inner) => Ok(match eval_formula(inner, inputs, n)? {
+            Value::Scalar(v) => Value::Scalar(-v),
+            Value::Series(x) => Value::Series(-x),
+        }),
+        Expr::Binary(op, lhs, rhs) => {
+            let apply = |a: f64, b: f64| match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                // Division by zero gives NaN like the crate's other ratios
+                _ => {
+                    let q = a / b;
+                    if q.is_finite() || a.is_infinite() { q } else { f64::NAN }
+                }
+            };
+            Ok(match (eval_formula(lhs, inputs, n)?, eval_formula(rhs, inputs, n)?) {
+                (Value::Scalar(a), Value::Scalar(b)) => Value::Scalar(apply(a, b)),
+                (a, b) => {
+                    let (a, b) = (a.into_series(n), b.into_series(n));
+                    Value::Series(Array1::from_iter(a.iter().zip(b.iter()).map(|(&x, &y)| apply(x, y))))
+                }
+            })
+        }
+        Expr::Call(name, args) => {
+            let arity = match FORMULA_FUNCTIONS.iter().find(|(f, _)| f == name) {
+                Some(&(_, arity)) => arity,
+                None => {
+                    let names: Vec<&str> = FORMULA_FUNCTIONS.iter().map(|(f, _)| *f).collect();
+                    return Err(ExprError::ParseError(format!(
+                        "unknown function '{}', expected one of: {}", name, names.join(", ")
+                    )));
+                }
+            };
+            if args.len() != arity {
+                return Err(ExprError::ParseError(format!(
+                    "{} expects {} argument{}, got {}", name, arity, if arity == 1 { "" } else { "s" }, args.len()
+                )));
+            }
+            let mut values = args.iter().map(|a| eval_formula(a, inputs, n)).collect::<Result<Vec<_>>>()?;
+            let last = values.pop().unwrap();
+            let result = match name.as_str() {
+                // Every formula input is a single series, so a cross-sectional rank has no
+                // meaning; rank each bar against the history up to it, which never looks ahead
+                "rank" => expanding_rank(&last.into_series(n)),
+                "correlation" | "ts_corr" => {
+                    let window = window_arg(name, last)?;
+                    let y = values.pop().unwrap().into_series(n);
+                    let x = values.pop().unwrap().into_series(n);
+                    rolling_correlation(&x, &y, window)?
+                }
+                _ => {
+                    let window = window_arg(name, last)?;
+                    let x = values.pop().unwrap().into_series(n);
+                    match name.as_str() {
+                        "ts_rank" => rolling_rank(&x, window, None)?,
+                        "mean" | "ts_mean" => rolling_mean(&x, window)?,
+                        "std" | "ts_std" => rolling_std(&x, window, 0)?,
+                        "delay" | "ts_delay" => delay(&x, window)?,
+                        _ => delta(&x, window)?,
+                    }
+                }
+            };
+            Ok(Value::Series(result))
+        }
+    }
+}
+
+/// Parse `formula` and evaluate it against `inputs`, which must share one length
+pub fn eval_expr_arrays(formula: &str, inputs: &HashMap<String, Array1<f64>>) -> Result<Array1<f64>> {
+    let expr = Parser::parse(formula)?;
+    let mut lengths = inputs.iter().map(|(name, x)| (name, x.len()));
+    let n = match lengths.next() {
+        Some((first, n)) => {
+            if let Some((name, len)) = lengths.find(|&(_, len)| len != n) {
+                return Err(ExprError::LengthMismatch(format!(
+                    "{} has length {} but {} has length {}", first, n, name, len
+                )));
+            }
+            n
+        }
+        None => 0,
+    };
+    Ok(eval_formula(&expr, inputs, n)?.into_series(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn hybrid_zscore_blends_components() {
+        let data = Array1::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0, 6.0, 8.0, 7.0]);
+        let eq = rolling_zscore(&data, 3, 0, 0.0).unwrap();
+        let ew = ew_zscore(&data, 0.3, 0.0).unwrap();
+        let hybrid = hybrid_zscore(&data, 3, 0.3, 0.25, 0.0).unwrap();
+
+        assert!(hybrid[0].is_nan() && hybrid[1].is_nan());
+        for i in 2..data.len() {
+            assert_close(hybrid[i], 0.25 * eq[i] + 0.75 * ew[i]);
+        }
+    }
+
+    #[test]
+    fn hybrid_zscore_endpoints_match_components() {
+        let data = Array1::from_vec(vec![1.0, 3.0, f64::NAN, 5.0, 4.0, 6.0]);
+        let eq = rolling_zscore(&data, 3, 0, 0.0).unwrap();
+        let ew = ew_zscore(&data, 0.5, 0.0).unwrap();
+        let only_eq = hybrid_zscore(&data, 3, 0.5, 1.0, 0.0).unwrap();
+        let only_ew = hybrid_zscore(&data, 3, 0.5, 0.0, 0.0).unwrap();
+
+        for i in 0..data.len() {
+            assert_eq!(only_eq[i].to_bits(), eq[i].to_bits());
+            assert_eq!(only_ew[i].to_bits(), ew[i].to_bits());
+        }
+    }
+
+    #[test]
+    fn hybrid_zscore_rejects_invalid_blend() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0]);
+        assert!(hybrid_zscore(&data, 2, 0.5, 1.5, 0.0).is_err());
+        assert!(hybrid_zscore(&data, 2, 0.5, -0.1, 0.0).is_err());
+    }
+
+    #[test]
+    fn ew_ess_matches_explicit_weights() {
+        let data = Array1::from_vec(vec![1.0, 2.0, f64::NAN, 3.0, 4.0]);
+        let ess = ew_ess(&data, 0.5).unwrap();
+
+        assert_close(ess[0], 1.0);
+        assert!(ess[2].is_nan());
+        // Four valid observations with weights 1, 0.5, 0.25, 0.125
+        let w = [1.0, 0.5, 0.25, 0.125];
+        let expected = w.iter().sum::<f64>().powi(2) / w.iter().map(|v| v * v).sum::<f64>();
+        assert_close(ess[4], expected);
+    }
+
+    #[test]
+    fn rolling_ess_drops_weights_on_missing_values() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, f64::NAN, 5.0]);
+        let weights = Array1::from_vec(vec![1.0, 1.0, 2.0]);
+        let ess = rolling_ess(&data, &weights).unwrap();
+
+        assert!(ess[0].is_nan() && ess[1].is_nan());
+        assert_close(ess[2], 16.0 / 6.0);
+        // Window [2, NaN, 5] keeps weights 1 and 2
+        assert_close(ess[4], 9.0 / 5.0);
+        assert!(rolling_ess(&data, &Array1::from_vec(vec![1.0, -1.0])).is_err());
+    }
+
+    #[test]
+    fn streaming_rank_matches_batch_bit_for_bit() {
+        let data = Array1::from_vec(vec![
+            3.0, 1.0, 2.0, 2.0, f64::NAN, 5.0, 2.0, 2.0, f64::INFINITY, 0.5, 2.0, 7.0, 7.0, 1.0,
+        ]);
+        let window = 4;
+        let mut stream = RollingRank::with_window(window).unwrap();
+
+        for i in 0..data.len() {
+            let streamed = stream.push(data[i]);
+            let batch = rolling_rank(&data.slice(s![..=i]).to_owned(), window, None).unwrap();
+            assert_eq!(streamed.to_bits(), batch[i].to_bits(), "index {}", i);
+        }
+    }
+
+    #[test]
+    fn rolling_lead_lag_peaks_at_true_lag() {
+        let x = Array1::from_vec(vec![1.0, 4.0, 2.0, 8.0, 5.0, 7.0, 3.0, 9.0, 6.0, 2.0, 5.0, 8.0]);
+        // y is x delayed by two bars, so x leads y
+        let y = shift(&x, 2);
+        let profile = rolling_lead_lag(&x, &y, 5, 3).unwrap();
+
+        assert_eq!(profile.dim(), (12, 7));
+        let last = profile.row(11);
+        // Lag -2 lives in column max_lag - 2
+        assert_close(last[1], 1.0);
+        assert!(last.iter().enumerate().all(|(k, &v)| k == 1 || v.is_nan() || v < 0.999));
+        assert!(rolling_lead_lag(&x, &y, 3, 3).is_err());
+    }
+
+    #[test]
+    fn combine_weighted_policies() {
+        let a = Array1::from_vec(vec![0.5, f64::NAN, f64::NAN]);
+        let b = Array1::from_vec(vec![1.0, 0.5, f64::NAN]);
+        let components = [a, b];
+        let weights = [0.6, 0.4];
+
+        let zero = combine_weighted(&components, &weights, NanPolicy::Zero);
+        assert_close(zero[0], 0.7);
+        assert_close(zero[1], 0.2);
+        assert_close(zero[2], 0.0);
+
+        let reweight = combine_weighted(&components, &weights, NanPolicy::SkipReweight);
+        assert_close(reweight[0], 0.7);
+        assert_close(reweight[1], 0.5);
+        assert!(reweight[2].is_nan());
+
+        let propagate = combine_weighted(&components, &weights, NanPolicy::Propagate);
+        assert_close(propagate[0], 0.7);
+        assert!(propagate[1].is_nan() && propagate[2].is_nan());
+    }
+
+    #[test]
+    fn rolling_volume_delta_signs_by_direction() {
+        let close = Array1::from_vec(vec![10.0, 11.0, 10.5, 10.5, f64::NAN, 12.0, 13.0]);
+        let volume = Array1::from_vec(vec![100.0, 200.0, 50.0, 70.0, 80.0, 90.0, 30.0]);
+        let delta = rolling_volume_delta(&close, &volume, 2).unwrap();
+
+        assert!(delta[0].is_nan() && delta[1].is_nan());
+        assert_close(delta[2], 150.0);
+        assert_close(delta[3], -50.0);
+        // Bars touching the missing close are skipped
+        assert!(delta[4].is_nan() && delta[5].is_nan());
+        assert_close(delta[6], 30.0);
+    }
+
+    #[test]
+    fn rolling_up_fraction_counts_positive_moves() {
+        let returns = Array1::from_vec(vec![0.1, -0.2, 0.0, 0.3, f64::NAN, 0.2]);
+        let ignore = rolling_up_fraction(&returns, 3, false).unwrap();
+        let include = rolling_up_fraction(&returns, 3, true).unwrap();
+
+        assert!(ignore[1].is_nan());
+        assert_close(ignore[2], 0.5);
+        assert_close(include[2], 1.0 / 3.0);
+        assert_close(ignore[3], 0.5);
+        assert_close(include[5], 1.0);
+        assert!(rolling_up_fraction(&Array1::from_vec(vec![0.0, 0.0]), 2, false).unwrap()[1].is_nan());
+    }
+
+    #[test]
+    fn rolling_mean_chunks_stitch_to_batch_result() {
+        let data: Vec<f64> = (0..23).map(|i| ((i * 7) % 11) as f64 * 0.5).collect();
+        let batch = rolling_mean(&Array1::from_vec(data.clone()), 4).unwrap();
+
+        for chunk_size in [1, 3, 4, 5, 23, 100] {
+            let mut chunks = RollingMeanChunks::with_data(data.clone(), 4, chunk_size).unwrap();
+            let mut stitched = Vec::new();
+            while let Some(chunk) = chunks.next_chunk() {
+                assert!(chunk.len() <= chunk_size);
+                stitched.extend(chunk.iter().copied());
+            }
+            assert_eq!(stitched.len(), batch.len());
+            for (a, b) in stitched.iter().zip(batch.iter()) {
+                assert_eq!(a.to_bits(), b.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_corr_matrix_matches_rolling_correlation() {
+        let a = Array1::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0, 6.0, 3.0, 7.0]);
+        let b = Array1::from_vec(vec![2.0, 1.0, 4.0, 3.0, 6.0, 5.0, 8.0, 4.0]);
+        let c = Array1::from_vec(vec![5.0, 4.0, 4.5, 2.0, 3.0, 1.0, 2.5, 0.5]);
+        let ab = rolling_correlation(&a, &b, 4).unwrap();
+        let bc = rolling_correlation(&b, &c, 4).unwrap();
+        let mut stream = StreamingCorrMatrix::with_shape(3, 4).unwrap();
+
+        for t in 0..a.len() {
+            let m = stream.push(vec![a[t], b[t], c[t]]).unwrap();
+            if t < 3 {
+                assert!(m.iter().all(|v| v.is_nan()));
+                continue;
+            }
+            assert!((m[[0, 1]] - ab[t]).abs() < 1e-10);
+            assert!((m[[2, 1]] - bc[t]).abs() < 1e-10);
+            assert_eq!(m[[0, 1]].to_bits(), m[[1, 0]].to_bits());
+            assert_close(m[[2, 2]], 1.0);
+        }
+        assert!(stream.push(vec![1.0]).is_err());
+
+        // A NaN is skipped by its pairs only, as in rolling_correlation
+        let mut gappy = a.clone();
+        gappy[2] = f64::NAN;
+        let ab = rolling_correlation(&gappy, &b, 4).unwrap();
+        let bc = rolling_correlation(&b, &c, 4).unwrap();
+        let mut stream = StreamingCorrMatrix::with_shape(3, 4).unwrap();
+        for t in 0..a.len() {
+            let m = stream.push(vec![gappy[t], b[t], c[t]]).unwrap();
+            assert!((m[[0, 1]] - ab[t]).abs() < 1e-10 || (m[[0, 1]].is_nan() && ab[t].is_nan()));
+            assert!((m[[1, 2]] - bc[t]).abs() < 1e-10 || (m[[1, 2]].is_nan() && bc[t].is_nan()));
+        }
+        assert!(ab[4].is_finite() && ab[5].is_finite());
+
+        // Large offsets do not cancel
+        let ab = rolling_correlation(&a, &b, 4).unwrap();
+        let mut stream = StreamingCorrMatrix::with_shape(2, 4).unwrap();
+        for t in 0..a.len() {
+            let m = stream.push(vec![a[t] + 1e8, b[t]]).unwrap();
+            if t >= 3 {
+                assert!((m[[0, 1]] - ab[t]).abs() < 1e-6, "{} != {}", m[[0, 1]], ab[t]);
+            }
+        }
+        stream.clear();
+        assert!(stream.push(vec![1.0, 2.0]).unwrap().iter().all(|v| v.is_nan()));
+
+        // An asset gone flat after moving has NaN variance, not rounding residue
+        let mut stream = StreamingCorrMatrix::with_shape(3, 4).unwrap();
+        let mut m = Array2::zeros((3, 3));
+        for t in 0..20 {
+            let flat = if t < 12 { 45_000.0 + ((t * 7) % 5) as f64 * 0.013 } else { 45_000.01 };
+            m = stream.push(vec![a[t % 8], flat, b[t % 8]]).unwrap();
+        }
+        assert!(m.row(1).iter().all(|v| v.is_nan()) && m.column(1).iter().all(|v| v.is_nan()));
+        assert_close(m[[0, 0]], 1.0);
+        assert!(m[[0, 2]].is_finite());
+        assert_eq!(stream.pairs.len(), 6);
+    }
+
+    #[test]
+    fn apply_blume_shrinks_towards_one() {
+        let beta = Array1::from_vec(vec![2.0, 1.0, 0.0, f64::NAN]);
+        let adjusted = apply_blume(&beta, 0.67, 1.0);
+        assert_close(adjusted[0], 1.67);
+        assert_close(adjusted[1], 1.0);
+        assert_close(adjusted[2], 0.33);
+        assert!(adjusted[3].is_nan());
+    }
+
+    #[test]
+    fn rolling_jarque_bera_matches_direct_moments() {
+        let data = Array1::from_vec(vec![0.01, -0.02, 0.05, 0.0, -0.01, 0.08, -0.03, 0.02]);
+        let jb = rolling_jarque_bera(&data, 5).unwrap();
+        assert!(jb.slice(s![..4]).iter().all(|v| v.is_nan()));
+
+        for i in 4..data.len() {
+            let w: Vec<f64> = data.slice(s![i - 4..=i]).to_vec();
+            let mean = w.iter().sum::<f64>() / 5.0;
+            let m = |k: i32| w.iter().map(|x| (x - mean).powi(k)).sum::<f64>() / 5.0;
+            let skew = m(3) / m(2).powf(1.5);
+            let kurt = m(4) / (m(2) * m(2)) - 3.0;
+            let expected = 5.0 / 6.0 * (skew * skew + kurt * kurt / 4.0);
+            assert!((jb[i] - expected).abs() < 1e-8, "{} != {}", jb[i], expected);
+        }
+
+        let flat = Array1::from_vec(vec![1.0; 6]);
+        assert!(rolling_jarque_bera(&flat, 3).unwrap().iter().all(|v| v.is_nan()));
+
+        for offset in [45_000.0, 1e6] {
+            let shifted = rolling_jarque_bera(&data.mapv(|v| v + offset), 5).unwrap();
+            for i in 4..data.len() {
+                assert!((shifted[i] - jb[i]).abs() < 1e-6 * jb[i].max(1.0), "{} != {}", shifted[i], jb[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn round_window_modes_and_guard() {
+        assert_eq!(round_window(252.0 / 12.0, RoundMode::Floor).unwrap(), 21);
+        assert_eq!(round_window(20.2, RoundMode::Ceil).unwrap(), 21);
+        assert_eq!(round_window(20.5, RoundMode::Nearest).unwrap(), 21);
+        assert_eq!(round_window(20.4, RoundMode::Nearest).unwrap(), 20);
+        assert_eq!(round_window(0.3, RoundMode::Ceil).unwrap(), 1);
+        assert!(round_window(0.3, RoundMode::Floor).is_err());
+        assert!(round_window(f64::NAN, RoundMode::Nearest).is_err());
+        assert!("round".parse::<RoundMode>().is_err());
+    }
+
+    #[test]
+    fn drop_warmup_keeps_alignment() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, f64::NAN, 5.0, 6.0]);
+        let (trimmed, start) = drop_warmup(&rolling_std(&data, 2, 0).unwrap());
+        assert_eq!(start, 1);
+        assert_eq!(trimmed.len(), 5);
+        // Gap NaNs after the warm-up are preserved
+        assert!(trimmed[2].is_nan());
+
+        let (empty, start) = drop_warmup(&Array1::from_elem(3, f64::NAN));
+        assert_eq!((empty.len(), start), (0, 3));
+    }
+
+    #[test]
+    fn cs_dispersion_per_row() {
+        let panel = Array2::from_shape_vec((3, 3), vec![
+            1.0, 3.0, f64::NAN,
+            2.0, 2.0, 2.0,
+            f64::NAN, f64::NAN, f64::NAN,
+        ]).unwrap();
+        let dispersion = cs_dispersion(&panel, None).unwrap();
+        assert_close(dispersion[0], 1.0);
+        assert_close(dispersion[1], 0.0);
+        assert!(dispersion[2].is_nan());
+
+        let smoothed = cs_dispersion(&panel.slice(s![..2, ..]).to_owned(), Some(2)).unwrap();
+        assert_close(smoothed[1], 0.5);
+        assert!(cs_dispersion(&Array2::zeros((2, 0)), None).is_err());
+    }
+
+    #[test]
+    fn regime_rolling_mean_keeps_separate_windows() {
+        let data = Array1::from_vec(vec![1.0, 10.0, 3.0, 20.0, f64::NAN, 5.0, 30.0]);
+        let regime = Array1::from_vec(vec![0, 1, 0, 1, 0, 0, 1]);
+        let result = regime_rolling_mean(&data, &regime, 2).unwrap();
+
+        assert!(result[0].is_nan() && result[1].is_nan());
+        assert_close(result[2], 2.0);
+        assert_close(result[3], 15.0);
+        assert!(result[4].is_nan());
+        assert_close(result[5], 4.0);
+        assert_close(result[6], 25.0);
+        assert!(regime_rolling_mean(&data, &Array1::from_vec(vec![0]), 2).is_err());
+    }
+
+    #[test]
+    fn discounted_sum_recursion() {
+        let data = Array1::from_vec(vec![f64::NAN, 1.0, 2.0, f64::NAN, -1.0]);
+        let result = discounted_sum(&data, 0.5).unwrap();
+        assert!(result[0].is_nan());
+        assert_close(result[1], 1.0);
+        assert_close(result[2], 2.5);
+        assert_close(result[3], 2.5);
+        assert_close(result[4], 0.25);
+        assert!(discounted_sum(&data, 1.0).is_err());
+    }
+
+    #[test]
+    fn partial_windows_are_flagged() {
+        let data = Array1::from_vec(vec![1.0, 3.0, f64::NAN, 5.0, 7.0, 9.0]);
+        let (std, std_partial) = rolling_std_partial(&data, 3, 2, 0).unwrap();
+        assert!(std[0].is_nan());
+        assert_close(std[1], 1.0);
+        assert!(std[2].is_nan());
+        // NaNs are skipped, so the window fills at the third valid value
+        assert!(std_partial[1] && std_partial[2]);
+        assert!(!std_partial[3] && std[3].is_finite());
+
+        let full = rolling_std(&data, 3, 0).unwrap();
+        assert_eq!(full[4].to_bits(), std[4].to_bits());
+
+        let clean = Array1::from_vec(vec![2.0, 4.0, 6.0, 8.0]);
+        let (mean, mean_partial) = rolling_mean_partial(&clean, 3, 1).unwrap();
+        assert_close(mean[0], 2.0);
+        assert_close(mean[1], 3.0);
+        assert_close(mean[3], 6.0);
+        assert_eq!(mean_partial.to_vec(), vec![true, true, false, false]);
+        assert!(rolling_mean_partial(&clean, 3, 4).is_err());
+    }
+
+    #[test]
+    fn rolling_logret_corr_uses_returns_not_levels() {
+        let x = Array1::from_vec(vec![100.0, 101.0, 99.0, 102.0, 103.0, 101.0, 104.0]);
+        // y compounds exactly twice x's log returns
+        let y = x.mapv(|p: f64| (p / 100.0).powi(2) * 50.0);
+        let corr = rolling_logret_corr(&x, &y, 3, 1, PrecisionMode::Fast).unwrap();
+
+        // Returns eat one slot, then the window needs three of them
+        assert!(corr.slice(s![..3]).iter().all(|v| v.is_nan()));
+        assert!(corr.slice(s![3..]).iter().all(|&v| (v - 1.0).abs() < 1e-9));
+        assert!(rolling_logret_corr(&x, &y.slice(s![1..]).to_owned(), 3, 1, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn rolling_drawdown_duration_counts_underwater_bars() {
+        let prices = Array1::from_vec(vec![10.0, 9.0, 8.0, f64::NAN, 11.0, 10.5, 12.0, 13.0]);
+        let duration = rolling_drawdown_duration(&prices, 5).unwrap();
+
+        assert!(duration.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_close(duration[4], 2.0);
+        assert_close(duration[5], 1.0);
+        assert_close(duration[7], 1.0);
+
+        let rising = Array1::from_vec(vec![1.0, 2.0, 3.0]);
+        assert_close(rolling_drawdown_duration(&rising, 2).unwrap()[2], 0.0);
+    }
+
+    #[test]
+    fn nan_report_summarises_each_column() {
+        let nan = f64::NAN;
+        let data = Array2::from_shape_vec(
+            (5, 2),
+            vec![nan, nan, 1.0, nan, nan, nan, nan, nan, 2.0, nan],
+        )
+        .unwrap();
+        let report = nan_report(&data);
+
+        assert_eq!(report.nan_count.to_vec(), vec![3, 5]);
+        assert_eq!(report.longest_nan_run.to_vec(), vec![2, 5]);
+        assert_eq!(report.first_valid.to_vec(), vec![1, -1]);
+        assert_eq!(report.last_valid.to_vec(), vec![4, -1]);
+    }
+
+    #[test]
+    fn rolling_robust_clean_replaces_spikes_with_median() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 1.0, 2.0, 100.0, 1.0, f64::NAN, 2.0]);
+        let (median, mad) = rolling_median_mad(&data, 4).unwrap();
+        assert!(median.slice(s![..3]).iter().all(|v| v.is_nan()));
+        assert_close(median[3], 1.5);
+        assert_close(mad[3], 0.5);
+        assert!(median[6].is_nan());
+
+        let (cleaned, replaced) = rolling_robust_clean(&data, 4, 5.0).unwrap();
+        assert_close(cleaned[4], 2.0);
+        assert!(replaced[4]);
+        assert_eq!(replaced.iter().filter(|&&r| r).count(), 1);
+        assert!(cleaned[6].is_nan());
+        assert!(rolling_robust_clean(&data, 4, 0.0).is_err());
+    }
+
+    #[test]
+    fn window_at_matches_kernel_windows() {
+        let data = Array1::from_vec(vec![1.0, 2.0, f64::NAN, 4.0, 5.0]);
+
+        assert_eq!(window_at(&data, 3, 1, false).unwrap().to_vec(), vec![1.0, 2.0]);
+        let positional = window_at(&data, 3, 3, false).unwrap();
+        assert!(positional[1].is_nan());
+        assert_eq!(window_at(&data, 3, 4, true).unwrap().to_vec(), vec![2.0, 4.0, 5.0]);
+        assert_close(window_at(&data, 3, 4, true).unwrap().std(0.0), rolling_std(&data, 3, 0).unwrap()[4]);
+        assert!(window_at(&data, 3, 5, true).is_err());
+    }
+
+    #[test]
+    fn rolling_predictive_r2_squares_correlation() {
+        let factor = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        let forward_ret = Array1::from_vec(vec![0.5, 0.1, 0.7, 0.2, 0.9]);
+        let r2 = rolling_predictive_r2(&factor, &forward_ret, 3, PrecisionMode::Fast).unwrap();
+        let corr = rolling_correlation_with(&factor, &forward_ret, 3, PrecisionMode::Fast).unwrap();
+
+        assert!(r2[1].is_nan());
+        for i in 2..5 {
+            assert_close(r2[i], corr[i] * corr[i]);
+        }
+        assert!(rolling_predictive_r2(&factor, &forward_ret.slice(s![..4]).to_owned(), 3, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn ols_fit_recovers_exact_coefficients() {
+        let design = Array2::from_shape_vec((4, 2), vec![1.0, 0.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0]).unwrap();
+        let target = Array1::from_vec(vec![1.0, 3.0, 5.0, 7.5]);
+        let fit = ols_fit(&design, &target).unwrap();
+        assert!((fit.coef[0] - 0.9).abs() < 1e-12);
+        assert!((fit.coef[1] - 2.15).abs() < 1e-12);
+        // sigma² = 0.075 / 2 and (X'X)^-1 has 0.2 on the slope diagonal
+        assert!((fit.std_err[1] - (0.0375_f64 * 0.2).sqrt()).abs() < 1e-12);
+        assert!((fit.rss - 0.075).abs() < 1e-12);
+
+        let collinear = Array2::from_shape_vec((3, 2), vec![1.0, 2.0, 2.0, 4.0, 3.0, 6.0]).unwrap();
+        assert!(ols_fit(&collinear, &Array1::from_vec(vec![1.0, 2.0, 3.0])).is_none());
+    }
+
+    #[test]
+    fn rolling_coint_stat_separates_cointegrated_pairs() {
+        let n = 200;
+        let mut state = 42_u64;
+        let noise: Vec<f64> = (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect();
+        let x = Array1::from_iter((0..n).map(|i| (i as f64 * 0.1).sin() * 5.0 + i as f64 * 0.3));
+        let walk = Array1::from_iter(noise.iter().scan(0.0, |acc, e| {
+            *acc += e;
+            Some(*acc)
+        }));
+        let cointegrated = &x * 2.0 + &Array1::from_vec(noise.clone());
+        let drifting = &x * 2.0 + &walk;
+
+        let tight = rolling_coint_stat(&cointegrated, &x, 100, 1).unwrap();
+        let loose = rolling_coint_stat(&drifting, &x, 100, 1).unwrap();
+        assert!(tight.slice(s![..99]).iter().all(|v| v.is_nan()));
+        // The two-variable Engle-Granger 5% critical value is about -3.34
+        assert!(tight[n - 1] < -3.34);
+        assert!(loose[n - 1] > -3.34);
+        assert!(rolling_coint_stat(&x, &x, 4, 1).is_err());
+    }
+
+    #[test]
+    fn adf_test_distinguishes_stationary_from_random_walk() {
+        let mut state = 7_u64;
+        let noise: Vec<f64> = (0..300)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect();
+        let stationary = Array1::from_vec(noise.clone());
+        let walk = Array1::from_iter(noise.iter().scan(0.0, |acc, e| {
+            *acc += e;
+            Some(*acc)
+        }));
+
+        let (stat, gamma) = adf_test(&stationary, 4, AdfRegression::Constant).unwrap();
+        // The constant-only 5% Dickey-Fuller critical value is about -2.87
+        assert!(stat < -2.87);
+        assert!(gamma < -0.5);
+        assert!(adf_test(&walk, 4, AdfRegression::Constant).unwrap().0 > -2.87);
+        assert!(adf_test(&walk, 4, AdfRegression::ConstantTrend).is_ok());
+
+        let mut gappy = stationary.clone();
+        gappy[10] = f64::NAN;
+        assert!(adf_test(&gappy, 4, AdfRegression::Constant).is_err());
+        assert!("x".parse::<AdfRegression>().is_err());
+    }
+
+    #[test]
+    fn accurate_precision_survives_large_offsets() {
+        let base = Array1::from_vec(vec![0.01, 0.02, 0.04, 0.03, 0.05]);
+        let shifted = base.mapv(|v| v + 1e8);
+        let other = Array1::from_vec(vec![1.0, 3.0, 2.0, 5.0, 4.0]);
+
+        let (std_ref, _) = rolling_std_partial_with(&base, 3, 3, 0, PrecisionMode::Fast, true).unwrap();
+        let (std_acc, _) = rolling_std_partial_with(&shifted, 3, 3, 0, PrecisionMode::Accurate, true).unwrap();
+        let corr_ref = rolling_correlation_with(&base, &other, 3, PrecisionMode::Fast).unwrap();
+        let corr_acc = rolling_correlation_with(&shifted, &other, 3, PrecisionMode::Accurate).unwrap();
+        for i in 2..5 {
+            assert!((std_acc[i] - std_ref[i]).abs() < 1e-6);
+            assert!((corr_acc[i] - corr_ref[i]).abs() < 1e-6);
+        }
+
+        assert!("exact".parse::<PrecisionMode>().is_err());
+        assert_eq!(resolve_precision(Some("accurate")).unwrap(), PrecisionMode::Accurate);
+    }
+
+    #[test]
+    fn rolling_risk_adjusted_switches_denominator() {
+        let returns = Array1::from_vec(vec![0.01, -0.02, 0.03, f64::NAN, 0.01, 0.02]);
+        let sharpe = rolling_risk_adjusted(&returns, 3, RiskDenominator::Std, 4.0, 0.0, 0.0).unwrap();
+        let sortino = rolling_risk_adjusted(&returns, 3, RiskDenominator::Downside, 4.0, 0.0, 0.0).unwrap();
+        let mad = rolling_risk_adjusted(&returns, 3, RiskDenominator::Mad, 4.0, 0.0, 0.0).unwrap();
+
+        let std = (((0.01_f64 - 0.02 / 3.0).powi(2) + (-0.02_f64 - 0.02 / 3.0).powi(2) + (0.03_f64 - 0.02 / 3.0).powi(2)) / 3.0).sqrt();
+        assert!(sharpe[1].is_nan());
+        assert_close(sharpe[2], 0.02 / 3.0 / std * 2.0);
+        assert_close(sortino[2], 0.02 / 3.0 / (0.0004_f64 / 3.0).sqrt() * 2.0);
+        assert_close(mad[2], 0.02 / 3.0 / (0.02 * 1.4826) * 2.0);
+        assert!(sharpe[3].is_nan());
+        // No returns below `mar` in the last window [0.03, 0.01, 0.02]
+        assert!(sortino[5].is_nan());
+        assert!("var".parse::<RiskDenominator>().is_err());
+    }
+
+    #[test]
+    fn ewm_correlation_follows_riskmetrics_recursion() {
+        let x = Array1::from_vec(vec![1.0, f64::NAN, -1.0, 2.0]);
+        let y = Array1::from_vec(vec![2.0, 1.0, 1.0, 1.0]);
+        let corr = ewm_correlation(&x, &y, 0.5).unwrap();
+
+        assert_close(corr[0], 1.0);
+        assert!(corr[1].is_nan());
+        // cov = 0.5 * 2 - 0.5 = 0.5, var_x = 1, var_y = 0.5 * 4 + 0.5 = 2.5
+        assert_close(corr[2], 0.5 / 2.5_f64.sqrt());
+        let (cov, var_x, var_y): (f64, f64, f64) = (0.25 + 1.0, 0.5 + 2.0, 1.25 + 0.5);
+        assert_close(corr[3], cov / (var_x * var_y).sqrt());
+
+        assert!(ewm_correlation(&x, &y, 1.0).is_err());
+        assert!(ewm_correlation(&x, &y.slice(s![..3]).to_owned(), 0.94).is_err());
+    }
+
+    #[test]
+    fn momentum_and_reversion_matches_individual_factors() {
+        let prices = Array1::from_vec(vec![10.0, 10.5, 10.2, 10.8, f64::NAN, 11.1, 11.0, 11.6, 11.3]);
+        let (mom, rev) = momentum_and_reversion(&prices, 3, false, 0.0, 0).unwrap();
+        let (mom_ref, rev_ref) = (momentum(&prices, 3, false, 0).unwrap(), mean_reversion(&prices, 3, false, 0.0, 0).unwrap());
+
+        for i in 0..prices.len() {
+            assert!(mom[i].to_bits() == mom_ref[i].to_bits());
+            assert!(rev[i].to_bits() == rev_ref[i].to_bits());
+        }
+        assert!(momentum_and_reversion(&prices, 0, false, 0.0, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_zero_crossings_skips_nan_and_zero() {
+        let data = Array1::from_vec(vec![1.0, -1.0, f64::NAN, -2.0, 0.0, 3.0, 4.0]);
+        let count = rolling_zero_crossings(&data, 4, false).unwrap();
+        let rate = rolling_zero_crossings(&data, 4, true).unwrap();
+
+        assert!(count.slice(s![..3]).iter().all(|v| v.is_nan()));
+        assert_close(count[3], 1.0);
+        assert_close(rate[3], 0.5);
+        // Window [-1, NaN, -2, 0]: the 1 -> -1 pair has left, -1 -> -2 is not a crossing
+        assert_close(count[4], 0.0);
+        assert_close(count[5], 1.0);
+        assert_close(count[6], 1.0);
+        assert_close(rate[6], 0.5);
+    }
+
+    #[test]
+    fn percentile_breakout_flags_band_exits() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 10.0, 5.0, -4.0, f64::NAN]);
+        let bands = rolling_quantiles(&data, 5, &[0.5, 0.9]).unwrap();
+        assert_close(bands[0][4], 3.0);
+        // Sorted [1, 2, 3, 4, 10]: position 3.6 → 4 + 0.6 * 6
+        assert_close(bands[1][4], 7.6);
+
+        let signal = percentile_breakout(&data, 5, 0.9, 0.1).unwrap();
+        assert!(signal.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_close(signal[4], 1.0);
+        assert_close(signal[5], 0.0);
+        assert_close(signal[6], -1.0);
+        assert!(signal[7].is_nan());
+        assert!(percentile_breakout(&data, 5, 0.1, 0.9).is_err());
+        assert!(rolling_quantiles(&data, 5, &[1.5]).is_err());
+    }
+
+    #[test]
+    fn rolling_pc1_share_measures_commonality() {
+        let n = 6;
+        let market = [1.0, -2.0, 0.5, 3.0, -1.0, 2.0];
+        let idio = [0.3, -0.1, 0.2, -0.4, 0.1, 0.0];
+        let mut common = Array2::zeros((n, 3));
+        for t in 0..n {
+            common[[t, 0]] = market[t];
+            common[[t, 1]] = 2.0 * market[t];
+            common[[t, 2]] = f64::NAN;
+        }
+        let mut mixed = common.clone();
+        for t in 0..n {
+            mixed[[t, 1]] = idio[t];
+        }
+
+        let share = rolling_pc1_share(&common, 4).unwrap();
+        assert!(share.slice(s![..3]).iter().all(|v| v.is_nan()));
+        // Perfectly collinear usable assets: PC1 explains everything
+        assert!((share[5] - 1.0).abs() < 1e-9);
+        let mixed_share = rolling_pc1_share(&mixed, 4).unwrap();
+        let (a, b) = (&market[2..], &idio[2..]);
+        let (ma, mb) = (a.iter().sum::<f64>() / 4.0, b.iter().sum::<f64>() / 4.0);
+        let var_a = a.iter().map(|x| (x - ma).powi(2)).sum::<f64>() / 4.0;
+        let var_b = b.iter().map(|x| (x - mb).powi(2)).sum::<f64>() / 4.0;
+        let cov = a.iter().zip(b).map(|(x, y)| (x - ma) * (y - mb)).sum::<f64>() / 4.0;
+        // Closed-form top eigenvalue of a symmetric 2x2 matrix
+        let top = (var_a + var_b) / 2.0 + (((var_a - var_b) / 2.0).powi(2) + cov * cov).sqrt();
+        assert!((mixed_share[5] - top / (var_a + var_b)).abs() < 1e-9);
+
+        let single = rolling_pc1_share(&common.slice(s![.., 2..]).to_owned(), 4).unwrap();
+        assert!(single.iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn rolling_mean_abs_dev_uses_window_mean() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 6.0, f64::NAN, 3.0]);
+        let mad = rolling_mean_abs_dev(&data, 3).unwrap();
+
+        assert!(mad[1].is_nan());
+        // mean 3: |1-3| + |2-3| + |6-3| = 6
+        assert_close(mad[2], 2.0);
+        assert!(mad[3].is_nan());
+        assert_close(mad[4], (5.0 / 3.0 + 7.0 / 3.0 + 2.0 / 3.0) / 3.0);
+    }
+
+    #[test]
+    fn rolling_iqr_spans_quartiles() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, f64::NAN, 9.0]);
+        let iqr = rolling_iqr(&data, 5, false).unwrap();
+        let scaled = rolling_iqr(&data, 5, true).unwrap();
+
+        assert!(iqr.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_close(iqr[4], 2.0);
+        assert_close(scaled[4], 2.0 / 1.349);
+        assert!(iqr[5].is_nan());
+        // Window [2, 3, 4, 5, 9]
+        assert_close(iqr[6], 2.0);
+    }
+
+    #[test]
+    fn rolling_nunique_counts_distinct_values() {
+        let nan = f64::NAN;
+        let data = Array1::from_vec(vec![1.0, 1.0, 2.0, -0.0, 0.0, nan, nan, nan]);
+        let nunique = rolling_nunique(&data, 3).unwrap();
+
+        assert!(nunique.slice(s![..2]).iter().all(|v| v.is_nan()));
+        assert_close(nunique[2], 2.0);
+        assert_close(nunique[3], 3.0);
+        assert_close(nunique[4], 2.0);
+        assert_close(nunique[6], 1.0);
+        assert!(nunique[7].is_nan());
+    }
+
+    #[test]
+    fn fill_warmup_only_touches_leading_nans() {
+        let nan = f64::NAN;
+        let result = Array1::from_vec(vec![nan, nan, 2.0, nan, 3.0]);
+
+        let first = fill_warmup(result.clone(), WarmupFill::FirstValid);
+        assert_eq!(first.slice(s![..3]).to_vec(), vec![2.0, 2.0, 2.0]);
+        assert!(first[3].is_nan());
+        let zero = fill_warmup(result.clone(), WarmupFill::Zero);
+        assert_eq!(zero.slice(s![..2]).to_vec(), vec![0.0, 0.0]);
+        assert!(fill_warmup(result, WarmupFill::Nan)[0].is_nan());
+        assert!(fill_warmup(Array1::from_elem(2, nan), WarmupFill::FirstValid).iter().all(|v| v.is_nan()));
+        assert!("bfill".parse::<WarmupFill>().is_err());
+    }
+
+    #[test]
+    fn rolling_beta_through_origin_skips_centering() {
+        let x = Array1::from_vec(vec![1.0, 2.0, 3.0, 0.0, 0.0]);
+        let y = Array1::from_vec(vec![3.0, 5.0, 7.0, 1.0, 1.0]);
+        let with_const = rolling_beta(&x, &y, 3, true).unwrap();
+        let origin = rolling_beta(&x, &y, 3, false).unwrap();
+
+        assert_close(with_const[2], 2.0);
+        // (3 + 10 + 21) / (1 + 4 + 9)
+        assert_close(origin[2], 34.0 / 14.0);
+        assert_close(origin[3], (10.0 + 21.0) / 13.0);
+        let zeros = Array1::from_vec(vec![0.0, 0.0]);
+        assert!(rolling_beta(&zeros, &zeros, 2, false).unwrap()[1].is_nan());
+    }
+
+    #[test]
+    fn rolling_beta_recovers_linear_slope() {
+        let x = Array1::from_iter((0..30).map(|i| ((i * 7) % 11) as f64 - 5.0));
+        let y = x.mapv(|v| 1.75 * v - 3.0);
+        let beta = rolling_beta(&x, &y, 5, true).unwrap();
+        assert!(beta.iter().take(4).all(|v| v.is_nan()));
+        for &b in beta.iter().skip(4) {
+            assert_close(b, 1.75);
+        }
+
+        let flat = Array1::from_elem(6, 2.0);
+        assert!(rolling_beta(&flat, &y.slice(s![..6]).to_owned(), 3, true).unwrap().iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn co_moments_are_exactly_zero_for_a_flat_window() {
+        let mut state = 5u64;
+        let mut x: Vec<f64> = (0..50)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                45_000.0 + (state >> 11) as f64 / (1u64 << 53) as f64
+            })
+            .collect();
+        x.extend([45_000.01; 10]);
+        let x = Array1::from_vec(x);
+        let y = Array1::from_iter((0..60).map(|i| ((i * 7) % 5) as f64));
+
+        let beta = rolling_beta(&x, &y, 5, true).unwrap();
+        let corr = rolling_correlation_with(&x, &y, 5, PrecisionMode::Fast).unwrap();
+        let cov = rolling_cov(&x, &y, 5, 1, PrecisionMode::Fast).unwrap();
+        assert!(beta[48].is_finite() && corr[48].is_finite());
+        for i in 54..60 {
+            assert!(beta[i].is_nan() && corr[i].is_nan(), "{}: beta {} corr {}", i, beta[i], corr[i]);
+            assert_eq!(cov[i], 0.0);
+        }
+    }
+
+    #[test]
+    fn rolling_beta_is_stable_under_large_offsets() {
+        let x = Array1::from_iter((0..120).map(|i| ((i * 37) % 11) as f64 * 0.01));
+        let y = Array1::from_iter((0..120).map(|i| ((i * 13) % 7) as f64 * 0.02 - x[i] * 0.3));
+        for (offset_x, offset_y) in [(1e6, 0.0), (1e8, 45_000.0)] {
+            let beta = rolling_beta(&x.mapv(|v| v + offset_x), &y.mapv(|v| v + offset_y), 20, true).unwrap();
+            for i in 19..120 {
+                let (wx, wy) = (x.slice(s![i - 19..=i]), y.slice(s![i - 19..=i]));
+                let (mx, my) = (wx.mean().unwrap(), wy.mean().unwrap());
+                let cov: f64 = wx.iter().zip(wy.iter()).map(|(a, b)| (a - mx) * (b - my)).sum();
+                let var: f64 = wx.iter().map(|a| (a - mx) * (a - mx)).sum();
+                assert!((beta[i] - cov / var).abs() < 1e-4, "{}: {} != {}", i, beta[i], cov / var);
+            }
+        }
+    }
+
+    #[test]
+    fn compute_trimmed_restores_edge_nans() {
+        let nan = f64::NAN;
+        let data = Array1::from_vec(vec![nan, nan, 1.0, 2.0, nan, 4.0, nan]);
+        let trimmed = compute_trimmed(std::slice::from_ref(&data), true, |v| rolling_sum(&v[0], 2)).unwrap();
+
+        assert_eq!(trimmed.len(), data.len());
+        assert!(trimmed[0].is_nan() && trimmed[1].is_nan() && trimmed[6].is_nan());
+        assert_close(trimmed[3], 3.0);
+        assert_close(trimmed[5], 6.0);
+
+        let all_nan = Array1::from_elem(3, nan);
+        assert!(compute_trimmed(&[all_nan], true, |v| rolling_sum(&v[0], 2)).unwrap().iter().all(|v| v.is_nan()));
+        let short = Array1::from_vec(vec![1.0]);
+        assert!(compute_trimmed(&[data, short], true, |v| rolling_beta(&v[0], &v[1], 2, true)).is_err());
+    }
+
+    #[test]
+    fn rolling_corr_pvalue_matches_t_test() {
+        let x = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let y = Array1::from_vec(vec![2.0, 1.0, 4.0, 3.0, 6.0, 5.0]);
+        let p = rolling_corr_pvalue(&x, &y, 5, PrecisionMode::Fast).unwrap();
+
+        assert!(p[3].is_nan());
+        // r ≈ 0.822 over the first window; the closed-form df = 3 Student-t CDF gives p ≈ 0.0877
+        assert!((p[4] - 0.08770664700806541).abs() < 1e-9);
+        let perfect = rolling_corr_pvalue(&x, &x, 3, PrecisionMode::Fast).unwrap();
+        assert_close(perfect[2], 0.0);
+        assert!(rolling_corr_pvalue(&x, &y, 2, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn streaming_quantile_tracks_exact_quantile() {
+        let mut estimator = StreamingQuantile::with_quantile(0.9).unwrap();
+        assert!(estimator.current().is_nan());
+        estimator.push(3.0);
+        estimator.push(f64::NAN);
+        assert_close(estimator.push(1.0), 2.8);
+
+        let mut state = 11_u64;
+        let mut values = vec![3.0, 1.0];
+        for _ in 0..5000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let v = (state >> 11) as f64 / (1u64 << 53) as f64;
+            values.push(v);
+            estimator.push(v);
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        assert!((estimator.current() - sorted_quantile(&values, 0.9)).abs() < 0.01);
+        assert!(StreamingQuantile::with_quantile(1.0).is_err());
+    }
+
+    #[test]
+    fn factor_decay_flag_compares_recent_to_baseline_ic() {
+        let factor = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        // Tracks the factor early on, then turns against it
+        let forward_ret = Array1::from_vec(vec![1.0, 2.1, 2.9, 4.2, 5.0, 4.0, 3.1, 1.9]);
+        let flag = factor_decay_flag(&factor, &forward_ret, 3, 6, 0.5).unwrap();
+
+        // Undefined baseline during warm-up never flags
+        assert!(!flag.slice(s![..5]).iter().any(|&f| f));
+        // Recent IC -0.19 against a baseline of 0.90
+        assert!(flag[5] && flag[6]);
+        // -1.00 against -0.45: a drop of about 0.55
+        assert!(flag[7]);
+        assert!(!factor_decay_flag(&factor, &forward_ret, 3, 6, 0.6).unwrap()[7]);
+        assert!(factor_decay_flag(&factor, &forward_ret, 6, 6, 0.5).is_err());
+        assert!(factor_decay_flag(&factor, &forward_ret, 3, 6, -0.1).is_err());
+    }
+
+    #[test]
+    fn rolling_beta_stability_chains_beta_and_cv() {
+        let market = Array1::from_vec(vec![1.0, 2.0, 1.0, 3.0, 2.0, 4.0, 3.0, 5.0]);
+        let asset = Array1::from_iter(market.iter().enumerate().map(|(i, m)| m * (1.0 + 0.1 * i as f64)));
+        let stability = rolling_beta_stability(&asset, &market, 3, 3).unwrap();
+        let beta = rolling_beta(&market, &asset, 3, true).unwrap();
+
+        assert!(stability.slice(s![..4]).iter().all(|v| v.is_nan()));
+        let window = beta.slice(s![2..5]).to_owned();
+        assert_close(stability[4], window.std(0.0) / window.mean().unwrap().abs());
+        assert!(stability.slice(s![4..]).iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn rolling_pairwise_corr_matches_one_dimensional_kernel() {
+        let data = Array2::from_shape_vec(
+            (5, 3),
+            vec![1.0, 2.0, 5.0, 2.0, 1.0, 5.0, 3.0, 4.0, 5.0, 4.0, f64::NAN, 5.0, 5.0, 3.0, 5.0],
+        )
+        .unwrap();
+        let corr = rolling_pairwise_corr(&data, 3, PrecisionMode::Fast).unwrap();
+        let ab = rolling_correlation_with(&data.column(0).to_owned(), &data.column(1).to_owned(), 3, PrecisionMode::Fast).unwrap();
+
+        assert_eq!(corr.dim(), (5, 3, 3));
+        for t in 0..5 {
+            assert!(corr[[t, 0, 1]].to_bits() == ab[t].to_bits());
+            assert!(corr[[t, 1, 0]].to_bits() == ab[t].to_bits());
+        }
+        assert_close(corr[[2, 0, 0]], 1.0);
+        // A constant column is degenerate on and off the diagonal
+        assert!(corr[[4, 2, 2]].is_nan() && corr[[4, 0, 2]].is_nan());
+        assert!(rolling_pairwise_corr(&Array2::zeros((2, 65)), 2, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn relative_strength_components_align_with_timeframes() {
+        let prices = Array1::from_iter((0..20).map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0 + i as f64));
+        let (factor, ranks) = relative_strength(&prices, 6, false, NanPolicy::Propagate).unwrap();
+        let (inverted, _) = relative_strength(&prices, 6, true, NanPolicy::Propagate).unwrap();
+
+        assert_eq!(ranks.dim(), (20, 3));
+        let expected = rolling_rank(&pct_change(&prices, 12, ZeroPrev::Nan).unwrap(), 6, None).unwrap();
+        for t in 0..20 {
+            assert!(ranks[[t, 2]].to_bits() == expected[t].to_bits());
+        }
+        let t = 19;
+        assert_close(factor[t], 0.5 * ranks[[t, 0]] + 0.3 * ranks[[t, 1]] + 0.2 * ranks[[t, 2]]);
+        assert_close(inverted[t], -factor[t]);
+    }
+
+    #[test]
+    fn std_floor_clamps_zscore_denominator() {
+        let data = Array1::from_vec(vec![1.0, 1.0, 1.0, 1.0, 1.3, 1.3]);
+
+        let plain = rolling_zscore(&data, 3, 0, 0.0).unwrap();
+        assert!(plain[3].is_nan());
+        let floored = rolling_zscore(&data, 3, 0, 0.5).unwrap();
+        assert_eq!(floored[3], 0.0);
+        // std over [1, 1, 1.3] is ~0.17, so the floor drives the denominator
+        assert!((floored[4] - (1.3 - 3.3 / 3.0) / 0.5).abs() < 1e-12);
+
+        let ew = ew_zscore(&data, 0.5, 0.5).unwrap();
+        assert_eq!(ew[0], 0.0);
+        assert!(ew_zscore(&data, 0.5, 0.0).unwrap()[0].is_nan());
+
+        let rev = mean_reversion(&data, 3, false, 0.5, 0).unwrap();
+        assert!((rev[4] + floored[4]).abs() < 1e-12);
+        assert!(rolling_zscore(&data, 3, 0, -1.0).is_err());
+    }
+
+    #[test]
+    fn fractional_window_mean_grows_to_cap() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        let result = fractional_window_mean(&data, 0.5, 3).unwrap();
+
+        // windows: 1, 1, 1, 2, 2, 3, 3, 3
+        let expected = [1.0, 2.0, 3.0, 3.5, 4.5, 5.0, 6.0, 7.0];
+        for (r, e) in result.iter().zip(expected.iter()) {
+            assert!((r - e).abs() < 1e-12);
+        }
+
+        let capped = fractional_window_mean(&data, 1.0, 3).unwrap();
+        let rolling = rolling_mean(&data, 3).unwrap();
+        assert_eq!(capped.slice(s![2..]), rolling.slice(s![2..]));
+        assert_eq!(capped[0], 1.0);
+
+        let mut gappy = data.clone();
+        gappy[4] = f64::NAN;
+        let result = fractional_window_mean(&gappy, 0.5, 3).unwrap();
+        assert!(result[4].is_nan() && result[5].is_nan() && result[6].is_nan());
+        assert_eq!(result[7], 7.0);
+
+        assert!(fractional_window_mean(&data, 0.0, 3).is_err());
+        assert!(fractional_window_mean(&data, 1.5, 3).is_err());
+        assert!(fractional_window_mean(&data, 0.5, 0).is_err());
+    }
+
+    #[test]
+    fn weighted_rolling_rank_emphasizes_recent_values() {
+        let data = Array1::from_vec(vec![5.0, 1.0, 3.0, f64::NAN, 4.0, 2.0]);
+        let plain = rolling_rank(&data, 3, None).unwrap();
+
+        let equal = rolling_rank(&data, 3, Some(&Array1::from_elem(3, 2.0))).unwrap();
+        for (a, b) in plain.iter().zip(equal.iter()) {
+            assert!((a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-12);
+        }
+
+        // Window [5, 1, 3] at index 2: only 1 and 3 are <= 3
+        let weights = Array1::from_vec(vec![1.0, 2.0, 3.0]);
+        let result = rolling_rank(&data, 3, Some(&weights)).unwrap();
+        assert!((result[2] - 5.0 / 6.0).abs() < 1e-12);
+        // Window [3, NaN, 4]: the NaN's weight is dropped before normalizing
+        assert!((result[4] - 1.0).abs() < 1e-12);
+        // Window [NaN, 4, 2]: only 2 itself is <= 2
+        assert!((result[5] - 3.0 / 5.0).abs() < 1e-12);
+
+        assert!(rolling_rank(&data, 3, Some(&Array1::from_elem(2, 1.0))).is_err());
+        assert!(rolling_rank(&data, 3, Some(&Array1::zeros(3))).is_err());
+        assert!(rolling_rank(&data, 3, Some(&Array1::from_vec(vec![1.0, -1.0, 1.0]))).is_err());
+    }
+
+    #[test]
+    fn min_periods_counts_buffered_or_valid_elements() {
+        let data = Array1::from_vec(vec![1.0, f64::NAN, 3.0, 2.0, 5.0]);
+        let other = Array1::from_vec(vec![2.0, 1.0, 5.0, 3.0, 4.0]);
+
+        // Positional rank: index 1 has two buffered elements, one of them valid
+        let rank = rolling_rank_partial(&data, 3, 2, None).unwrap();
+        assert!(rank[0].is_nan() && rank[1].is_nan());
+        assert_eq!(rank[2], 1.0);
+        assert_eq!(rank.slice(s![2..]), rolling_rank(&data, 3, None).unwrap().slice(s![2..]));
+
+        // Valid-count correlation: the skipped NaN pair does not count towards min_periods
+        let corr = rolling_correlation_partial_with(&data, &other, 3, 2, PrecisionMode::Fast).unwrap();
+        assert!(corr[1].is_nan());
+        assert!((corr[2] - 1.0).abs() < 1e-12);
+        let full = rolling_correlation_partial_with(&data, &other, 3, 3, PrecisionMode::Fast).unwrap();
+        assert!(full[2].is_nan() && full[3].is_finite());
+        let default = rolling_correlation_with(&data, &other, 3, PrecisionMode::Fast).unwrap();
+        assert!(full.iter().zip(default.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+
+        assert!(rolling_rank_partial(&data, 3, 4, None).is_err());
+        assert!(rolling_correlation_partial_with(&data, &other, 3, 0, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn cost_aware_smooth_limits_per_bar_change() {
+        let factor = Array1::from_vec(vec![0.0, 1.0, 0.3, 0.35, f64::NAN, 2.0, -2.0]);
+        let result = cost_aware_smooth(&factor, 0.25).unwrap();
+
+        let expected = [0.0, 0.25, 0.3, 0.35, f64::NAN, 2.0, 1.75];
+        for (r, e) in result.iter().zip(expected.iter()) {
+            assert!((r.is_nan() && e.is_nan()) || (r - e).abs() < 1e-12);
+        }
+
+        let unlimited = cost_aware_smooth(&factor, f64::INFINITY).unwrap();
+        assert_eq!(unlimited[6], -2.0);
+        assert!(cost_aware_smooth(&factor, 0.0).is_err());
+        assert!(cost_aware_smooth(&factor, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn rolling_cov_matches_numpy_cov_ddof() {
+        let mut state = 7u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let x = Array1::from_iter((0..40).map(|_| 100.0 + next()));
+        let y = Array1::from_iter((0..40).map(|_| next() - 0.5));
+        let window = 10;
+
+        for mode in [PrecisionMode::Fast, PrecisionMode::Accurate] {
+            let sample = rolling_cov(&x, &y, window, 1, mode).unwrap();
+            let population = rolling_cov(&x, &y, window, 0, mode).unwrap();
+            assert!(sample.slice(s![..window - 1]).iter().all(|v| v.is_nan()));
+
+            for i in window - 1..x.len() {
+                // Two-pass estimate, as numpy.cov(x, y, ddof=1)[0, 1] computes it
+                let (wx, wy) = (x.slice(s![i + 1 - window..=i]), y.slice(s![i + 1 - window..=i]));
+                let (mx, my) = (wx.mean().unwrap(), wy.mean().unwrap());
+                let comoment: f64 = wx.iter().zip(wy.iter()).map(|(a, b)| (a - mx) * (b - my)).sum();
+                assert!((sample[i] - comoment / (window - 1) as f64).abs() < 1e-12, "index {}", i);
+                assert!((population[i] - comoment / window as f64).abs() < 1e-12, "index {}", i);
+            }
+        }
+
+        assert!(rolling_cov(&x, &y, window, window, PrecisionMode::Fast).is_err());
+        assert!(rolling_cov(&x, &y.slice(s![1..]).to_owned(), window, 1, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn rolling_std_ddof_selects_sample_or_population() {
+        let data = Array1::from_vec(vec![2.0, 4.0, f64::NAN, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        let population = rolling_std(&data, 4, 0).unwrap();
+        let sample = rolling_std(&data, 4, 1).unwrap();
+
+        for i in 4..data.len() {
+            let win = window_at(&data, 4, i, true).unwrap();
+            assert!((population[i] - win.std(0.0)).abs() < 1e-12);
+            assert!((sample[i] - win.std(1.0)).abs() < 1e-12);
+        }
+
+        // A single valid observation leaves no degrees of freedom for the sample std
+        let (early, _) = rolling_std_partial(&data, 4, 1, 1).unwrap();
+        assert!(early[0].is_nan());
+        assert!((early[1] - 2.0f64.sqrt()).abs() < 1e-12);
+
+        // With full windows the sample std is the population std scaled by sqrt(n / (n - 1))
+        let prices = Array1::from_vec(vec![10.0, 10.5, 10.2, 10.8, 11.0, 10.7, 11.4]);
+        let (mom_pop, mom_sample) = (momentum(&prices, 3, false, 0).unwrap(), momentum(&prices, 3, false, 1).unwrap());
+        for i in 4..prices.len() {
+            assert!((mom_sample[i] * 1.5f64.sqrt() - mom_pop[i]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rolling_max_with_pos_matches_brute_force() {
+        let data = Array1::from_vec(vec![3.0, 1.0, 4.0, 4.0, f64::NAN, 2.0, 5.0, 1.0, 0.0, -1.0, 2.0]);
+        let window = 4;
+        let (max_value, bars_since) = rolling_max_with_pos(&data, window).unwrap();
+
+        // Same values and warm-up as rolling_max, with bars_since defined exactly where it is
+        assert_eq!(max_value.mapv(f64::to_bits), rolling_max(&data, window).unwrap().mapv(f64::to_bits));
+        assert_eq!(max_value.mapv(f64::is_nan), bars_since.mapv(f64::is_nan));
+        for i in 0..data.len() {
+            if max_value[i].is_nan() {
+                continue;
+            }
+            // Latest position of the maximum among the last `window` finite values
+            let valid: Vec<usize> = (0..=i).filter(|&j| data[j].is_finite()).collect();
+            let (mut best, mut pos) = (f64::NEG_INFINITY, 0);
+            for &j in &valid[valid.len() - window..] {
+                if data[j] >= best {
+                    best = data[j];
+                    pos = j;
+                }
+            }
+            assert_eq!(max_value[i], best, "index {}", i);
+            assert_eq!(bars_since[i], (i - pos) as f64, "index {}", i);
+        }
+        // The tie at indices 2 and 3 reports the later one
+        assert_eq!(bars_since[3], 0.0);
+        // The window at index 5 reaches back past the NaN to the ties, five bars wide
+        assert_eq!((max_value[5], bars_since[5]), (4.0, 2.0));
+
+        // A leading NaN delays the first output until `window` valid values
+        let gappy = Array1::from_vec(vec![f64::NAN, 1.0, 2.0, f64::NAN, 0.5, 3.0, 1.0]);
+        let (max_value, bars_since) = rolling_max_with_pos(&gappy, 3).unwrap();
+        assert!(max_value.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_eq!((max_value[4], bars_since[4]), (2.0, 2.0));
+        assert_eq!((max_value[5], bars_since[5]), (3.0, 0.0));
+        assert_eq!((max_value[6], bars_since[6]), (3.0, 1.0));
+        assert!(rolling_max_with_pos(&data, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_min_with_pos_prefers_latest_tie() {
+        let data = Array1::from_vec(vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0]);
+        let (min_value, bars_since) = rolling_extreme_with_pos(&data, 3, false).unwrap();
+
+        assert!(bars_since[1].is_nan());
+        assert_eq!((min_value[2], bars_since[2]), (1.0, 1.0));
+        // Tie between indices 1 and 3 resolves to index 3
+        assert_eq!((min_value[3], bars_since[3]), (1.0, 0.0));
+        assert_eq!((min_value[5], bars_since[5]), (1.0, 2.0));
+        assert_eq!((min_value[6], bars_since[6]), (2.0, 0.0));
+    }
+
+    #[test]
+    fn rolling_mean_recovers_after_nan_leaves_window() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, f64::NAN, 4.0, 5.0, 6.0, 7.0]);
+        let mean = rolling_mean(&data, 3).unwrap();
+
+        assert!(mean[3].is_nan());
+        // The NaN is skipped, so the window at index 4 is the valid values [2, 3, 4]
+        assert_eq!(mean[4], 3.0);
+        assert_eq!(mean[5], 4.0);
+        assert_eq!(mean[7], 6.0);
+        for i in [2, 4, 5, 6, 7] {
+            assert_close(mean[i], window_at(&data, 3, i, true).unwrap().mean().unwrap());
+        }
+
+        // Same valid windows as rolling_std
+        let std = rolling_std(&data, 3, 0).unwrap();
+        assert_eq!(mean.mapv(f64::is_nan), std.mapv(f64::is_nan));
+    }
+
+    #[test]
+    fn rolling_min_max_track_sawtooth_extremes() {
+        // Period-5 sawtooth 0, 1, 2, 3, 4, 0, 1, ... with a NaN gap
+        let mut data = Array1::from_iter((0..23).map(|i| (i % 5) as f64));
+        data[12] = f64::NAN;
+        let window = 3;
+        let (lo, hi) = (rolling_min(&data, window).unwrap(), rolling_max(&data, window).unwrap());
+
+        assert!(lo.slice(s![..window - 1]).iter().all(|v| v.is_nan()));
+        assert!(lo[12].is_nan() && hi[12].is_nan());
+        for i in window - 1..data.len() {
+            if !data[i].is_finite() {
+                continue;
+            }
+            let win = window_at(&data, window, i, true).unwrap();
+            assert_eq!(lo[i], win.fold(f64::INFINITY, |a, &b| a.min(b)), "index {}", i);
+            assert_eq!(hi[i], win.fold(f64::NEG_INFINITY, |a, &b| a.max(b)), "index {}", i);
+        }
+        // The 4 at index 4 expires from the max once three newer values arrive
+        assert_eq!(hi[6], 4.0);
+        assert_eq!(hi[7], 2.0);
+        assert!(rolling_max(&data, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_quantile_interpolates_like_numpy() {
+        let data = Array1::from_vec(vec![4.0, 1.0, f64::INFINITY, 3.0, 2.0, 8.0, f64::NAN, 5.0]);
+        let q = rolling_quantile(&data, 4, 0.3).unwrap();
+
+        // Index 4 sees [1, 2, 3, 4]: position 0.9 lies between 1 and 2
+        assert!(q.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_close(q[4], 1.9);
+        // Index 7 sees [2, 3, 5, 8] sorted
+        assert_close(q[7], 2.9);
+        assert!(q[6].is_nan());
+
+        let median = rolling_median(&data, 4).unwrap();
+        let (reference, _) = rolling_median_mad(&data, 4).unwrap();
+        for (a, b) in median.iter().zip(reference.iter()) {
+            assert!((a.is_nan() && b.is_nan()) || a == b);
+        }
+
+        assert!(rolling_quantile(&data, 4, 1.5).is_err());
+        assert!(rolling_quantile(&data, 4, -0.1).is_err());
+    }
+
+    #[test]
+    fn weighted_cs_rank_ic_reduces_to_spearman() {
+        let factor = Array2::from_shape_vec((3, 5), vec![
+            1.0, 2.0, 3.0, 4.0, 5.0,
+            0.3, 0.1, 0.2, 0.2, f64::NAN,
+            1.0, 2.0, 3.0, 4.0, 5.0,
+        ]).unwrap();
+        let fwd = Array2::from_shape_vec((3, 5), vec![
+            0.01, 0.03, 0.02, 0.05, 0.04,
+            -0.02, 0.01, 0.00, 0.02, 0.09,
+            0.05, 0.04, 0.03, 0.02, 0.01,
+        ]).unwrap();
+        let equal = Array2::from_elem((3, 5), 2.0);
+        let ic = weighted_cs_rank_ic(&factor, &fwd, &equal).unwrap();
+
+        // Spearman of ranks [1..5] and [1, 3, 2, 5, 4]: 1 - 6 * 4 / (5 * 24)
+        assert_close(ic[0], 0.8);
+        // Row 1 drops the NaN asset and has a tie: Pearson of average ranks
+        let (rf, rr) = ([4.0, 1.0, 2.5, 2.5], [1.0, 3.0, 2.0, 4.0]);
+        assert_close(ic[1], weighted_pearson(&rf, &rr, &[1.0; 4]));
+        assert_close(ic[2], -1.0);
+
+        // Down-weighting one name of each swapped pair moves the IC towards +1
+        let mut tilted = equal.clone();
+        tilted[[0, 2]] = 0.5;
+        tilted[[0, 4]] = 0.5;
+        let tilted_ic = weighted_cs_rank_ic(&factor, &fwd, &tilted).unwrap()[0];
+        assert!(tilted_ic > 0.8 && tilted_ic < 1.0);
+        tilted[[0, 2]] = 0.0;
+        tilted[[0, 4]] = 0.0;
+        assert_close(weighted_cs_rank_ic(&factor, &fwd, &tilted).unwrap()[0], 1.0);
+
+        assert!(weighted_cs_rank_ic(&factor, &fwd, &Array2::from_elem((3, 4), 1.0)).is_err());
+        assert!(weighted_cs_rank_ic(&factor, &fwd, &Array2::from_elem((3, 5), -1.0)).is_err());
+    }
+
+    #[test]
+    fn regime_label_combines_trend_and_vol() {
+        // Calm uptrend, then a volatile downtrend
+        let mut prices: Vec<f64> = (0..30).map(|i| 100.0 + i as f64 + 0.1 * (i % 2) as f64).collect();
+        for i in 0..30 {
+            prices.push(129.0 - i as f64 + if i % 2 == 0 { 3.0 } else { -3.0 });
+        }
+        let prices = Array1::from_vec(prices);
+        let labels = regime_label(&prices, 5, 5, 0.8).unwrap();
+
+        // The vol rank needs 1 + (5 - 1) + (5 - 1) bars of history
+        assert!(labels.slice(s![..9]).iter().all(|&l| l == REGIME_UNKNOWN));
+        assert!(labels.slice(s![9..30]).iter().all(|&l| l == 0 || l == 1));
+        // The jump in vol after the switch ranks at the top of its window
+        assert_eq!(labels[33], 3);
+        assert!(labels.slice(s![40..]).iter().all(|&l| l == 2 || l == 3));
+
+        let mut gappy = prices.clone();
+        gappy[20] = f64::NAN;
+        assert_eq!(regime_label(&gappy, 5, 5, 0.8).unwrap()[20], REGIME_UNKNOWN);
+        assert!(regime_label(&prices, 5, 5, 1.0).is_err());
+    }
+
+    #[test]
+    fn rolling_skew_and_kurt_match_sample_formulas() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0, 10.0, f64::NAN, 4.0, 4.0, 4.0, 4.0, 4.0]);
+        let (skew, kurt) = (rolling_skew(&data, 5).unwrap(), rolling_kurt(&data, 5).unwrap());
+
+        // pandas: Series([1, 2, 3, 4, 10]).skew() / .kurt()
+        assert!(skew.slice(s![..4]).iter().all(|v| v.is_nan()));
+        assert_close(skew[4], 1.6970562748477143);
+        assert_close(kurt[4], 3.152);
+        assert!(skew[5].is_nan());
+        // A constant window has zero variance
+        assert!(skew[10].is_nan() && kurt[10].is_nan());
+
+        // Symmetric windows have zero skew
+        let symmetric = Array1::from_vec(vec![-2.0, -1.0, 0.0, 1.0, 2.0]);
+        assert_close(rolling_skew(&symmetric, 5).unwrap()[4], 0.0);
+        assert!(rolling_skew(&data, 2).is_err());
+        assert!(rolling_kurt(&data, 3).is_err());
+    }
+
+    #[test]
+    fn rolling_residualize_uses_lagged_beta() {
+        let market = Array1::from_vec(vec![0.01, -0.02, 0.015, 0.03, -0.01, f64::NAN, 0.02, -0.005]);
+        let mut asset = market.mapv(|m| 0.001 + 1.5 * m);
+        // A shock at t = 4 must not leak into its own hedge ratio
+        asset[4] += 0.05;
+        let resid = rolling_residualize(&asset, &market, 3).unwrap();
+
+        assert!(resid.slice(s![..3]).iter().all(|v| v.is_nan()));
+        // Betas up to t = 3 are exactly 1.5, leaving the intercept and the shock
+        assert_close(resid[3], 0.001);
+        assert_close(resid[4], 0.001 + 0.05);
+        assert!(resid[5].is_nan());
+        // After the shock the beta moves, but t = 6 still uses the beta from t = 4
+        let beta = rolling_beta(&market, &asset, 3, true).unwrap();
+        assert_close(resid[6], asset[6] - beta[4] * market[6]);
+        assert!(rolling_residualize(&asset, &market.slice(s![1..]).to_owned(), 3).is_err());
+    }
+
+    #[test]
+    fn ema_recursion_adjust_and_nan_carry() {
+        let data = Array1::from_vec(vec![f64::NAN, 1.0, 2.0, f64::NAN, 3.0]);
+        let alpha = span_to_alpha(3.0).unwrap();
+        assert_eq!(alpha, 0.5);
+
+        let plain = ema(&data, alpha, false).unwrap();
+        assert!(plain[0].is_nan());
+        assert_eq!(plain.slice(s![1..]).to_vec(), vec![1.0, 1.5, 1.5, 2.25]);
+
+        // pandas ewm(alpha=0.5, adjust=True, ignore_na=True).mean()
+        let adjusted = ema(&data, alpha, true).unwrap();
+        assert_close(adjusted[2], (2.0 + 0.5) / 1.5);
+        assert_eq!(adjusted[3], adjusted[2]);
+        assert_close(adjusted[4], (3.0 + 0.5 * 2.0 + 0.25) / 1.75);
+
+        assert!(span_to_alpha(0.5).is_err());
+        assert!(ema(&data, 0.0, false).is_err());
+    }
+
+    #[test]
+    fn near_zero_denominators_are_treated_as_zero() {
+        for tiny in [1e-16, 1e-300] {
+            let prices = Array1::from_vec(vec![tiny, 1.0]);
+            assert!(pct_change(&prices, 1, ZeroPrev::Nan).unwrap()[1].is_nan(), "prev {}", tiny);
+            assert_eq!(pct_change(&prices, 1, ZeroPrev::Inf).unwrap()[1], f64::INFINITY);
+        }
+        // Ordinary small denominators are untouched
+        let prices = Array1::from_vec(vec![1e-6, 2e-6]);
+        assert_close(pct_change(&prices, 1, ZeroPrev::Nan).unwrap()[1], 1.0);
+
+        // A flat window whose std is pure rounding noise at the data's scale
+        assert!(floored_std(1e-14, 1e3, 0.0).is_nan());
+        assert_eq!(floored_std(1e-14, 1e3, 0.5), 0.5);
+        assert_eq!(floored_std(1e-14, 1.0, 0.0), 1e-14);
+
+        assert!(is_near_zero_with(1e-9, 1.0, 1e-6, 0.0));
+        assert!(!is_near_zero_with(1e-9, 1.0, 0.0, 1e-12));
+        assert!(is_near_zero_with(0.0, f64::NAN, 0.0, 0.0));
+    }
+
+    #[test]
+    fn decay_linear_weights_recent_bars_most() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 3.0, f64::NAN, 6.0]);
+        let result = decay_linear(&data, 3).unwrap();
+
+        assert!(result[0].is_nan() && result[1].is_nan());
+        // (1*1 + 2*2 + 3*3) / 6
+        assert_close(result[2], 14.0 / 6.0);
+        // The NaN's weight 3 is dropped: (1*2 + 2*3) / 3
+        assert_close(result[3], 8.0 / 3.0);
+        // (1*3 + 3*6) / 4
+        assert_close(result[4], 21.0 / 4.0);
+
+        let gap = Array1::from_vec(vec![f64::NAN, f64::NAN, f64::NAN, 1.0]);
+        let result = decay_linear(&gap, 3).unwrap();
+        assert!(result[2].is_nan());
+        assert_eq!(result[3], 1.0);
+        assert!(decay_linear(&data, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_vol_of_vol_aligns_both_stages() {
+        let returns = Array1::from_iter((0..30).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 } * (1.0 + 0.1 * i as f64)));
+        let vov = rolling_vol_of_vol(&returns, 5, 4).unwrap();
+
+        // Warm-up covers both windows: first output at 5 + 4 - 2
+        assert!(vov.slice(s![..7]).iter().all(|v| v.is_nan()));
+        let vol = rolling_std(&returns, 5, 0).unwrap();
+        for i in 7..returns.len() {
+            let win = vol.slice(s![i - 3..=i]).to_owned();
+            assert_close(vov[i], win.std(0.0));
+        }
+    }
+
+    #[test]
+    fn higher_moments_are_stable_under_large_offsets() {
+        let pattern = [0.3, -1.2, 0.8, 2.5, -0.4, 0.1, -2.2, 1.7, 0.9, -0.6, 3.1, -1.5, 0.2, 0.0, -0.9, 1.1, 0.4, -3.0, 0.7, 1.9];
+        let base = Array1::from_iter((0..200).map(|i| pattern[(i * 7) % 20] * (1.0 + (i % 3) as f64)));
+        let (skew_ref, kurt_ref) = (rolling_skew(&base, 20).unwrap(), rolling_kurt(&base, 20).unwrap());
+        for offset in [45_000.0, 1e6] {
+            let shifted = base.mapv(|v| v + offset);
+            let (skew, kurt) = (rolling_skew(&shifted, 20).unwrap(), rolling_kurt(&shifted, 20).unwrap());
+            for i in 19..200 {
+                assert!((skew[i] - skew_ref[i]).abs() < 1e-6, "{}: {} != {}", i, skew[i], skew_ref[i]);
+                assert!((kurt[i] - kurt_ref[i]).abs() < 1e-6, "{}: {} != {}", i, kurt[i], kurt_ref[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn fast_moments_are_stable_under_large_offsets() {
+        let mut state = 7u64;
+        let noise: Vec<f64> = (0..200)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 * 0.01
+            })
+            .collect();
+        let base = Array1::from_vec(noise);
+        let shifted = base.mapv(|v| v + 45_000.0);
+        let other = Array1::from_vec((0..200).map(|i| ((i * 37) % 11) as f64).collect());
+
+        let (std_ref, _) = rolling_std_partial_with(&base, 20, 20, 1, PrecisionMode::Accurate, true).unwrap();
+        let (std_fast, _) = rolling_std_partial_with(&shifted, 20, 20, 1, PrecisionMode::Fast, true).unwrap();
+        let corr_ref = rolling_correlation_with(&base, &other, 20, PrecisionMode::Accurate).unwrap();
+        let corr_fast = rolling_correlation_with(&shifted, &other, 20, PrecisionMode::Fast).unwrap();
+        let cov_ref = rolling_cov(&base, &other, 20, 1, PrecisionMode::Accurate).unwrap();
+        let cov_fast = rolling_cov(&shifted, &other, 20, 1, PrecisionMode::Fast).unwrap();
+        for i in 19..200 {
+            assert!(std_fast[i].is_finite() && std_fast[i] > 0.0);
+            assert!((std_fast[i] - std_ref[i]).abs() < 1e-6 * std_ref[i]);
+            assert!((corr_fast[i] - corr_ref[i]).abs() < 1e-6);
+            assert!((cov_fast[i] - cov_ref[i]).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn groupby_rolling_matches_per_group_kernels() {
+        let data = Array1::from_vec(vec![1.0, 10.0, 2.0, 20.0, 3.0, 40.0, 5.0, 30.0]);
+        let groups = Array1::from_vec(vec![7, 3, 7, 3, 7, 3, 7, 3]);
+        let params = GroupParams::default();
+        let result = groupby_rolling(&data, &groups, GroupFunc::Mean, 2, &params).unwrap();
+
+        let a = rolling_mean(&Array1::from_vec(vec![1.0, 2.0, 3.0, 5.0]), 2).unwrap();
+        let b = rolling_mean(&Array1::from_vec(vec![10.0, 20.0, 40.0, 30.0]), 2).unwrap();
+        for k in 0..4 {
+            assert_eq!(result[2 * k].to_bits(), a[k].to_bits());
+            assert_eq!(result[2 * k + 1].to_bits(), b[k].to_bits());
+        }
+
+        let std = groupby_rolling(&data, &groups, GroupFunc::Std, 2, &GroupParams { ddof: 1, ..params }).unwrap();
+        assert_close(std[7], (50.0_f64).sqrt());
+        assert!(groupby_rolling(&data, &Array1::from_vec(vec![0]), GroupFunc::Mean, 2, &params).is_err());
+        assert!("ewm".parse::<GroupFunc>().is_err());
+    }
+
+    #[test]
+    fn flat_windows_have_zero_std() {
+        let data = Array1::from_vec(vec![0.1, 0.1, 0.1, 0.1, 0.7, 0.3, 0.3, 0.3, 0.3]);
+        for mode in [PrecisionMode::Fast, PrecisionMode::Accurate] {
+            let (std, _) = rolling_std_partial_with(&data, 3, 3, 0, mode, true).unwrap();
+            assert_eq!(std[2], 0.0);
+            assert_eq!(std[3], 0.0);
+            assert!(std[4] > 0.0);
+            // Flat again once the jump has left the window, despite the rounding residue
+            assert_eq!(std[8], 0.0);
+        }
+
+        let (strict, _) = rolling_std_partial_with(&data, 3, 3, 0, PrecisionMode::Fast, false).unwrap();
+        assert_eq!(strict[2], 0.0);
+        assert!(strict.iter().skip(2).all(|v| v.is_nan() || *v >= 0.0));
+        assert!(rolling_zscore(&data, 3, 0, 0.0).unwrap()[3].is_nan());
+    }
+
+    #[test]
+    fn rolling_kelly_scales_mean_over_variance() {
+        let returns = Array1::from_vec(vec![0.01, 0.03, 0.02, 0.02, 0.02, 0.02]);
+        let full = rolling_kelly(&returns, 3, 1.0, None).unwrap();
+        let half = rolling_kelly(&returns, 3, 0.5, None).unwrap();
+        let capped = rolling_kelly(&returns, 3, 1.0, Some(50.0)).unwrap();
+
+        assert!(full[1].is_nan());
+        // window [0.01, 0.03, 0.02]: mean 0.02, population variance 0.0002 / 3
+        assert_close(full[2], 0.02 / (0.0002 / 3.0));
+        assert_close(half[2], full[2] / 2.0);
+        assert_eq!(capped[2], 50.0);
+        // Flat window: zero variance
+        assert!(full[5].is_nan());
+        assert!(rolling_kelly(&returns, 3, 0.0, None).is_err());
+        assert!(rolling_kelly(&returns, 3, 1.0, Some(-1.0)).is_err());
+    }
+
+    #[test]
+    fn apply_columns_keeps_nans_per_column() {
+        let mut panel = Array2::from_shape_fn((8, 3), |(i, j)| 100.0 + (i * (j + 1)) as f64 + (i % 3) as f64);
+        panel[[4, 1]] = f64::NAN;
+        let result = apply_columns(&panel, |c| mean_reversion(c, 3, false, 0.0, 0)).unwrap();
+
+        assert_eq!(result.dim(), panel.dim());
+        for j in 0..3 {
+            let expected = mean_reversion(&panel.column(j).to_owned(), 3, false, 0.0, 0).unwrap();
+            for i in 0..8 {
+                assert_eq!(result[[i, j]].to_bits(), expected[i].to_bits());
+            }
+        }
+        assert!(result[[4, 1]].is_nan());
+        assert!(result[[4, 0]].is_finite() && result[[4, 2]].is_finite());
+        assert!(apply_columns(&panel, |_| Ok(Array1::zeros(2))).is_err());
+    }
+
+    #[test]
+    fn rolling_current_drawdown_tracks_window_peak() {
+        let prices = Array1::from_vec(vec![100.0, 110.0, 99.0, f64::NAN, 88.0, 90.0, 95.0]);
+        let dd = rolling_current_drawdown(&prices, 3).unwrap();
+
+        assert!(dd[1].is_nan());
+        assert_close(dd[2], 99.0 / 110.0 - 1.0);
+        assert!(dd[3].is_nan());
+        // NaN is skipped, so the window is [110, 99, 88]
+        assert_close(dd[4], 88.0 / 110.0 - 1.0);
+        assert_close(dd[5], 90.0 / 99.0 - 1.0);
+        assert_eq!(dd[6], 0.0);
+        assert!(dd.iter().all(|v| v.is_nan() || *v <= 0.0));
+    }
+
+    #[test]
+    fn parallel_columns_are_bit_identical() {
+        let mut state = 11u64;
+        let mut panel = Array2::from_shape_fn((120, 40), |_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            100.0 + (state >> 11) as f64 / (1u64 << 53) as f64
+        });
+        panel[[30, 7]] = f64::NAN;
+        panel[[31, 7]] = f64::INFINITY;
+
+        let serial = apply_columns(&panel, |c| momentum(c, 10, false, 1)).unwrap();
+        let parallel = apply_columns_par(&panel, |c| momentum(c, 10, false, 1)).unwrap();
+        assert!(serial.iter().zip(parallel.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+
+        let serial = apply_columns(&panel, |c| mean_reversion(c, 10, false, 0.0, 0)).unwrap();
+        let parallel = apply_columns_par(&panel, |c| mean_reversion(c, 10, false, 0.0, 0)).unwrap();
+        assert!(serial.iter().zip(parallel.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn stride_windows_keeps_block_ends() {
+        let data = Array1::from_vec((0..10).map(|i| i as f64).collect());
+        let means = rolling_mean(&data, 3).unwrap();
+
+        let stamped = stride_windows(means.clone(), 3, 3, false).unwrap();
+        assert_eq!(stamped.len(), 10);
+        assert_eq!(stamped[2], 1.0);
+        assert_eq!(stamped[5], 4.0);
+        assert_eq!(stamped[8], 7.0);
+        assert_eq!(stamped.iter().filter(|v| v.is_finite()).count(), 3);
+
+        // (10 - 3) / 3 + 1 disjoint blocks: [0..3), [3..6), [6..9)
+        let compact = stride_windows(means.clone(), 3, 3, true).unwrap();
+        assert_eq!(compact.to_vec(), vec![1.0, 4.0, 7.0]);
+        assert_eq!(stride_windows(means.clone(), 3, 1, true).unwrap().len(), 8);
+        assert_eq!(stride_windows(means.clone(), 3, 1, false).unwrap().len(), 10);
+        assert_eq!(stride_windows(Array1::zeros(2), 3, 3, true).unwrap().len(), 0);
+        assert!(stride_windows(means, 3, 0, false).is_err());
+    }
+
+    #[test]
+    fn cs_rank_averages_ties_and_skips_nans() {
+        let nan = f64::NAN;
+        let data = Array2::from_shape_vec((4, 4), vec![
+            3.0, 1.0, 2.0, 5.0,
+            1.0, 2.0, 2.0, nan,
+            nan, nan, nan, nan,
+            nan, 7.0, f64::INFINITY, nan,
+        ]).unwrap();
+        let ranks = cs_rank(&data);
+
+        assert_eq!(ranks.row(0).to_vec(), vec![2.0 / 3.0, 0.0, 1.0 / 3.0, 1.0]);
+        assert_eq!(ranks[[1, 0]], 0.0);
+        assert_eq!(ranks[[1, 1]], 0.75);
+        assert_eq!(ranks[[1, 2]], 0.75);
+        assert!(ranks[[1, 3]].is_nan());
+        assert!(ranks.row(2).iter().all(|v| v.is_nan()));
+        assert_eq!(ranks[[3, 1]], 0.5);
+        assert!(ranks[[3, 2]].is_nan());
+    }
+
+    #[test]
+    fn delay_and_delta_shift_by_periods() {
+        let data = Array1::from_vec(vec![1.0, 4.0, f64::INFINITY, 10.0, 11.0]);
+        let lagged = delay(&data, 2).unwrap();
+        let diff = delta(&data, 2).unwrap();
+
+        assert!(lagged[0].is_nan() && lagged[1].is_nan());
+        assert_eq!(lagged[3], 4.0);
+        assert!(lagged[4].is_nan());
+        assert!(diff[1].is_nan() && diff[2].is_nan());
+        assert_eq!(diff[3], 6.0);
+        assert!(diff[4].is_nan());
+        assert!(delay(&data, 9).unwrap().iter().all(|v| v.is_nan()));
+        assert!(delta(&data, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_robust_corr_drops_outlier_pairs() {
+        let mut x: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let mut y: Vec<f64> = (0..20).map(|i| 2.0 * i as f64 + (i % 3) as f64 * 0.1).collect();
+        x[15] = 5.0;
+        y[15] = 60.0;
+        x[16] = f64::NAN;
+        let (x, y) = (Array1::from_vec(x), Array1::from_vec(y));
+
+        let plain = rolling_correlation(&x, &y, 10).unwrap();
+        let robust = rolling_robust_corr(&x, &y, 10, 0.1).unwrap();
+        let untrimmed = rolling_robust_corr(&x, &y, 10, 0.0).unwrap();
+
+        assert!(robust[8].is_nan());
+        assert!(robust[16].is_nan());
+        assert_close(untrimmed[19], plain[19]);
+        assert!(plain[19] < 0.9);
+        assert!(robust[19] > 0.99);
+        assert!(rolling_robust_corr(&x, &y, 10, 0.5).is_err());
+    }
+
+    #[test]
+    fn rolling_partial_corr_removes_common_driver() {
+        let mut state = 5u64;
+        let mut noise = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        };
+        let z: Vec<f64> = (0..80).map(|_| noise()).collect();
+        let x: Vec<f64> = z.iter().map(|&v| v + 0.3 * noise()).collect();
+        let y: Vec<f64> = z.iter().map(|&v| v + 0.3 * noise()).collect();
+        let (x, y, z) = (Array1::from_vec(x), Array1::from_vec(y), Array1::from_vec(z));
+
+        let raw = rolling_correlation(&x, &y, 40).unwrap();
+        let partial = rolling_partial_corr(&x, &y, &z, 40, PrecisionMode::Fast).unwrap();
+        assert!(partial[38].is_nan());
+        assert!(raw[79] > 0.8);
+        assert!(partial[79].abs() < 0.4);
+
+        // Controlling for a copy of x leaves a zero denominator
+        assert!(rolling_partial_corr(&x, &y, &x, 40, PrecisionMode::Fast).unwrap()[79].is_nan());
+        assert!(rolling_partial_corr(&x, &y, &z.slice(s![..10]).to_owned(), 40, PrecisionMode::Fast).is_err());
+    }
+
+    #[test]
+    fn scale_to_gross_hits_target_exposure() {
+        let data = Array1::from_vec(vec![2.0, -1.0, f64::NAN, 1.0]);
+        let scaled = scale_to_gross(&data, 2.0).unwrap();
+
+        assert_eq!(scaled[0], 1.0);
+        assert_eq!(scaled[1], -0.5);
+        assert!(scaled[2].is_nan());
+        assert_close(scaled.iter().filter(|v| v.is_finite()).map(|v| v.abs()).sum::<f64>(), 2.0);
+        let zeros = Array1::from_vec(vec![0.0, f64::NAN, -0.0]);
+        assert!(scale_to_gross(&zeros, 1.0).unwrap().iter().all(|v| v.is_nan()));
+        assert!(scale_to_gross(&data, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn rolling_snr_is_abs_mean_over_std() {
+        let data = Array1::from_vec(vec![1.0, 3.0, -5.0, f64::NAN, 2.0, 2.0, 2.0]);
+        let snr = rolling_snr(&data, 3).unwrap();
+
+        assert!(snr[1].is_nan());
+        // window [1, 3, -5]: mean -1/3, population variance 104/9
+        assert_close(snr[2], (1.0 / 3.0) / (104.0_f64 / 9.0).sqrt());
+        assert!(snr[3].is_nan());
+        assert!(snr[4].is_finite());
+        // Flat window: zero std
+        assert!(snr[6].is_nan());
+    }
+
+    #[test]
+    fn signed_power_keeps_sign() {
+        let data = Array1::from_vec(vec![-4.0, 9.0, 0.0, f64::NAN, f64::NEG_INFINITY]);
+        let root = signed_power(&data, 0.5);
+        assert_eq!(root[0], -2.0);
+        assert_eq!(root[1], 3.0);
+        assert_eq!(root[2], 0.0);
+        assert!(root[3].is_nan());
+        assert_eq!(root[4], f64::NEG_INFINITY);
+        assert_eq!(signed_power(&data, -1.0)[2], 0.0);
+    }
+
+    #[test]
+    fn eval_expr_matches_composed_primitives() {
+        let mut state = 13u64;
+        let mut draw = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let high = Array1::from_iter((0..40).map(|_| 100.0 + draw()));
+        let volume = Array1::from_iter((0..40).map(|_| 1000.0 * draw()));
+        let inputs: HashMap<String, Array1<f64>> =
+            [("high".to_string(), high.clone()), ("volume".to_string(), volume.clone())].into_iter().collect();
+
+        let result = eval_expr_arrays("-ts_rank(ts_std(high, 10), 10) * correlation(high, volume, 10)", &inputs).unwrap();
+        let expected = alpha101_42(&high, &volume).unwrap();
+        for i in 0..40 {
+            assert!(result[i] == expected[i] || (result[i].is_nan() && expected[i].is_nan()), "index {}", i);
+        }
+        assert!(result[30].is_finite());
+
+        // rank(x) is the causal expanding_rank, so extending the inputs leaves earlier bars alone
+        let formula = "rank(ts_std(high, 10)) * correlation(high, volume, 10)";
+        let result = eval_expr_arrays(formula, &inputs).unwrap();
+        let expected = expanding_rank(&rolling_std(&high, 10, 0).unwrap()) * rolling_correlation(&high, &volume, 10).unwrap();
+        for i in 0..40 {
+            assert!(result[i] == expected[i] || (result[i].is_nan() && expected[i].is_nan()), "index {}", i);
+        }
+        assert!(result[30].is_finite());
+        let truncated: HashMap<String, Array1<f64>> =
+            inputs.iter().map(|(name, x)| (name.clone(), x.slice(s![..25]).to_owned())).collect();
+        let prefix = eval_expr_arrays(formula, &truncated).unwrap();
+        for i in 0..25 {
+            assert!(prefix[i] == result[i] || (prefix[i].is_nan() && result[i].is_nan()), "index {}", i);
+        }
+
+        let arith = eval_expr_arrays("-(1 + 2 * 3) / 7 + delta(high, 1) - delta(high, 1)", &inputs).unwrap();
+        assert!(arith[0].is_nan());
+        assert_close(arith[5], -1.0);
+        assert!(eval_expr_arrays("high / 0", &inputs).unwrap().iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn eval_expr_reports_bad_formulas() {
+        let inputs: HashMap<String, Array1<f64>> = [("close".to_string(), Array1::zeros(5))].into_iter().collect();
+        let message = |f: &str| eval_expr_arrays(f, &inputs).unwrap_err().to_string();
+
+        assert!(message("clse + 1").contains("unknown input 'clse'"));
+        assert!(message("foo(close)").contains("unknown function 'foo'"));
+        assert!(message("mean(close)").contains("mean expects 2 arguments, got 1"));
+        assert!(message("mean(close, 2.5)").contains("positive integer"));
+        assert!(message("(close + 1").contains("expected ')'"));
+        assert!(message("close $ 1").contains("unexpected character '$'"));
+        assert!(message("close close").contains("unexpected"));
+        assert!(message("rank(close, 5)").contains("rank expects 1 argument, got 2"));
+
+        let uneven: HashMap<String, Array1<f64>> =
+            [("a".to_string(), Array1::zeros(3)), ("b".to_string(), Array1::zeros(4))].into_iter().collect();
+        assert!(eval_expr_arrays("a + b", &uneven).is_err());
+    }
+
+    #[test]
+    fn winsorize_clamps_to_quantiles_and_mad() {
+        let data = Array1::from_vec(vec![0.0, 1.0, 2.0, 3.0, 4.0, f64::NAN, 100.0, f64::NEG_INFINITY]);
+        // Finite values [0, 1, 2, 3, 4, 100]: 10% at 0.5, 90% at 52
+        let clipped = winsorize(&data, 0.1, 0.9).unwrap();
+        assert_eq!(clipped[0], 0.5);
+        assert_eq!(clipped[3], 3.0);
+        assert!(clipped[5].is_nan());
+        assert_eq!(clipped[6], 52.0);
+        assert_eq!(clipped[7], 0.5);
+        assert!(winsorize(&data, 0.9, 0.1).is_err());
+        assert!(winsorize(&data, -0.1, 0.9).is_err());
+
+        // Median 2.5, MAD 1.5: bounds [-0.5, 5.5]
+        let robust = winsorize_mad(&data, 2.0).unwrap();
+        assert_eq!(robust[0], 0.0);
+        assert_eq!(robust[6], 5.5);
+        assert_eq!(robust[7], -0.5);
+        let flat = Array1::from_vec(vec![1.0, 1.0, 1.0, 9.0]);
+        assert_eq!(winsorize_mad(&flat, 3.0).unwrap(), flat);
+        assert!(winsorize_mad(&data, 0.0).is_err());
+    }
+
+    #[test]
+    fn apply_skipna_blanks_windows_touching_non_finite_values() {
+        let x = Array1::from_vec(vec![1.0, 2.0, f64::NAN, 4.0, 5.0, 6.0, 7.0]);
+        let y = Array1::from_vec(vec![1.0, f64::INFINITY, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        let (mean, _) = rolling_mean_partial(&x, 3, 1).unwrap();
+        let kept = apply_skipna(mean.clone(), true, &[&x], 3);
+        assert!(kept.iter().zip(mean.iter()).all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+
+        let masked = apply_skipna(mean, false, &[&x], 3);
+        assert!(masked.iter().take(5).skip(2).all(|v| v.is_nan()));
+        assert_close(masked[5], 5.0);
+        assert_close(masked[1], 1.5);
+
+        let joint = apply_skipna(Array1::zeros(7), false, &[&x, &y], 2);
+        let blanked: Vec<bool> = joint.iter().map(|v| v.is_nan()).collect();
+        assert_eq!(blanked, vec![false, true, true, true, false, false, false]);
+    }
+
+    #[test]
+    fn neutralize_removes_exposure_and_skips_nan_rows() {
+        let nan = f64::NAN;
+        let style = [0.5, -1.0, 2.0, 0.0, 1.5, -0.5, 3.0];
+        let noise = [0.1, -0.2, 0.05, 0.15, -0.1, 0.0, 0.3];
+        let mut factor = Array1::from_iter(style.iter().zip(noise.iter()).map(|(s, e)| 2.0 + 3.0 * s + e));
+        factor[6] = nan;
+        let exposures = Array2::from_shape_vec((7, 1), style.to_vec()).unwrap();
+        let resid = neutralize(&factor, &exposures).unwrap();
+
+        assert!(resid[6].is_nan());
+        let fitted: Vec<f64> = resid.iter().take(6).copied().collect();
+        assert!(fitted.iter().sum::<f64>().abs() < 1e-10);
+        assert!(fitted.iter().zip(style.iter()).map(|(r, s)| r * s).sum::<f64>().abs() < 1e-10);
+
+        let exact = Array1::from_iter(style.iter().map(|s| 1.0 - s));
+        assert!(neutralize(&exact, &exposures).unwrap().iter().all(|r| r.abs() < 1e-12));
+        let constant = Array2::from_elem((7, 1), 1.0);
+        assert!(neutralize(&exact, &constant).unwrap().iter().all(|r| r.is_nan()));
+        assert!(neutralize(&exact, &Array2::zeros((6, 1))).is_err());
+    }
+
+    #[test]
+    fn cumulative_ops_skip_and_carry_nans() {
+        let nan = f64::NAN;
+        let returns = Array1::from_vec(vec![0.01, -0.02, 0.03, 0.005, -0.01]);
+        let growth = cumprod(&returns.mapv(|r| 1.0 + r));
+        let mut price = 100.0;
+        for (i, r) in returns.iter().enumerate() {
+            price *= 1.0 + r;
+            assert_close(100.0 * growth[i], price);
+        }
+
+        let data = Array1::from_vec(vec![nan, 2.0, 1.0, nan, 3.0, f64::INFINITY, 0.5]);
+        let sum = cumsum(&data);
+        assert!(sum[0].is_nan() && sum[3].is_nan() && sum[5].is_nan());
+        assert_close(sum[4], 6.0);
+        assert_close(sum[6], 6.5);
+
+        let high = cummax(&data);
+        let finite: Vec<f64> = high.iter().copied().filter(|v| v.is_finite()).collect();
+        assert!(finite.windows(2).all(|w| w[1] >= w[0]));
+        assert_eq!(finite, vec![2.0, 2.0, 3.0, 3.0]);
+        let low = cummin(&data);
+        assert_close(low[6], 0.5);
+        assert_close(low[4], 1.0);
+    }
+
+    #[test]
+    fn max_drawdown_is_worst_decline_from_running_peak() {
+        let nan = f64::NAN;
+        let prices = Array1::from_vec(vec![nan, nan, 100.0, 120.0, 90.0, 110.0, 60.0, 130.0]);
+        assert_close(max_drawdown(&prices), 60.0 / 120.0 - 1.0);
+
+        let rising = Array1::from_vec(vec![nan, 1.0, 2.0, 2.0, 3.0]);
+        assert_eq!(max_drawdown(&rising), 0.0);
+        assert!(max_drawdown(&Array1::from_elem(3, nan)).is_nan());
+    }
+
+    #[test]
+    fn rolling_sharpe_matches_hand_computation() {
+        let returns = Array1::from_vec(vec![0.01, -0.02, 0.03, 0.005, 0.005, 0.005]);
+        let sharpe = rolling_sharpe(&returns, 3, 252.0).unwrap();
+        assert!(sharpe[0].is_nan() && sharpe[1].is_nan());
+        // mean 0.02 / 3, population std sqrt(((0.01 - m)² + (-0.02 - m)² + (0.03 - m)²) / 3)
+        let m: f64 = 0.02 / 3.0;
+        let sd = (((0.01 - m).powi(2) + (-0.02 - m).powi(2) + (0.03 - m).powi(2)) / 3.0).sqrt();
+        assert_close(sharpe[2], m / sd * 252.0_f64.sqrt());
+        assert!(sharpe[5].is_nan());
+        assert!(rolling_sharpe(&returns, 3, 0.0).is_err());
+    }
+
+    #[test]
+    fn rolling_downside_dev_counts_full_window() {
+        let returns = Array1::from_vec(vec![0.02, -0.01, 0.03, -0.03, 0.01, 0.02, 0.04, 0.01]);
+        let dd = rolling_downside_dev(&returns, 4, 0.0).unwrap();
+        assert!(dd.iter().take(3).all(|v| v.is_nan()));
+        assert_close(dd[3], ((0.0001 + 0.0009) / 4.0_f64).sqrt());
+        assert_close(dd[7], 0.0);
+        // A 1% hurdle turns the 1% return into a zero shortfall, not a negative one
+        assert_close(rolling_downside_dev(&returns, 4, 0.01).unwrap()[4], ((0.0004 + 0.0016) / 4.0_f64).sqrt());
+
+        let sortino = rolling_sortino(&returns, 4, 0.0, 1.0).unwrap();
+        assert_close(sortino[3], (0.01 / 4.0) / dd[3]);
+        assert!(sortino[7].is_nan());
+    }
+
+    #[test]
+    fn ewm_var_matches_weighted_definition() {
+        let data = Array1::from_vec(vec![1.0, 3.0, f64::NAN, 2.0, 6.0, 4.0]);
+        let alpha = span_to_alpha(3.0).unwrap();
+        let unbiased = ewm_var(&data, alpha, false).unwrap();
+        let biased = ewm_var(&data, alpha, true).unwrap();
+        assert!(unbiased[0].is_nan());
+        assert_eq!(biased[0], 0.0);
+
+        let valid = [1.0, 3.0, 2.0, 6.0, 4.0];
+        for (i, t) in [(1, 1), (3, 2), (4, 3), (5, 4)] {
+            let w: Vec<f64> = (0..=t).map(|k| (1.0 - alpha).powi(t - k)).collect();
+            let (sw, sw2) = (w.iter().sum::<f64>(), w.iter().map(|v| v * v).sum::<f64>());
+            let mean = w.iter().zip(valid.iter()).map(|(w, x)| w * x).sum::<f64>() / sw;
+            let var = w.iter().zip(valid.iter()).map(|(w, x)| w * (x - mean).powi(2)).sum::<f64>() / sw;
+            assert_close(biased[i], var);
+            assert_close(unbiased[i], var * sw * sw / (sw * sw - sw2));
+        }
+        // NaN input carries the previous value
+        assert_eq!(unbiased[2], unbiased[1]);
+        assert_close(ewm_std(&data, alpha, false).unwrap()[5], unbiased[5].sqrt());
+    }
+
+    #[test]
+    fn rolling_rank_methods_split_ties() {
+        let data = Array1::from_vec(vec![1.0, 2.0, 2.0, 3.0, 2.0]);
+        let rank = |method| rolling_rank_with(&data, 5, 5, None, method).unwrap()[4];
+        // Current 2.0 ties two earlier 2.0s at positions 2..=4 of 5
+        assert_close(rank(RankMethod::Max), 4.0 / 5.0);
+        assert_close(rank(RankMethod::Min), 2.0 / 5.0);
+        assert_close(rank(RankMethod::Average), 3.0 / 5.0);
+        assert_eq!(rolling_rank(&data, 5, None).unwrap()[4], rank(RankMethod::Max));
+
+        let weights = Array1::from_vec(vec![1.0, 1.0, 1.0, 1.0, 2.0]);
+        let weighted = rolling_rank_with(&data, 5, 5, Some(&weights), RankMethod::Min).unwrap();
+        assert_close(weighted[4], 3.0 / 6.0);
+        assert!("dense".parse::<RankMethod>().is_err());
+        assert_eq!(rank(RankMethod::First), rank(RankMethod::Max));
+    }
+
+    #[test]
+    fn cs_rank_methods_share_or_split_tied_percentiles() {
+        let panel = Array2::from_shape_vec((1, 6), vec![1.0, 2.0, 2.0, f64::NAN, 2.0, 3.0]).unwrap();
+        let average = cs_rank_with(&panel, RankMethod::Average);
+        assert_eq!(average.row(0).to_vec()[..3], [0.0, 0.5, 0.5]);
+        assert_eq!(average[[0, 4]], 0.5);
+        assert_eq!(average[[0, 5]], 1.0);
+        assert!(average[[0, 3]].is_nan());
+        assert_eq!(cs_rank(&panel)[[0, 4]], 0.5);
+
+        let first = cs_rank_with(&panel, RankMethod::First);
+        assert_eq!([first[[0, 1]], first[[0, 2]], first[[0, 4]]], [0.25, 0.5, 0.75]);
+        assert_eq!(cs_rank_with(&panel, RankMethod::Min)[[0, 4]], 0.25);
+        assert_eq!(cs_rank_with(&panel, RankMethod::Max)[[0, 1]], 0.75);
+    }
+
+    #[test]
+    fn compute_alphas_stacks_requested_factors() {
+        let mut state = 7u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let high = Array1::from_iter((0..40).map(|_| 100.0 + next()));
+        let volume = Array1::from_iter((0..40).map(|_| 1e6 * (1.0 + next())));
+        let other = Array1::zeros(40);
+
+        let alphas = compute_alphas(&other, &high, &other, &other, &volume, &[42, 42]).unwrap();
+        let single = alpha101_42(&high, &volume).unwrap();
+        assert_eq!(alphas.dim(), (40, 2));
+        for j in 0..2 {
+            assert!(alphas.column(j).iter().zip(single.iter()).all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+        }
+        assert!(single.iter().skip(20).all(|v| v.is_finite()));
+        let err = compute_alphas(&other, &high, &other, &other, &volume, &[42, 1]).unwrap_err();
+        assert!(err.to_string().contains("[42]"));
+        assert!(compute_alphas(&other, &high, &other, &other, &volume.slice(s![1..]).to_owned(), &[42]).is_err());
+
+        let swept = alpha101_42_with(&high, &volume, 5, 8, 12).unwrap();
+        assert_eq!(swept.iter().position(|v| v.is_finite()), Some(11));
+        assert!(matches!(alpha101_42_with(&high, &volume, 10, 41, 10), Err(ExprError::InvalidPeriod(_))));
+    }
+
+    #[test]
+    fn rolling_spearman_sees_through_monotone_transforms() {
+        let x = Array1::from_iter((0..12).map(|i| ((i * 5) % 12) as f64 * 0.5 - 2.0));
+        let y = x.mapv(f64::exp);
+        let spearman = rolling_spearman(&x, &y, 6).unwrap();
+        let pearson = rolling_correlation(&x, &y, 6).unwrap();
+        assert!(spearman.iter().take(5).all(|v| v.is_nan()));
+        for i in 5..12 {
+            assert_close(spearman[i], 1.0);
+            assert!(pearson[i] < 0.99);
+        }
+
+        // Ties share their average rank: ranks [1, 2.5, 2.5, 4] against [1, 2, 3, 4]
+        let tied = Array1::from_vec(vec![1.0, 2.0, 2.0, 3.0]);
+        let ordered = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+        assert_close(rolling_spearman(&tied, &ordered, 4).unwrap()[3], 4.5 / (4.5_f64 * 5.0).sqrt());
+        let flat = Array1::from_elem(4, 1.0);
+        assert!(rolling_spearman(&flat, &ordered, 4).unwrap()[3].is_nan());
+    }
+
+    #[test]
+    fn factors_reject_windows_longer_than_the_series() {
+        let prices = Array1::from_iter((0..50).map(|i| 100.0 + i as f64));
+        let err = momentum(&prices, 200, false, 0).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid period: lookback 200 exceeds series length 50");
+        assert!(mean_reversion(&prices, 51, false, 0.0, 0).is_err());
+        assert!(relative_strength(&prices, 26, false, NanPolicy::Zero).is_err());
+
+        // Windows up to the full length keep their partial, mostly-NaN output
+        assert!(mean_reversion(&prices, 50, false, 0.0, 0).unwrap()[49].is_finite());
+        assert!(momentum(&prices, 50, false, 0).unwrap().iter().all(|v| v.is_nan()));
+        assert!(relative_strength(&prices, 25, false, NanPolicy::Zero).is_ok());
+    }
+
+    #[test]
+    fn center_windows_matches_pandas_alignment() {
+        let data = Array1::from_iter((0..8).map(|i| (i * i) as f64));
+        let odd = center_windows(&rolling_mean(&data, 3).unwrap(), 3);
+        assert!(odd[0].is_nan() && odd[7].is_nan());
+        assert_close(odd[1], (0.0 + 1.0 + 4.0) / 3.0);
+        assert_close(odd[6], (25.0 + 36.0 + 49.0) / 3.0);
+
+        // Even windows lean back: [i - 2, i + 1]
+        let even = center_windows(&rolling_mean(&data, 4).unwrap(), 4);
+        assert!(even[1].is_nan() && even[7].is_nan());
+        assert_close(even[2], (0.0 + 1.0 + 4.0 + 9.0) / 4.0);
+        assert!(even[6].is_finite());
+    }
+
+    #[test]
+    fn group_apply_resets_at_each_boundary() {
+        let values = Array1::from(vec![1.0, 2.0, 3.0, 10.0, 20.0, 30.0, 40.0]);
+        let groups = Array1::from(vec![7, 7, 7, 3, 3, 3, 3]);
+        let result = group_apply(&values, &groups, true, |v| rolling_mean(v, 2)).unwrap();
+        assert!(result[0].is_nan() && result[3].is_nan());
+        assert_close(result[2], 2.5);
+        assert_close(result[4], 15.0);
+        assert_close(result[6], 35.0);
+
+        let short = groups.slice(s![1..]).to_owned();
+        assert!(matches!(group_apply(&values, &short, true, |v| rolling_mean(v, 2)), Err(ExprError::LengthMismatch(_))));
+        let split = Array1::from(vec![7, 7, 3, 3, 7, 7, 7]);
+        assert!(matches!(group_apply(&values, &split, true, |v| rolling_mean(v, 2)), Err(ExprError::InvalidParameter(_))));
+        // Without the flag the split group is gathered back together
+        let gathered = group_apply(&values, &split, false, |v| rolling_mean(v, 2)).unwrap();
+        assert_close(gathered[4], 11.0);
+    }
+
+    #[test]
+    fn rolling_mean_time_uses_a_half_open_span() {
+        let data = Array1::from(vec![1.0, 2.0, f64::NAN, 4.0, 8.0]);
+        let stamps = [0, 10, 15, 30, 30];
+        let result = rolling_mean_time(&data, &stamps, 20).unwrap();
+        assert_close(result[0], 1.0);
+        assert_close(result[1], 1.5);
+        assert!(result[2].is_nan());
+        // (10, 30] drops the bars at 0 and 10 and the NaN at 15
+        assert_close(result[3], 6.0);
+        assert_close(result[4], 6.0);
+
+        assert!(matches!(rolling_mean_time(&data, &[0, 10, 5, 30, 30], 20), Err(ExprError::InvalidParameter(_))));
+        assert!(matches!(rolling_mean_time(&data, &stamps, 0), Err(ExprError::InvalidPeriod(_))));
+        assert!(matches!(rolling_mean_time(&data, &stamps[1..], 20), Err(ExprError::LengthMismatch(_))));
+
+        // Windows reaching below i64::MIN keep the current bar
+        let pair = Array1::from(vec![1.0, 2.0]);
+        let at_min = rolling_mean_time(&pair, &[i64::MIN, i64::MIN], 1).unwrap();
+        assert_close(at_min[0], 1.5);
+        assert_close(at_min[1], 1.5);
+        let extremes = rolling_mean_time(&Array1::from(vec![1.0, 2.0, 4.0]), &[i64::MIN, 0, i64::MAX], i64::MAX).unwrap();
+        assert_eq!(extremes.to_vec(), vec![1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn elementwise_transforms_turn_non_finite_into_nan() {
+        let data = Array1::from(vec![-800.0, -1.0, 0.0, 2.0, f64::NAN, f64::INFINITY]);
+        let clipped = clip(&data, -1.5, 1.5).unwrap();
+        assert_eq!(clipped.slice(s![..4]).to_vec(), vec![-1.5, -1.0, 0.0, 1.5]);
+        assert!(clipped[4].is_nan() && clipped[5].is_nan());
+        assert!(matches!(clip(&data, 1.0, -1.0), Err(ExprError::InvalidParameter(_))));
+
+        let logistic = sigmoid(&data);
+        assert_eq!(logistic[0], 0.0);
+        assert_close(logistic[2], 0.5);
+        assert_close(logistic[1] + 1.0 / (1.0 + 1.0f64.exp()), 2.0 / (1.0 + 1.0f64.exp()));
+        assert!(logistic[5].is_nan());
+
+        let squashed = tanh_squash(&data, 2.0).unwrap();
+        assert_close(squashed[3], 1.0f64.tanh());
+        assert!(squashed[4].is_nan());
+        assert!(tanh_squash(&data, 0.0).is_err());
+
+        let prices = Array1::from(vec![100.0, 110.0, 0.0, 121.0, -5.0, 133.1]);
+        let returns = log_return(&prices, 1).unwrap();
+        assert!(returns[0].is_nan() && returns[2].is_nan() && returns[3].is_nan() && returns[4].is_nan());
+        assert_close(returns[1], 1.1f64.ln());
+        assert!(returns[5].is_nan());
+        assert_close(log_return(&prices, 2).unwrap()[3], 1.1f64.ln());
+    }
+
+    #[test]
+    fn streaming_mean_and_std_match_the_batch_kernels() {
+        let mut state: u64 = 17;
+        let data = Array1::from_iter((0..200).map(|i| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            if i % 37 == 5 { f64::NAN } else { 100.0 + (state >> 11) as f64 / (1u64 << 53) as f64 }
+        }));
+        let (mean, std) = (rolling_mean(&data, 12).unwrap(), rolling_std(&data, 12, 1).unwrap());
+        let (mut stream_mean, mut stream_std) = (RollingMean::with_window(12).unwrap(), RollingStd::with_window(12, 1).unwrap());
+        for (i, &x) in data.iter().enumerate() {
+            let (m, s) = (stream_mean.push(x), stream_std.push(x));
+            assert!(m.to_bits() == mean[i].to_bits() || (m.is_nan() && mean[i].is_nan()));
+            assert!(s.to_bits() == std[i].to_bits() || (s.is_nan() && std[i].is_nan()));
+        }
+
+        stream_mean.reset();
+        stream_std.reset();
+        assert!(stream_mean.push(1.0).is_nan() && stream_std.push(1.0).is_nan());
+    }
+
+    #[test]
+    fn rolling_sum_and_var_share_the_mean_and_std_windows() {
+        let data = Array1::from(vec![1.0, 2.0, f64::NAN, 4.0, 8.0, 8.0, 8.0]);
+        let (sum, mean) = (rolling_sum(&data, 3).unwrap(), rolling_mean(&data, 3).unwrap());
+        let (var, std) = (rolling_var(&data, 3, 1).unwrap(), rolling_std(&data, 3, 1).unwrap());
+        for i in 0..data.len() {
+            assert_eq!(sum[i].is_nan(), mean[i].is_nan());
+            if mean[i].is_finite() {
+                assert_close(sum[i], 3.0 * mean[i]);
+                assert_close(var[i], std[i] * std[i]);
+            }
+        }
+        assert_close(sum[3], 7.0);
+        assert_close(var[4], 28.0 / 3.0);
+        assert_eq!(var[6], 0.0);
+    }
+
+    #[test]
+    fn alpha101_ts_rank_averages_ties_over_the_full_window() {
+        let data = Array1::from(vec![3.0, 1.0, 3.0, 2.0, 3.0, f64::NAN, 5.0, 6.0, 7.0]);
+        let ranks = alpha101_ts_rank(&data, 3).unwrap();
+        assert!(ranks[0].is_nan() && ranks[1].is_nan());
+        // [3, 1, 3]: the two 3s share ranks 2 and 3
+        assert_close(ranks[2], 2.5 / 3.0);
+        assert_close(ranks[3], 2.0 / 3.0);
+        assert_close(ranks[4], 2.5 / 3.0);
+        assert!(ranks.slice(s![5..8]).iter().all(|v| v.is_nan()));
+        assert_close(ranks[8], 1.0);
+        // rolling_rank ranks ties high
+        assert_close(rolling_rank(&data, 3, None).unwrap()[2], 1.0);
+    }
+
+    #[test]
+    fn mean_reversion_is_the_negated_rolling_zscore() {
+        let prices = Array1::from_iter((0..40).map(|i| 100.0 + ((i * 7) % 11) as f64 - 0.3 * i as f64));
+        let z = rolling_zscore(&prices, 8, 1, 0.0).unwrap();
+        let rev = mean_reversion(&prices, 8, false, 0.0, 1).unwrap();
+        for (a, b) in z.iter().zip(rev.iter()) {
+            assert!((a.is_nan() && b.is_nan()) || -a == *b);
+        }
+        assert!(z[6].is_nan() && z[7].is_finite());
+    }
+
+    #[test]
+    fn rolling_geomean_reconstructs_compounded_growth() {
+        let returns = [0.05, -0.02, 0.10, 0.0, -0.07, 0.03, 0.04];
+        let growth = Array1::from_iter(returns.iter().map(|r| 1.0 + r));
+        let geo = rolling_geomean(&growth, 4).unwrap();
+        assert!(geo[2].is_nan());
+        for i in 3..growth.len() {
+            let compounded: f64 = growth.slice(s![i - 3..=i]).product();
+            assert_close(geo[i].powi(4), compounded);
+        }
+
+        let data = Array1::from(vec![2.0, 0.0, 3.0, f64::NAN, 4.0, 5.0, -1.0]);
+        let prod = rolling_prod(&data, 2).unwrap();
+        assert_eq!(prod[1], 0.0);
+        assert_eq!(prod[2], 0.0);
+        assert!(prod[3].is_nan());
+        assert_eq!(prod[4], 12.0);
+        assert_eq!(prod[5], 20.0);
+        assert!(rolling_geomean(&data, 2).unwrap()[6].is_nan());
+
+        let tiny = Array1::from(vec![1e-200, 1e-200, 1e-200, 3.0, 5.0]);
+        assert_close(rolling_prod(&tiny, 2).unwrap()[4], 15.0);
+    }
+
+    #[test]
+    fn halflife_halves_an_impulse_after_halflife_steps() {
+        let alpha = halflife_to_alpha(5.0).unwrap();
+        let mut impulse = Array1::zeros(21);
+        impulse[0] = 1.0;
+        let decay = ema(&impulse, alpha, false).unwrap();
+        assert_close(decay[5], 0.5);
+        assert_close(decay[10], 0.25);
+        assert_close(decay[20], 0.0625);
+        assert_close(halflife_to_alpha(1.0).unwrap(), 0.5);
+        assert!(halflife_to_alpha(0.0).is_err());
+    }
+
+    #[test]
+    fn rolling_minmax_matches_the_separate_extremes() {
+        let data = Array1::from(vec![3.0, 1.0, 4.0, f64::NAN, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0]);
+        let (min, max) = rolling_minmax(&data, 3).unwrap();
+        let (lone_min, lone_max) = (rolling_min(&data, 3).unwrap(), rolling_max(&data, 3).unwrap());
+        for i in 0..data.len() {
+            assert!(min[i] == lone_min[i] || (min[i].is_nan() && lone_min[i].is_nan()));
+            assert!(max[i] == lone_max[i] || (max[i].is_nan() && lone_max[i].is_nan()));
+        }
+
+        let range = rolling_range(&data, 3).unwrap();
+        assert_eq!(range[4], 3.0);
+        let stoch = stochastic(&data, 3).unwrap();
+        assert_eq!(stoch[6], 1.0);
+        assert_eq!(stoch[7], 0.0);
+        assert_close(stoch[8], 4.0 / 7.0);
+        assert!(stoch[3].is_nan());
+        assert!(stochastic(&Array1::from_elem(5, 2.0), 3).unwrap().iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn relative_strength_clamps_short_timeframes() {
+        let prices = Array1::from_iter((0..30).map(|i| 100.0 + (i as f64 * 0.7).sin()));
+        let (factor, ranks) = relative_strength(&prices, 2, false, NanPolicy::Zero).unwrap();
+        assert!(factor.iter().skip(4).all(|v| v.is_finite()));
+        // The first timeframe is 1 bar: ranked 1-bar returns
+        let one_bar = rolling_rank(&pct_change(&prices, 1, ZeroPrev::Nan).unwrap(), 2, None).unwrap();
+        assert_eq!(ranks.column(0).to_vec()[2..], one_bar.to_vec()[2..]);
+        assert!(matches!(relative_strength(&prices, 1, false, NanPolicy::Zero), Err(ExprError::InvalidPeriod(_))));
+    }
+
+    #[test]
+    fn relative_strength_with_normalizes_custom_weights() {
+        let prices = Array1::from_iter((0..60).map(|i| 100.0 + (i as f64 * 0.3).sin() + 0.05 * i as f64));
+        let (default, _) = relative_strength(&prices, 9, false, NanPolicy::Zero).unwrap();
+        let (same, _) = relative_strength_with(&prices, 9, &[3, 9, 18], &[5.0, 3.0, 2.0], false, NanPolicy::Zero).unwrap();
+        for (a, b) in default.iter().zip(same.iter()) {
+            assert_close(*a, *b);
+        }
+
+        let (single, ranks) = relative_strength_with(&prices, 9, &[5], &[2.0], false, NanPolicy::Zero).unwrap();
+        assert_eq!(ranks.ncols(), 1);
+        assert_close(single[30], ranks[[30, 0]]);
+
+        assert!(matches!(relative_strength_with(&prices, 9, &[3, 9], &[1.0], false, NanPolicy::Zero), Err(ExprError::LengthMismatch(_))));
+        assert!(matches!(relative_strength_with(&prices, 9, &[3], &[-1.0], false, NanPolicy::Zero), Err(ExprError::InvalidParameter(_))));
+        assert!(matches!(relative_strength_with(&prices, 9, &[61], &[1.0], false, NanPolicy::Zero), Err(ExprError::InvalidPeriod(_))));
+    }
+
+    #[test]
+    fn sign_change_compares_against_the_last_nonzero_sign() {
+        let data = Array1::from(vec![-2.0, 0.0, 3.0, f64::NAN, 1.0, -0.0, 4.0, -1.0, f64::NEG_INFINITY]);
+        let signs = sign(&data);
+        assert_eq!(signs.slice(s![..3]).to_vec(), vec![-1.0, 0.0, 1.0]);
+        assert!(signs[3].is_nan());
+        assert_eq!(signs[5].to_bits(), 0.0f64.to_bits());
+        assert_eq!(signs[8], -1.0);
+
+        let flips = sign_change(&data);
+        assert_eq!(flips.slice(s![..3]).to_vec(), vec![0.0, 0.0, 1.0]);
+        assert!(flips[3].is_nan());
+        assert_eq!(flips.slice(s![4..]).to_vec(), vec![0.0, 0.0, 0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn rolling_autocorr_decays_with_lag_for_ar1() {
+        let mut state: u64 = 99;
+        let mut x = 0.0;
+        let data = Array1::from_iter((0..4000).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let noise = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            x = 0.7 * x + noise;
+            x
+        }));
+        let window = 3000;
+        let rho: Vec<f64> = (1..=3).map(|lag| rolling_autocorr(&data, window, lag).unwrap()[3999]).collect();
+        for (lag, r) in rho.iter().enumerate() {
+            assert!((r - 0.7f64.powi(lag as i32 + 1)).abs() < 0.05, "lag {}: {}", lag + 1, r);
+        }
+        assert!(rho[0] > rho[1] && rho[1] > rho[2]);
+
+        let short = rolling_autocorr(&data, 10, 2).unwrap();
+        assert!(short[10].is_nan() && short[11].is_finite());
+        assert!(rolling_autocorr(&data, 10, 10).is_err());
+        assert!(rolling_autocorr(&data, 10, 0).is_err());
+    }
+
+    #[test]
+    fn rolling_slope_recovers_a_noisy_trend() {
+        let line = Array1::from_iter((0..50).map(|i| 1e6 + 0.25 * i as f64));
+        let slope = rolling_slope(&line, 10).unwrap();
+        assert!(slope[8].is_nan());
+        assert!((slope[49] - 0.25).abs() < 1e-9);
+        assert!((rolling_r2(&line, 10).unwrap()[49] - 1.0).abs() < 1e-9);
+
+        // A gap keeps the true spacing: the three points lie on y = 2t
+        let gappy = Array1::from(vec![0.0, 2.0, f64::NAN, 6.0]);
+        assert_close(rolling_slope(&gappy, 3).unwrap()[3], 2.0);
+
+        let zigzag = Array1::from(vec![0.0, 1.0, 0.0, 1.0, 0.0]);
+        let r2 = rolling_r2(&zigzag, 4).unwrap();
+        assert_close(r2[3], 0.2);
+        assert_close(rolling_slope(&zigzag, 4).unwrap()[3], 0.2);
+
+        let flat = Array1::from_elem(5, 3.0);
+        assert_eq!(rolling_slope(&flat, 3).unwrap()[4], 0.0);
+        assert!(rolling_r2(&flat, 3).unwrap()[4].is_nan());
+
+        // A flat stretch after a volatile one leaves no downdate residue
+        let mut settled: Vec<f64> = (0..50).map(|i| 45_000.0 + ((i * 37) % 11) as f64 * 0.137).collect();
+        settled.extend([45_000.01; 10]);
+        let settled = Array1::from_vec(settled);
+        let (slope, r2) = (rolling_slope(&settled, 5).unwrap(), rolling_r2(&settled, 5).unwrap());
+        for i in 54..60 {
+            assert_eq!(slope[i], 0.0);
+            assert!(r2[i].is_nan());
+        }
+    }
+
+    #[test]
+    fn rank_ic_is_the_per_row_spearman_correlation() {
+        let factor = Array2::from_shape_vec((3, 4), vec![
+            1.0, 2.0, 3.0, 4.0,
+            1.0, 2.0, 3.0, 4.0,
+            1.0, f64::NAN, f64::NAN, f64::NAN,
+        ]).unwrap();
+        let returns = Array2::from_shape_vec((3, 4), vec![
+            0.1, 0.2, 0.3, 0.9,
+            0.4, 0.3, 0.2, f64::NAN,
+            0.1, 0.2, 0.3, 0.4,
+        ]).unwrap();
+        let ic = rank_ic(&factor, &returns).unwrap();
+        assert_close(ic[0], 1.0);
+        assert_close(ic[1], -1.0);
+        assert!(ic[2].is_nan());
+        assert!(matches!(rank_ic(&factor, &returns.slice(s![..2, ..]).to_owned()), Err(ExprError::LengthMismatch(_))));
+    }
+
+    #[test]
+    fn fills_stop_after_limit_bars_of_a_gap() {
+        let nan = f64::NAN;
+        let data = Array1::from_vec(vec![nan, 1.0, nan, nan, nan, 5.0, nan, 7.0, nan]);
+        let same = |a: &Array1<f64>, b: &[f64]| {
+            a.iter().zip(b).all(|(x, y)| (x.is_nan() && y.is_nan()) || x == y)
+        };
+        assert!(same(&forward_fill(&data, Some(2)), &[nan, 1.0, 1.0, 1.0, nan, 5.0, 5.0, 7.0, 7.0]));
+        assert!(same(&forward_fill(&data, None), &[nan, 1.0, 1.0, 1.0, 1.0, 5.0, 5.0, 7.0, 7.0]));
+        assert!(same(&backward_fill(&data, Some(1)), &[1.0, 1.0, nan, nan, 5.0, 5.0, 7.0, 7.0, nan]));
+        assert!(same(&forward_fill(&data, Some(0)), data.as_slice().unwrap()));
+    }
+
+    #[test]
+    fn rolling_entropy_bins_each_window_on_its_own_range() {
+        let data = Array1::from_vec(vec![0.0, 1.0, 2.0, 3.0, 3.0, 3.0, 3.0, f64::NAN, 10.0]);
+        let h = rolling_entropy(&data, 4, 2).unwrap();
+        assert!(h.slice(s![..3]).iter().all(|v| v.is_nan()));
+        // [0, 1, 2, 3] splits 2 / 2 across the halves of [0, 3]
+        assert_close(h[3], 1.0);
+        // [1, 2, 3, 3]: only 1 falls below the midpoint 2
+        assert_close(h[4], -(0.25f64 * 0.25f64.log2() + 0.75 * 0.75f64.log2()));
+        assert!(h[6].is_nan());
+        assert!(h[7].is_nan());
+        // [3, 3, 3, 10] skips the NaN bar
+        assert_close(h[8], h[4]);
+        assert!(matches!(rolling_entropy(&data, 4, 0), Err(ExprError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn cs_normalize_ranks_demeans_and_scales_each_row() {
+        let nan = f64::NAN;
+        let data = Array2::from_shape_vec((3, 4), vec![
+            3.0, 1.0, nan, 2.0,
+            nan, nan, nan, nan,
+            5.0, 5.0, 5.0, 5.0,
+        ]).unwrap();
+
+        let demeaned = cs_demean(&data);
+        assert_close(demeaned[[0, 0]], 1.0);
+        assert_close(demeaned[[0, 1]], -1.0);
+        assert!(demeaned[[0, 2]].is_nan());
+        assert!(demeaned.row(1).iter().all(|v| v.is_nan()));
+
+        let weights = cs_normalize(&data, true, true, true).unwrap();
+        // ranks 1, 0, 0.5 demean to 0.5, -0.5, 0 and scale to gross 1
+        assert_close(weights[[0, 0]], 0.5);
+        assert_close(weights[[0, 1]], -0.5);
+        assert_close(weights[[0, 3]], 0.0);
+        assert!(weights[[0, 2]].is_nan());
+        assert!(weights.row(1).iter().all(|v| v.is_nan()));
+        // A flat row demeans to zero, which cannot be scaled
+        assert!(weights.row(2).iter().all(|v| v.is_nan()));
+
+        let unchanged = cs_normalize(&data, false, false, false).unwrap();
+        assert_close(unchanged[[2, 0]], 5.0);
+    }
+
+    #[test]
+    fn apply_column_pairs_matches_the_per_column_alpha42() {
+        let mut state = 17u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let high = Array2::from_shape_fn((40, 3), |_| 100.0 + next());
+        let volume = Array2::from_shape_fn((40, 3), |_| 1e6 * next());
+        let panel = apply_column_pairs_par(&high, &volume, alpha101_42).unwrap();
+        for j in 0..3 {
+            let column = alpha101_42(&high.column(j).to_owned(), &volume.column(j).to_owned()).unwrap();
+            assert!(panel.column(j).iter().zip(column.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+        }
+        let short = volume.slice(s![.., ..2]).to_owned();
+        assert!(matches!(apply_column_pairs_par(&high, &short, alpha101_42), Err(ExprError::LengthMismatch(_))));
+    }
+
+    #[test]
+    fn panel_beta_drops_gaps_per_asset() {
+        let market = Array1::from_vec(vec![0.01, -0.02, 0.015, 0.03, -0.01, 0.005, 0.02]);
+        let mut returns = Array2::zeros((7, 2));
+        returns.column_mut(0).assign(&(&market * 2.0));
+        returns.column_mut(1).assign(&(&market * -0.5 + 0.001));
+        returns[[2, 1]] = f64::NAN;
+        let betas = panel_beta(&returns, &market, 3, true).unwrap();
+        assert!(betas.slice(s![..2, ..]).iter().all(|v| v.is_nan()));
+        assert!(betas.column(0).slice(s![2..]).iter().all(|&b| (b - 2.0).abs() < 1e-12));
+        // Bar 3 completes asset 1's window from bars 0, 1 and 3
+        assert!(betas[[2, 1]].is_nan());
+        assert!(betas.column(1).slice(s![3..]).iter().all(|&b| (b + 0.5).abs() < 1e-12));
+        assert!(matches!(panel_beta(&returns, &market.slice(s![..5]).to_owned(), 3, true), Err(ExprError::LengthMismatch(_))));
+    }
+
+    #[test]
+    fn fill_non_finite_only_replaces_missing_outputs() {
+        let data = Array1::from_vec(vec![f64::NAN, 1.5, f64::INFINITY, -2.0]);
+        assert_eq!(fill_non_finite(data.clone(), Some(0.0)).to_vec(), vec![0.0, 1.5, 0.0, -2.0]);
+        let kept = fill_non_finite(data, None);
+        assert!(kept[0].is_nan() && kept[2].is_infinite());
+        let panel = fill_non_finite(Array2::from_elem((2, 2), f64::NAN), Some(-1.0));
+        assert!(panel.iter().all(|&v| v == -1.0));
+    }
+
+    #[test]
+    fn rolling_vwap_weights_by_volume_and_skips_gaps() {
+        let price = Array1::from_vec(vec![10.0, 11.0, f64::NAN, 12.0, 13.0, 14.0, 15.0]);
+        let volume = Array1::from_vec(vec![1.0, 3.0, 5.0, 0.0, 1e12, 0.0, 0.0]);
+        let vwap = rolling_vwap(&price, &volume, 2).unwrap();
+        assert!(vwap[0].is_nan() && vwap[2].is_nan());
+        assert_close(vwap[1], (10.0 + 33.0) / 4.0);
+        // The NaN price is skipped, so bar 3 pairs with bar 1
+        assert_close(vwap[3], 11.0);
+        assert_close(vwap[4], 13.0);
+        assert_close(vwap[5], 13.0);
+        // Only zero-volume bars left in the window
+        assert!(vwap[6].is_nan());
+    }
+
+    #[test]
+    fn compare_masks_feed_where() {
+        let momentum = Array1::from_vec(vec![0.5, -0.2, 0.0, f64::NAN]);
+        let zero = Array1::zeros(4);
+        let mask = compare(&momentum, &zero, Comparison::Gt).unwrap();
+        assert_eq!(mask.to_vec(), vec![true, false, false, false]);
+        assert_eq!(compare(&momentum, &zero, Comparison::Ge).unwrap().to_vec(), vec![true, false, true, false]);
+        assert_eq!(compare(&momentum, &zero, Comparison::Le).unwrap().to_vec(), vec![false, true, true, false]);
+
+        let a = Array1::from_vec(vec![1.0, 2.0, 3.0, 4.0]);
+        let b = Array1::from_vec(vec![-1.0, f64::NAN, -3.0, -4.0]);
+        let picked = where_(&mask, &a, &b).unwrap();
+        assert_eq!(picked[0], 1.0);
+        assert!(picked[1].is_nan());
+        assert_eq!(picked.slice(s![2..]).to_vec(), vec![-3.0, -4.0]);
+        assert!(matches!(where_(&mask.slice(s![..3]).to_owned(), &a, &b), Err(ExprError::LengthMismatch(_))));
+    }
+
+    #[test]
+    fn rolling_mad_ignores_a_single_outlier() {
+        let data = Array1::from_vec(vec![1.0, 2.0, f64::NAN, 3.0, 4.0, 1000.0]);
+        let mad = rolling_mad(&data, 4, false).unwrap();
+        assert!(mad.slice(s![..4]).iter().all(|v| v.is_nan()));
+        // [1, 2, 3, 4]: deviations 1.5, 0.5, 0.5, 1.5
+        assert_close(mad[4], 1.0);
+        // [2, 3, 4, 1000]: median 3.5, deviations 1.5, 0.5, 0.5, 996.5
+        assert_close(mad[5], 1.0);
+        assert_close(rolling_mad(&data, 4, true).unwrap()[5], 1.4826);
+    }
+
+    #[test]
+    fn rolling_mean_with_count_reports_valid_observations() {
+        let data = Array1::from_vec(vec![1.0, f64::NAN, 3.0, 5.0, f64::NAN, 7.0]);
+        let (mean, counts) = rolling_mean_with_count(&data, 3, 2).unwrap();
+        assert_eq!(counts.to_vec(), vec![1, 1, 2, 3, 3, 3]);
+        assert!(mean[0].is_nan() && mean[1].is_nan() && mean[4].is_nan());
+        assert_close(mean[2], 2.0);
+        assert_close(mean[5], 5.0);
+        let (partial, _) = rolling_mean_partial(&data, 3, 2).unwrap();
+        assert!(mean.iter().zip(partial.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+    }
+
+    #[test]
+    fn bollinger_factors_follow_the_band_math() {
+        let data = Array1::from_vec(vec![1.0, 3.0, f64::NAN, 5.0, 5.0, 5.0]);
+        let percent_b = bollinger_percent_b(&data, 2, 2.0).unwrap();
+        let bandwidth = bollinger_bandwidth(&data, 2, 2.0).unwrap();
+        assert!(percent_b[0].is_nan() && percent_b[2].is_nan() && bandwidth[2].is_nan());
+        // [1, 3]: mean 2, std 1, bands 0 and 4
+        assert_close(percent_b[1], 0.75);
+        assert_close(bandwidth[1], 2.0);
+        // [3, 5] skips the NaN
+        assert_close(percent_b[3], 0.75);
+        assert_close(bandwidth[3], 1.0);
+        // A flat window has no bands
+        assert!(percent_b[5].is_nan());
+        assert_close(bandwidth[5], 0.0);
+        assert!(bollinger_bandwidth(&Array1::from_vec(vec![-1.0, 1.0]), 2, 2.0).unwrap()[1].is_nan());
+        assert!(matches!(bollinger_percent_b(&data, 2, 0.0), Err(ExprError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn shuffle_rows_is_seeded_and_stays_within_rows() {
+        let data = Array2::from_shape_fn((6, 8), |(i, j)| if j == 3 { f64::NAN } else { (i * 8 + j) as f64 });
+        let shuffled = shuffle_rows(&data, 42);
+        let same = |a: &Array2<f64>, b: &Array2<f64>| a.iter().zip(b.iter()).all(|(x, y)| x.to_bits() == y.to_bits());
+        assert!(same(&shuffled, &shuffle_rows(&data, 42)));
+        assert!(!same(&shuffled, &data));
+        for (original, permuted) in data.rows().into_iter().zip(shuffled.rows()) {
+            let sort = |row: ArrayView1<f64>| {
+                let mut v = row.to_vec();
+                v.sort_by(|a, b| a.total_cmp(b));
+                v.into_iter().map(f64::to_bits).collect::<Vec<_>>()
+            };
+            assert_eq!(sort(original), sort(permuted));
+        }
+    }
+
+    #[test]
+    fn dema_and_tema_lag_a_ramp_less_than_ema() {
+        let mut ramp = Array1::from_iter((0..400).map(|i| i as f64));
+        ramp[50] = f64::NAN;
+        let alpha = 2.0 / 21.0;
+        let lag = |smoothed: &Array1<f64>| ramp[399] - smoothed[399];
+        let (plain, double, triple) = (ema(&ramp, alpha, false).unwrap(), dema(&ramp, alpha, false).unwrap(), tema(&ramp, alpha, false).unwrap());
+        // A steady-state EMA trails a unit ramp by (1 - alpha) / alpha bars
+        assert!((lag(&plain) - (1.0 - alpha) / alpha).abs() < 1e-6);
+        assert!(lag(&double).abs() < 1e-6);
+        assert!(lag(&triple).abs() < 1e-6);
+        assert_eq!(double[50], double[49]);
+        assert!(dema(&ramp, 1.5, false).is_err());
+    }
+
+    #[test]
+    fn cs_bucket_splits_rows_into_equal_rank_groups() {
+        let nan = f64::NAN;
+        let data = Array2::from_shape_vec((3, 6), vec![
+            6.0, 1.0, 5.0, 2.0, 4.0, 3.0,
+            1.0, nan, 2.0, 2.0, 3.0, f64::INFINITY,
+            nan, nan, nan, nan, nan, nan,
+        ]).unwrap();
+        let buckets = cs_bucket(&data, 3).unwrap();
+        assert_eq!(buckets.row(0).to_vec(), vec![2, 0, 2, 0, 1, 1]);
+        // The tied 2s share the bucket of their mean position 1.5
+        assert_eq!(buckets.row(1).to_vec(), vec![0, -1, 1, 1, 2, -1]);
+        assert!(buckets.row(2).iter().all(|&b| b == -1));
+        assert!(matches!(cs_bucket(&data, 0), Err(ExprError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn two_array_kernels_reject_mismatched_lengths() {
+        let x = Array1::from_iter((0..100).map(|i| i as f64));
+        let y = Array1::from_iter((0..98).map(|i| (i * i) as f64));
+        let message = |result: Result<Array1<f64>>| match result {
+            Err(ExprError::LengthMismatch(msg)) => msg,
+            other => panic!("expected LengthMismatch, got {:?}", other.map(|v| v.len())),
+        };
+        assert_eq!(message(rolling_correlation(&x, &y, 10)), "x has length 100 but y has length 98");
+        assert_eq!(message(rolling_correlation(&y, &x, 10)), "x has length 98 but y has length 100");
+        assert_eq!(message(alpha101_42(&x, &y)), "high has length 100 but volume has length 98");
+        assert_eq!(message(rolling_residualize(&x, &y, 10)), "asset_ret has length 100 but market_ret has length 98");
+        assert!(matches!(rolling_cov(&x, &y, 10, 1, PrecisionMode::Fast), Err(ExprError::LengthMismatch(_))));
+        assert!(matches!(rolling_beta(&x, &y, 10, true), Err(ExprError::LengthMismatch(_))));
+    }
+
+    #[test]
+    fn expanding_stats_cover_the_whole_history() {
+        let data = Array1::from_vec(vec![3.0, 1.0, f64::NAN, 2.0, 3.0, 0.0]);
+        let rank = expanding_rank(&data);
+        assert_close(rank[0], 1.0);
+        assert_close(rank[1], 0.5);
+        assert!(rank[2].is_nan());
+        assert_close(rank[3], 2.0 / 3.0);
+        // Ties rank high: both 3s count
+        assert_close(rank[4], 1.0);
+        assert_close(rank[5], 0.2);
+        let full_window = rolling_rank_partial(&data, data.len(), 1, None).unwrap();
+        for i in [1, 3, 4, 5] {
+            assert_close(rank[i], full_window[i]);
+        }
+
+        let mean = expanding_mean(&data);
+        assert_close(mean[3], 2.0);
+        assert!(mean[2].is_nan());
+        let std = expanding_std(&data, 1);
+        assert!(std[0].is_nan() && std[2].is_nan());
+        assert_close(std[1], 2.0f64.sqrt());
+        assert_close(std[5], 1.3038404810405297);
+    }
+
+    #[test]
+    fn factor_chain_matches_the_function_calls() {
+        let data = Array1::from_iter((0..60).map(|i| ((i * 37) % 23) as f64 + if i == 20 { f64::NAN } else { 0.0 }));
+        let chained = Factor::new(data.clone())
+            .rolling_mean(5).unwrap()
+            .zscore(10, 1, 0.0).unwrap()
+            .clip(-2.0, 2.0).unwrap()
+            .into_values();
+        let direct = clip(&rolling_zscore(&rolling_mean(&data, 5).unwrap(), 10, 1, 0.0).unwrap(), -2.0, 2.0).unwrap();
+        assert!(chained.iter().zip(direct.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+        assert!(Factor::new(data).rank(1).is_err());
+    }
+
+    #[test]
+    fn hampel_filter_replaces_spikes_with_the_centred_median() {
+        let mut data = Array1::from_iter((0..20).map(|i| 10.0 + (i % 3) as f64 * 0.1));
+        data[8] = 50.0;
+        data[15] = f64::NAN;
+        let (cleaned, replaced) = hampel_filter(&data, 5, 3.0).unwrap();
+        assert!(replaced[8] && !replaced[7] && !replaced[9]);
+        // The window centred on 8 is bars 6..=10: 10.0, 10.1, 50.0, 10.0, 10.1
+        assert_close(cleaned[8], 10.1);
+        assert!(cleaned[15].is_nan() && !replaced[15]);
+        assert_eq!(replaced.iter().filter(|&&r| r).count(), 1);
+
+        let mut flat = Array1::from_elem(9, 1.0);
+        flat[4] = 1.5;
+        let (flat_cleaned, _) = hampel_filter(&flat, 5, 3.0).unwrap();
+        assert!(flat_cleaned.iter().all(|&v| v == 1.0));
+        assert!(hampel_filter(&data, 5, -1.0).is_err());
+
+        // NaNs thin the window around 9 (bars 7..=11) but do not move it
+        let mut gappy = Array1::from_iter((0..20).map(|i| if i < 10 { 1.0 } else { 5.0 }));
+        gappy[7] = f64::NAN;
+        gappy[8] = f64::NAN;
+        let (gappy_cleaned, gappy_replaced) = hampel_filter(&gappy, 5, 3.0).unwrap();
+        // Bar 9 sees 1.0, 5.0, 5.0: median 5.0, MAD 0.0
+        assert!(gappy_replaced[9]);
+        assert_close(gappy_cleaned[9], 5.0);
+        // Bar 10 sees 1.0, 5.0, 5.0, 5.0 and is on the median
+        assert!(!gappy_replaced[10]);
+        assert!(gappy_cleaned[7].is_nan() && !gappy_replaced[7]);
+    }
+
+    #[test]
+    fn rolling_count_measures_data_density() {
+        let nan = f64::NAN;
+        let data = Array1::from_vec(vec![1.0, nan, 2.0, f64::INFINITY, nan, 3.0, 4.0]);
+        let count = rolling_count(&data, 4).unwrap();
+        assert!(count.slice(s![..3]).iter().all(|v| v.is_nan()));
+        assert_eq!(count.slice(s![3..]).to_vec(), vec![2.0, 1.0, 2.0, 2.0]);
+        let ratio = rolling_nan_ratio(&data, 4).unwrap();
+        assert_eq!(ratio.slice(s![3..]).to_vec(), vec![0.5, 0.75, 0.5, 0.5]);
+        assert!(rolling_count(&data, 0).is_err());
+    }
+
+    #[test]
+    fn weighted_rolling_mean_renormalizes_over_valid_bars() {
+        let data = Array1::from_vec(vec![1.0, 2.0, f64::NAN, 4.0, 5.0]);
+        let weights = Array1::from_vec(vec![1.0, 0.0, 3.0]);
+        let mean = weighted_rolling_mean(&data, &weights).unwrap();
+        assert!(mean[0].is_nan() && mean[1].is_nan());
+        // [1, 2, NaN]: only the oldest bar has weight and a value
+        assert_close(mean[2], 1.0);
+        // [2, NaN, 4] -> (1 * 2 + 3 * 4) / 4
+        assert_close(mean[3], 3.5);
+        // [NaN, 4, 5]: 4 has zero weight
+        assert_close(mean[4], 5.0);
+
+        let flat = weighted_rolling_mean(&data, &Array1::from_elem(2, 0.5)).unwrap();
+        let rolling = rolling_mean(&Array1::from_vec(vec![4.0, 5.0]), 2).unwrap();
+        assert_close(flat[4], rolling[1]);
+        let zero_at_valid = weighted_rolling_mean(&Array1::from_vec(vec![f64::NAN, 1.0]), &Array1::from_vec(vec![1.0, 0.0])).unwrap();
+        assert!(zero_at_valid[1].is_nan());
+
+        assert!(matches!(weighted_rolling_mean(&data, &Array1::zeros(0)), Err(ExprError::InvalidPeriod(_))));
+        for bad in [vec![1.0, f64::NAN], vec![1.0, -1.0], vec![0.0, 0.0]] {
+            assert!(matches!(weighted_rolling_mean(&data, &Array1::from_vec(bad)), Err(ExprError::InvalidParameter(_))));
+        }
+    }
+
+    #[test]
+    fn pct_change_treats_negative_zero_and_subnormal_prev_as_zero() {
+        let tiny = f64::MIN_POSITIVE / 4.0;
+        assert!(tiny > 0.0 && !tiny.is_normal());
+
+        let data = Array1::from_vec(vec![-0.0, 1.0, tiny, 1.0, 1e-300, 2e-300]);
+        let result = pct_change(&data, 1, ZeroPrev::Nan).unwrap();
+        assert!(result[1].is_nan());
+        assert_close(result[2], -1.0);
+        assert!(result[3].is_nan());
+        // Normal but below the absolute tolerance: also a zero denominator
+        assert!(result[5].is_nan());
+
+        let zero_curr = pct_change(&Array1::from_vec(vec![2.0, -0.0]), 1, ZeroPrev::Nan).unwrap();
+        assert_close(zero_curr[1], -1.0);
+    }
+
+    #[test]
+    fn pct_change_zero_prev_modes() {
+        let volume = Array1::from_vec(vec![0.0, 5.0, 0.0, 0.0, -0.0, -2.0, f64::NAN, 0.0]);
+
+        let nan = pct_change(&volume, 1, ZeroPrev::Nan).unwrap();
+        assert!(nan[1].is_nan() && nan[3].is_nan());
+        assert_eq!(nan[2], -1.0);
+
+        let inf = pct_change(&volume, 1, ZeroPrev::Inf).unwrap();
+        assert_eq!(inf[1], f64::INFINITY);
+        assert_eq!(inf[3], 0.0);
+        assert_eq!(inf[5], f64::NEG_INFINITY);
+
+        let zero = pct_change(&volume, 1, ZeroPrev::Zero).unwrap();
+        assert_eq!(zero[1], 0.0);
+        assert_eq!(zero[5], 0.0);
+
+        // Non-finite neighbours stay NaN whatever the mode
+        for result in [&nan, &inf, &zero] {
+            assert!(result[0].is_nan() && result[6].is_nan() && result[7].is_nan());
+        }
+        assert!("large".parse::<ZeroPrev>().is_err());
+    }
+
+    #[test]
+    fn rolling_std_and_correlation_treat_negative_zero_as_zero() {
+        let pos = Array1::from_vec(vec![1.0, 0.0, 2.0, 0.0, 3.0]);
+        let neg = Array1::from_vec(vec![1.0, -0.0, 2.0, -0.0, 3.0]);
+        let other = Array1::from_vec(vec![2.0, 1.0, 5.0, 0.5, 4.0]);
+
+        let std_pos = rolling_std(&pos, 3, 0).unwrap();
+        let std_neg = rolling_std(&neg, 3, 0).unwrap();
+        let corr_pos = rolling_correlation(&pos, &other, 3).unwrap();
+        let corr_neg = rolling_correlation(&neg, &other, 3).unwrap();
+        for i in 0..pos.len() {
+            assert_eq!(std_pos[i].to_bits(), std_neg[i].to_bits());
+            assert_eq!(corr_pos[i].to_bits(), corr_neg[i].to_bits());
+        }
+    }
+
+    #[test]
+    fn underflowing_windows_are_degenerate() {
+        let tiny = Array1::from_vec(vec![1e-310, 2e-310, 3e-310, 4e-310]);
+        assert!(rolling_std(&tiny, 3, 0).unwrap().iter().skip(2).all(|&v| v == 0.0));
+
+        let x = Array1::from_vec(vec![1e-200, 2e-200, 3e-200, 4e-200]);
+        let y = Array1::from_vec(vec![1.0, 3.0, 2.0, 4.0]);
+        assert!(rolling_correlation(&x, &y, 3).unwrap().iter().all(|v| v.is_nan()));
+
+        // Small values whose squares stay normal are still computed
+        let small = Array1::from_vec(vec![1e-100, 2e-100, 3e-100]);
+        assert!(rolling_std(&small, 3, 0).unwrap()[2].is_finite());
+    }
+
+    #[test]
+    fn pair_zscore_ols_removes_hedge_ratio() {
+        let x = Array1::from_vec((0..12).map(|i| 10.0 + i as f64 + (i % 3) as f64).collect());
+        let noise = [0.1, -0.2, 0.3, 0.0, -0.1, 0.2, -0.3, 0.1, 0.0, 0.2, -0.1, 0.1];
+        let y = Array1::from_vec((0..12).map(|i| 2.0 * x[i] + noise[i]).collect());
+        let diff = pair_zscore(&y, &x, 4, SpreadMode::Diff).unwrap();
+        let ols = pair_zscore(&y, &x, 4, SpreadMode::Ols).unwrap();
+
+        assert!(diff.slice(s![..3]).iter().all(|v| v.is_nan()));
+        assert!(diff[3].is_finite());
+        // The OLS spread needs a full beta window before its own z-score window
+        assert!(ols.slice(s![..6]).iter().all(|v| v.is_nan()));
+        assert!(ols.slice(s![6..]).iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn pair_zscore_rejects_mismatched_lengths() {
+        let y = Array1::from_vec(vec![1.0, 2.0, 3.0]);
+        let x = Array1::from_vec(vec![1.0, 2.0]);
+        assert!(matches!(pair_zscore(&y, &x, 2, SpreadMode::Diff), Err(ExprError::LengthMismatch(_))));
+        assert!("spread".parse::<SpreadMode>().is_err());
+    }
 } 
\ No newline at end of file
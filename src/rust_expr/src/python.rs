@@ -0,0 +1,2532 @@
+//! Python bindings: thin `#[pyfunction]` shims over the kernels in the crate root
+
+// pyo3 0.19's `#[pymethods]` expansion trips this lint on recent compilers
+#![allow(non_local_definitions)]
+
+use numpy::{IntoPyArray, PyArray1, PyArray2, PyArray3};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use super::*;
+
+#[cfg(feature = "polars")]
+mod polars;
+
+/// Python exception classes mirroring `ExprError`
+///
+/// `ExprError` subclasses `ValueError`, so callers catching `ValueError` keep working.
+// pyo3 0.19's `create_exception!` expansion checks a cfg recent compilers don't know
+#[allow(unexpected_cfgs)]
+mod exceptions {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyValueError;
+
+    create_exception!(rust_expr, ExprError, PyValueError, "Base class for rust_expr errors.");
+    create_exception!(rust_expr, InvalidPeriod, ExprError, "A window or period is out of range.");
+    create_exception!(rust_expr, ComputationError, ExprError, "A computation could not be completed.");
+    create_exception!(rust_expr, InvalidParameter, ExprError, "A parameter value is invalid.");
+    create_exception!(rust_expr, LengthMismatch, ExprError, "Paired inputs have different lengths.");
+    create_exception!(rust_expr, ParseError, ExprError, "A formula could not be parsed or evaluated.");
+}
+
+impl From<ExprError> for PyErr {
+    fn from(e: ExprError) -> Self {
+        let message = e.to_string();
+        match e {
+            ExprError::InvalidPeriod(_) => exceptions::InvalidPeriod::new_err(message),
+            ExprError::ComputationError(_) => exceptions::ComputationError::new_err(message),
+            ExprError::InvalidParameter(_) => exceptions::InvalidParameter::new_err(message),
+            ExprError::LengthMismatch(_) => exceptions::LengthMismatch::new_err(message),
+            ExprError::ParseError(_) => exceptions::ParseError::new_err(message),
+        }
+    }
+}
+
+/// Set the module-wide precision mode for rolling std/correlation ("fast" or "accurate")
+///
+/// "fast" (the default) is the sliding Welford update, O(1) per step. "accurate" recomputes
+/// each window's moments about its mean, O(window) per step.
+#[pyfunction]
+fn set_precision_mode(mode: &str) -> PyResult<()> {
+    let mode: PrecisionMode = mode.parse()?;
+    ACCURATE_PRECISION.store(mode == PrecisionMode::Accurate, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The current module-wide precision mode
+#[pyfunction]
+fn get_precision_mode() -> &'static str {
+    match precision_mode() {
+        PrecisionMode::Fast => "fast",
+        PrecisionMode::Accurate => "accurate",
+    }
+}
+
+/// Set the module-wide near-zero denominator tolerances (both default to f64 epsilon)
+#[pyfunction]
+#[pyo3(signature = (rel_tol=f64::EPSILON, abs_tol=f64::EPSILON))]
+fn set_zero_tolerance(rel_tol: f64, abs_tol: f64) -> PyResult<()> {
+    if !(rel_tol.is_finite() && abs_tol.is_finite() && rel_tol >= 0.0 && abs_tol >= 0.0) {
+        return Err(ExprError::InvalidParameter("rel_tol and abs_tol must be finite and non-negative".into()).into());
+    }
+    ZERO_REL_TOL.store(rel_tol.to_bits(), Ordering::Relaxed);
+    ZERO_ABS_TOL.store(abs_tol.to_bits(), Ordering::Relaxed);
+    Ok(())
+}
+
+/// The current module-wide `(rel_tol, abs_tol)`
+#[pyfunction]
+fn get_zero_tolerance() -> (f64, f64) {
+    (
+        f64::from_bits(ZERO_REL_TOL.load(Ordering::Relaxed)),
+        f64::from_bits(ZERO_ABS_TOL.load(Ordering::Relaxed)),
+    )
+}
+
+/// Copy a numpy array into an owned ndarray
+fn to_array(data: &PyArray1<f64>) -> Array1<f64> {
+    data.readonly().as_array().to_owned()
+}
+
+/// Copy a 2D numpy array into an owned ndarray
+fn to_array2(data: &PyArray2<f64>) -> Array2<f64> {
+    data.readonly().as_array().to_owned()
+}
+
+/// Copy `values` into a caller-provided float64 array of the same length
+///
+/// Backs the `*_into` variants: the kernel still builds its result internally, but no new
+/// numpy array is created, so Python sees no allocation. A length mismatch raises
+/// `LengthMismatch` (a `ValueError`); a read-only or already borrowed `out` raises too.
+fn write_into(out: &PyArray1<f64>, values: &Array1<f64>) -> PyResult<()> {
+    if out.len() != values.len() {
+        return Err(ExprError::LengthMismatch(format!(
+            "out has length {} but the input has length {}", out.len(), values.len()
+        )).into());
+    }
+    let mut out = out.try_readwrite()?;
+    out.as_array_mut().assign(values);
+    Ok(())
+}
+
+/// A float64 or float32 series accepted by the `ts_*` functions
+///
+/// float32 input is widened to f64 for the kernels, which accumulate in f64 as usual, and
+/// the result is rounded back to float32 (see `float_output`).
+#[derive(FromPyObject)]
+enum FloatArray1<'py> {
+    F64(&'py PyArray1<f64>),
+    F32(&'py PyArray1<f32>),
+}
+
+impl FloatArray1<'_> {
+    fn to_f64(&self) -> Array1<f64> {
+        match self {
+            FloatArray1::F64(data) => to_array(data),
+            FloatArray1::F32(data) => data.readonly().as_array().mapv(f64::from),
+        }
+    }
+
+    fn is_f32(&self) -> bool {
+        matches!(self, FloatArray1::F32(_))
+    }
+}
+
+/// A float64 or float32 `(time, assets)` panel; see `FloatArray1`
+#[derive(FromPyObject)]
+enum FloatArray2<'py> {
+    F64(&'py PyArray2<f64>),
+    F32(&'py PyArray2<f32>),
+}
+
+impl FloatArray2<'_> {
+    fn to_f64(&self) -> Array2<f64> {
+        match self {
+            FloatArray2::F64(data) => to_array2(data),
+            FloatArray2::F32(data) => data.readonly().as_array().mapv(f64::from),
+        }
+    }
+
+    fn is_f32(&self) -> bool {
+        matches!(self, FloatArray2::F32(_))
+    }
+}
+
+/// Hand a result back as float32 when the input was float32, otherwise as float64
+///
+/// Narrowing rounds each value to the nearest f32 (relative error up to about 6e-8), so
+/// f32 results can differ from the f64 path in the last digits and compare unequal to it
+/// bit-for-bit; NaN stays NaN and magnitudes beyond f32 range become inf. Peak memory is
+/// the f32 input plus one temporary f64 copy.
+fn float_output<D: ndarray::Dimension>(py: Python<'_>, values: ndarray::Array<f64, D>, as_f32: bool) -> PyObject {
+    if as_f32 {
+        values.mapv(|v| v as f32).into_pyarray(py).into_py(py)
+    } else {
+        values.into_pyarray(py).into_py(py)
+    }
+}
+
+#[pymethods]
+impl RollingMeanChunks {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Py<PyArray1<f64>>> {
+        let py = slf.py();
+        slf.next_chunk().map(|chunk| chunk.into_pyarray(py).to_owned())
+    }
+}
+
+/// Rolling mean yielded chunk by chunk
+#[pyfunction]
+fn rolling_mean_chunks(data: &PyArray1<f64>, window: usize, chunk_size: usize) -> PyResult<RollingMeanChunks> {
+    let data = data.readonly().as_array().to_vec();
+    Ok(RollingMeanChunks::with_data(data, window, chunk_size)?)
+}
+
+#[pymethods]
+impl RollingRank {
+    #[new]
+    fn new(window: usize) -> PyResult<Self> {
+        Ok(RollingRank::with_window(window)?)
+    }
+
+    /// Add an observation and return the current percentile rank
+    #[pyo3(name = "push")]
+    fn py_push(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Alias of `push`
+    fn update(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Clear the window
+    fn reset(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+#[pymethods]
+impl RollingMean {
+    #[new]
+    fn new(window: usize) -> PyResult<Self> {
+        Ok(RollingMean::with_window(window)?)
+    }
+
+    /// Add an observation and return the current mean (NaN until the window fills)
+    #[pyo3(name = "push")]
+    fn py_push(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Alias of `push`
+    fn update(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Clear the window
+    #[pyo3(name = "reset")]
+    fn py_reset(&mut self) {
+        self.reset();
+    }
+}
+
+#[pymethods]
+impl RollingStd {
+    #[new]
+    #[pyo3(signature = (window, ddof=0))]
+    fn new(window: usize, ddof: usize) -> PyResult<Self> {
+        Ok(RollingStd::with_window(window, ddof)?)
+    }
+
+    /// Add an observation and return the current std (NaN until the window fills)
+    #[pyo3(name = "push")]
+    fn py_push(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Alias of `push`
+    fn update(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// Clear the window
+    #[pyo3(name = "reset")]
+    fn py_reset(&mut self) {
+        self.reset();
+    }
+}
+
+#[pymethods]
+impl StreamingQuantile {
+    #[new]
+    fn new(q: f64) -> PyResult<Self> {
+        Ok(StreamingQuantile::with_quantile(q)?)
+    }
+
+    /// Add an observation and return the current quantile estimate
+    fn update(&mut self, value: f64) -> f64 {
+        self.push(value)
+    }
+
+    /// The current quantile estimate (NaN before any finite update)
+    #[pyo3(name = "current")]
+    fn py_current(&self) -> f64 {
+        self.current()
+    }
+
+    /// Forget all observations
+    fn reset(&mut self) {
+        *self = StreamingQuantile::with_quantile(self.q).unwrap();
+    }
+}
+
+#[pymethods]
+impl Factor {
+    #[new]
+    fn py_new(values: &PyArray1<f64>) -> Self {
+        Factor::new(to_array(values))
+    }
+
+    /// `rolling_mean` of the wrapped series as a new `Factor`
+    #[pyo3(name = "rolling_mean")]
+    fn py_rolling_mean(&self, window: usize) -> PyResult<Factor> {
+        Ok(self.rolling_mean(window)?)
+    }
+
+    /// `rolling_std` of the wrapped series as a new `Factor`
+    #[pyo3(name = "rolling_std", signature = (window, ddof=0))]
+    fn py_rolling_std(&self, window: usize, ddof: usize) -> PyResult<Factor> {
+        Ok(self.rolling_std(window, ddof)?)
+    }
+
+    /// `ts_rank`-style percentile over the last `window` bars as a new `Factor`
+    #[pyo3(name = "rank")]
+    fn py_rank(&self, window: usize) -> PyResult<Factor> {
+        Ok(self.rank(window)?)
+    }
+
+    /// `rolling_zscore` of the wrapped series as a new `Factor`
+    #[pyo3(name = "zscore", signature = (window, ddof=0, std_floor=0.0))]
+    fn py_zscore(&self, window: usize, ddof: usize, std_floor: f64) -> PyResult<Factor> {
+        Ok(self.zscore(window, ddof, std_floor)?)
+    }
+
+    /// `ts_delta` of the wrapped series as a new `Factor`
+    #[pyo3(name = "delta", signature = (periods=1))]
+    fn py_delta(&self, periods: usize) -> PyResult<Factor> {
+        Ok(self.delta(periods)?)
+    }
+
+    /// `clip` of the wrapped series as a new `Factor`
+    #[pyo3(name = "clip")]
+    fn py_clip(&self, lo: f64, hi: f64) -> PyResult<Factor> {
+        Ok(self.clip(lo, hi)?)
+    }
+
+    /// `sign` of the wrapped series as a new `Factor`
+    #[pyo3(name = "sign")]
+    fn py_sign(&self) -> Factor {
+        self.sign()
+    }
+
+    /// Replace NaN/inf with `value`, as the factor functions' `fill_value` does
+    #[pyo3(name = "fill_nan")]
+    fn py_fill_nan(&self, value: f64) -> Factor {
+        self.fill_nan(value)
+    }
+
+    /// Copy the current values out as a float64 array
+    fn to_numpy<'py>(&self, py: Python<'py>) -> &'py PyArray1<f64> {
+        self.values().clone().into_pyarray(py)
+    }
+
+    fn __len__(&self) -> usize {
+        self.values().len()
+    }
+}
+
+#[pymethods]
+impl StreamingCorrMatrix {
+    #[new]
+    fn new(n_assets: usize, window: usize) -> PyResult<Self> {
+        Ok(StreamingCorrMatrix::with_shape(n_assets, window)?)
+    }
+
+    /// Add one tick of asset values and return the current correlation matrix
+    fn update<'py>(&mut self, py: Python<'py>, values: &PyArray1<f64>) -> PyResult<&'py PyArray2<f64>> {
+        let tick = values.readonly().as_array().to_vec();
+        Ok(self.push(tick)?.into_pyarray(py))
+    }
+
+    /// Clear all ticks
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+/// Linear-decay weighted moving average
+#[pyfunction]
+#[pyo3(name = "decay_linear", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_decay_linear<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| decay_linear(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Weighted moving average with a user-supplied kernel (oldest bar first)
+#[pyfunction]
+#[pyo3(name = "weighted_rolling_mean", signature = (data, weights, trim_edges=false, warmup_fill="nan"))]
+fn py_weighted_rolling_mean<'py>(py: Python<'py>, data: &PyArray1<f64>, weights: &PyArray1<f64>, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let weights = to_array(weights);
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| weighted_rolling_mean(&v[0], &weights))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Smoothing factor from exactly one of `span`, `alpha` or `halflife`
+fn resolve_alpha(span: Option<f64>, alpha: Option<f64>, halflife: Option<f64>) -> Result<f64> {
+    match (span, alpha, halflife) {
+        (Some(span), None, None) => span_to_alpha(span),
+        (None, Some(alpha), None) => Ok(alpha),
+        (None, None, Some(halflife)) => halflife_to_alpha(halflife),
+        _ => Err(ExprError::InvalidParameter("pass exactly one of span, alpha or halflife".into())),
+    }
+}
+
+/// Exponential moving average from one of `span`, `alpha` or `halflife`, optionally pandas-`adjust`ed
+#[pyfunction]
+#[pyo3(name = "ema", signature = (data, span=None, alpha=None, adjust=false, halflife=None))]
+fn py_ema<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    span: Option<f64>,
+    alpha: Option<f64>,
+    adjust: bool,
+    halflife: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    Ok(ema(&to_array(data), resolve_alpha(span, alpha, halflife)?, adjust)?.into_pyarray(py))
+}
+
+/// Double EMA `2 * ema - ema(ema)`, from one of `span`, `alpha` or `halflife` as in `ema`
+#[pyfunction]
+#[pyo3(name = "dema", signature = (data, span=None, alpha=None, adjust=false, halflife=None))]
+fn py_dema<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    span: Option<f64>,
+    alpha: Option<f64>,
+    adjust: bool,
+    halflife: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    Ok(dema(&to_array(data), resolve_alpha(span, alpha, halflife)?, adjust)?.into_pyarray(py))
+}
+
+/// Triple EMA `3 * ema - 3 * ema(ema) + ema(ema(ema))`, from one of `span`, `alpha` or `halflife`
+#[pyfunction]
+#[pyo3(name = "tema", signature = (data, span=None, alpha=None, adjust=false, halflife=None))]
+fn py_tema<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    span: Option<f64>,
+    alpha: Option<f64>,
+    adjust: bool,
+    halflife: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    Ok(tema(&to_array(data), resolve_alpha(span, alpha, halflife)?, adjust)?.into_pyarray(py))
+}
+
+/// Exponentially weighted variance from `span`, `alpha` or `halflife`; `bias=False` is pandas' default
+#[pyfunction]
+#[pyo3(name = "ewm_var", signature = (data, span=None, alpha=None, bias=false, halflife=None))]
+fn py_ewm_var<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    span: Option<f64>,
+    alpha: Option<f64>,
+    bias: bool,
+    halflife: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    Ok(ewm_var(&to_array(data), resolve_alpha(span, alpha, halflife)?, bias)?.into_pyarray(py))
+}
+
+/// Exponentially weighted std, the square root of `ewm_var`
+#[pyfunction]
+#[pyo3(name = "ewm_std", signature = (data, span=None, alpha=None, bias=false, halflife=None))]
+fn py_ewm_std<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    span: Option<f64>,
+    alpha: Option<f64>,
+    bias: bool,
+    halflife: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    Ok(ewm_std(&to_array(data), resolve_alpha(span, alpha, halflife)?, bias)?.into_pyarray(py))
+}
+
+/// Fill the warm-up region of any rolling output ("nan", "first_valid" or "zero")
+#[pyfunction]
+#[pyo3(name = "warmup_fill")]
+fn py_warmup_fill<'py>(py: Python<'py>, result: &PyArray1<f64>, mode: &str) -> PyResult<&'py PyArray1<f64>> {
+    Ok(fill_warmup(to_array(result), mode.parse()?).into_pyarray(py))
+}
+
+/// Trim the warm-up region off any rolling output
+#[pyfunction]
+#[pyo3(name = "drop_warmup")]
+fn py_drop_warmup<'py>(py: Python<'py>, result: &PyArray1<f64>) -> (&'py PyArray1<f64>, usize) {
+    let (trimmed, start) = drop_warmup(&to_array(result));
+    (trimmed.into_pyarray(py), start)
+}
+
+/// Carry the last valid value forward over NaN gaps, at most `limit` bars per gap
+#[pyfunction]
+#[pyo3(name = "forward_fill", signature = (data, limit=None))]
+fn py_forward_fill<'py>(py: Python<'py>, data: &PyArray1<f64>, limit: Option<usize>) -> &'py PyArray1<f64> {
+    forward_fill(&to_array(data), limit).into_pyarray(py)
+}
+
+/// Carry the next valid value backward over NaN gaps, at most `limit` bars per gap
+#[pyfunction]
+#[pyo3(name = "backward_fill", signature = (data, limit=None))]
+fn py_backward_fill<'py>(py: Python<'py>, data: &PyArray1<f64>, limit: Option<usize>) -> &'py PyArray1<f64> {
+    backward_fill(&to_array(data), limit).into_pyarray(py)
+}
+
+/// Inspect the window behind a single rolling output
+#[pyfunction]
+#[pyo3(name = "window_at", signature = (data, window, index, skipna=false))]
+fn py_window_at<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, index: usize, skipna: bool) -> PyResult<&'py PyArray1<f64>> {
+    let result = window_at(&to_array(data), window, index, skipna)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling mean with early partial windows and a companion `is_partial` mask
+#[pyfunction]
+#[pyo3(name = "rolling_mean_partial")]
+fn py_rolling_mean_partial<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, min_periods: usize) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<bool>)> {
+    let (values, partial) = rolling_mean_partial(&to_array(data), window, min_periods)?;
+    Ok((values.into_pyarray(py), partial.into_pyarray(py)))
+}
+
+/// Rolling mean with the number of valid observations behind each output as int64
+///
+/// `min_periods` defaults to `window`.
+#[pyfunction]
+#[pyo3(name = "rolling_mean_with_count", signature = (data, window, min_periods=None))]
+fn py_rolling_mean_with_count<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, min_periods: Option<usize>) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<i64>)> {
+    let (values, counts) = rolling_mean_with_count(&to_array(data), window, min_periods.unwrap_or(window))?;
+    Ok((values.into_pyarray(py), counts.into_pyarray(py)))
+}
+
+/// Mean over a window growing as a fraction of history, capped at `max_window`
+#[pyfunction]
+#[pyo3(name = "fractional_window_mean")]
+fn py_fractional_window_mean<'py>(py: Python<'py>, data: &PyArray1<f64>, frac: f64, max_window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(fractional_window_mean(&to_array(data), frac, max_window)?.into_pyarray(py))
+}
+
+/// Rolling mean over `(t - window_ns, t]` for irregularly sampled bars
+///
+/// `timestamps_ns` is an int64 array (e.g. `datetime64[ns]` viewed as int64) that must be
+/// non-decreasing; NaN/inf inputs give NaN at their own index and are skipped.
+#[pyfunction]
+#[pyo3(name = "rolling_mean_time")]
+fn py_rolling_mean_time<'py>(py: Python<'py>, values: &PyArray1<f64>, timestamps_ns: &PyArray1<i64>, window_ns: i64) -> PyResult<&'py PyArray1<f64>> {
+    let stamps = timestamps_ns.readonly().as_array().to_vec();
+    Ok(rolling_mean_time(&to_array(values), &stamps, window_ns)?.into_pyarray(py))
+}
+
+/// Rolling std with early partial windows and a companion `is_partial` mask
+#[pyfunction]
+#[pyo3(name = "rolling_std_partial", signature = (data, window, min_periods, precision=None, ddof=0, clamp_negative=true))]
+fn py_rolling_std_partial<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    min_periods: usize,
+    precision: Option<&str>,
+    ddof: usize,
+    clamp_negative: bool,
+) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<bool>)> {
+    let mode = resolve_precision(precision)?;
+    let (values, partial) = rolling_std_partial_with(&to_array(data), window, min_periods, ddof, mode, clamp_negative)?;
+    Ok((values.into_pyarray(py), partial.into_pyarray(py)))
+}
+
+/// Derive an integer window from a fractional specification
+#[pyfunction]
+#[pyo3(name = "round_window", signature = (value, mode="nearest"))]
+fn py_round_window(value: f64, mode: &str) -> PyResult<usize> {
+    Ok(round_window(value, mode.parse()?)?)
+}
+
+/// Rolling mean absolute deviation from the mean
+#[pyfunction]
+#[pyo3(name = "rolling_mean_abs_dev", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_mean_abs_dev<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_mean_abs_dev(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling quantile with numpy-style linear interpolation
+#[pyfunction]
+#[pyo3(name = "rolling_quantile", signature = (data, window, q, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_quantile<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    q: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_quantile(&v[0], window, q))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling median
+#[pyfunction]
+#[pyo3(name = "rolling_median", signature = (data, window, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+fn py_rolling_median<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_median(&v[0], window))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling interquartile range, optionally as a Gaussian-equivalent std
+#[pyfunction]
+#[pyo3(name = "rolling_iqr", signature = (data, window, normalize=false, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_iqr<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    normalize: bool,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_iqr(&v[0], window, normalize))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling sum over the last `window` valid values, with `ts_mean`'s windows
+#[pyfunction]
+#[pyo3(name = "rolling_sum", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_sum<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_sum(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling product over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_prod", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_prod<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_prod(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling geometric mean; of `1 + r` values, the per-bar compounded growth
+#[pyfunction]
+#[pyo3(name = "rolling_geomean", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_geomean<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_geomean(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling variance, `ts_std` before the square root
+#[pyfunction]
+#[pyo3(name = "rolling_var", signature = (data, window, ddof=0, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_var<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    ddof: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_var(&v[0], window, ddof))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling median absolute deviation, optionally scaled by 1.4826 to a normal-consistent std
+#[pyfunction]
+#[pyo3(name = "rolling_mad", signature = (data, window, normalized=false, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_mad<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    normalized: bool,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_mad(&v[0], window, normalized))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling Shannon entropy (bits) of a `bins`-bucket histogram over each window's own range
+#[pyfunction]
+#[pyo3(name = "rolling_entropy", signature = (data, window, bins=10, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_entropy<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    bins: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_entropy(&v[0], window, bins))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling sample skewness
+#[pyfunction]
+#[pyo3(name = "rolling_skew", signature = (data, window, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+fn py_rolling_skew<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_skew(&v[0], window))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling sample excess kurtosis
+#[pyfunction]
+#[pyo3(name = "rolling_kurt", signature = (data, window, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+fn py_rolling_kurt<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_kurt(&v[0], window))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling Jarque-Bera normality statistic
+#[pyfunction]
+#[pyo3(name = "rolling_jarque_bera", signature = (returns, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_jarque_bera<'py>(py: Python<'py>, returns: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_jarque_bera(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling beta of `y` on `x`, optionally Blume-adjusted
+#[pyfunction]
+#[pyo3(name = "rolling_beta", signature = (x, y, window, blume_adjust=false, blume_weight=0.67, blume_target=1.0, fit_intercept=true, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_beta<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    window: usize,
+    blume_adjust: bool,
+    blume_weight: f64,
+    blume_target: f64,
+    fit_intercept: bool,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(x), to_array(y)];
+    let mut result = compute_trimmed(&inputs, trim_edges, |v| rolling_beta(&v[0], &v[1], window, fit_intercept))?;
+    if blume_adjust {
+        result = apply_blume(&result, blume_weight, blume_target);
+    }
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Pairs-trading spread z-score
+#[pyfunction]
+#[pyo3(name = "pair_zscore", signature = (y, x, window, mode="ratio"))]
+fn py_pair_zscore<'py>(py: Python<'py>, y: &PyArray1<f64>, x: &PyArray1<f64>, window: usize, mode: &str) -> PyResult<&'py PyArray1<f64>> {
+    let result = pair_zscore(&to_array(y), &to_array(x), window, mode.parse()?)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling log-return correlation of two price series
+#[pyfunction]
+#[pyo3(name = "rolling_logret_corr", signature = (price_x, price_y, window, return_periods=1, precision=None, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_logret_corr<'py>(
+    py: Python<'py>,
+    price_x: &PyArray1<f64>,
+    price_y: &PyArray1<f64>,
+    window: usize,
+    return_periods: usize,
+    precision: Option<&str>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mode = resolve_precision(precision)?;
+    let inputs = [to_array(price_x), to_array(price_y)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_logret_corr(&v[0], &v[1], window, return_periods, mode))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling R² of forward returns regressed on a factor
+#[pyfunction]
+#[pyo3(name = "rolling_predictive_r2", signature = (factor, forward_ret, window, precision=None, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_predictive_r2<'py>(
+    py: Python<'py>,
+    factor: &PyArray1<f64>,
+    forward_ret: &PyArray1<f64>,
+    window: usize,
+    precision: Option<&str>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mode = resolve_precision(precision)?;
+    let inputs = [to_array(factor), to_array(forward_ret)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_predictive_r2(&v[0], &v[1], window, mode))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Single-factor residual returns against a lagged rolling market beta
+#[pyfunction]
+#[pyo3(name = "rolling_residualize", signature = (asset_ret, market_ret, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_residualize<'py>(
+    py: Python<'py>,
+    asset_ret: &PyArray1<f64>,
+    market_ret: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(asset_ret), to_array(market_ret)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_residualize(&v[0], &v[1], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// `rolling_beta` of every column of a `(time, assets)` return panel on a shared `market`
+///
+/// Runs with the GIL released; a NaN/inf in an asset or the market drops that bar from
+/// that asset's window only.
+#[pyfunction]
+#[pyo3(name = "panel_beta", signature = (returns, market, window, fit_intercept=true))]
+fn py_panel_beta<'py>(py: Python<'py>, returns: &PyArray2<f64>, market: &PyArray1<f64>, window: usize, fit_intercept: bool) -> PyResult<&'py PyArray2<f64>> {
+    let (returns, market) = (to_array2(returns), to_array(market));
+    let result = py.allow_threads(|| panel_beta(&returns, &market, window, fit_intercept))?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Beta-quality check: rolling CV of the rolling beta
+#[pyfunction]
+#[pyo3(name = "rolling_beta_stability", signature = (asset, market, beta_window, stability_window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_beta_stability<'py>(
+    py: Python<'py>,
+    asset: &PyArray1<f64>,
+    market: &PyArray1<f64>,
+    beta_window: usize,
+    stability_window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(asset), to_array(market)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_beta_stability(&v[0], &v[1], beta_window, stability_window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Factor-health monitor flagging recent IC decay
+#[pyfunction]
+#[pyo3(name = "factor_decay_flag")]
+fn py_factor_decay_flag<'py>(
+    py: Python<'py>,
+    factor: &PyArray1<f64>,
+    forward_ret: &PyArray1<f64>,
+    recent_window: usize,
+    baseline_window: usize,
+    drop_threshold: f64,
+) -> PyResult<&'py PyArray1<bool>> {
+    let result = factor_decay_flag(&to_array(factor), &to_array(forward_ret), recent_window, baseline_window, drop_threshold)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling covariance with a configurable ddof (default 1, as in `numpy.cov`)
+#[pyfunction]
+#[pyo3(name = "rolling_cov", signature = (x, y, window, ddof=1, precision=None, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_cov<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    window: usize,
+    ddof: usize,
+    precision: Option<&str>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mode = resolve_precision(precision)?;
+    let inputs = [to_array(x), to_array(y)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_cov(&v[0], &v[1], window, ddof, mode))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling Spearman rank correlation, average ranks for ties
+#[pyfunction]
+#[pyo3(name = "rolling_spearman", signature = (x, y, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_spearman<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(x), to_array(y)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_spearman(&v[0], &v[1], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling least-squares slope per bar against the bar index ("price velocity")
+///
+/// NaN/inf bars are skipped at their true spacing; `skipna=False` instead gives NaN
+/// wherever the trailing `window` bars hold one.
+#[pyfunction]
+#[pyo3(name = "rolling_slope", signature = (data, window, skipna=true))]
+fn py_rolling_slope<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, skipna: bool) -> PyResult<&'py PyArray1<f64>> {
+    let data = to_array(data);
+    Ok(apply_skipna(rolling_slope(&data, window)?, skipna, &[&data], window).into_pyarray(py))
+}
+
+/// Rolling R² of the `rolling_slope` fit, NaN for flat windows; `skipna` as in `rolling_slope`
+#[pyfunction]
+#[pyo3(name = "rolling_r2", signature = (data, window, skipna=true))]
+fn py_rolling_r2<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, skipna: bool) -> PyResult<&'py PyArray1<f64>> {
+    let data = to_array(data);
+    Ok(apply_skipna(rolling_r2(&data, window)?, skipna, &[&data], window).into_pyarray(py))
+}
+
+/// Rolling autocorrelation at `lag` over a trailing `window` (`1 <= lag < window`)
+#[pyfunction]
+#[pyo3(name = "rolling_autocorr", signature = (data, window, lag=1, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_autocorr<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    lag: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_autocorr(&v[0], window, lag))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Outlier-robust rolling correlation with Mahalanobis trimming
+#[pyfunction]
+#[pyo3(name = "rolling_robust_corr", signature = (x, y, window, trim_frac=0.1, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_robust_corr<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    window: usize,
+    trim_frac: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(x), to_array(y)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_robust_corr(&v[0], &v[1], window, trim_frac))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling correlation of two series net of a common driver
+#[pyfunction]
+#[pyo3(name = "rolling_partial_corr", signature = (x, y, z, window, precision=None, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_partial_corr<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    z: &PyArray1<f64>,
+    window: usize,
+    precision: Option<&str>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mode = resolve_precision(precision)?;
+    let inputs = [to_array(x), to_array(y), to_array(z)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_partial_corr(&v[0], &v[1], &v[2], window, mode))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling correlation significance p-value
+#[pyfunction]
+#[pyo3(name = "rolling_corr_pvalue", signature = (x, y, window, precision=None, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_corr_pvalue<'py>(
+    py: Python<'py>,
+    x: &PyArray1<f64>,
+    y: &PyArray1<f64>,
+    window: usize,
+    precision: Option<&str>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mode = resolve_precision(precision)?;
+    let inputs = [to_array(x), to_array(y)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_corr_pvalue(&v[0], &v[1], window, mode))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling Engle-Granger residual ADF statistic for a pair
+#[pyfunction]
+#[pyo3(name = "rolling_coint_stat", signature = (y, x, window, lags=1, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_coint_stat<'py>(py: Python<'py>, y: &PyArray1<f64>, x: &PyArray1<f64>, window: usize, lags: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(y), to_array(x)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_coint_stat(&v[0], &v[1], window, lags))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Augmented Dickey-Fuller stationarity test
+#[pyfunction]
+#[pyo3(name = "adf_test", signature = (data, max_lag, regression="c"))]
+fn py_adf_test(data: &PyArray1<f64>, max_lag: usize, regression: &str) -> PyResult<(f64, f64)> {
+    Ok(adf_test(&to_array(data), max_lag, regression.parse()?)?)
+}
+
+/// RiskMetrics-style exponentially weighted correlation
+#[pyfunction]
+#[pyo3(name = "ewm_correlation", signature = (x, y, lambda_=0.94))]
+fn py_ewm_correlation<'py>(py: Python<'py>, x: &PyArray1<f64>, y: &PyArray1<f64>, lambda_: f64) -> PyResult<&'py PyArray1<f64>> {
+    let result = ewm_correlation(&to_array(x), &to_array(y), lambda_)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling lead-lag correlation profile
+#[pyfunction]
+#[pyo3(name = "rolling_lead_lag")]
+fn py_rolling_lead_lag<'py>(py: Python<'py>, x: &PyArray1<f64>, y: &PyArray1<f64>, window: usize, max_lag: usize) -> PyResult<&'py PyArray2<f64>> {
+    let result = rolling_lead_lag(&to_array(x), &to_array(y), window, max_lag)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling percentile rank, optionally weighting the window observations
+///
+/// `method` ranks ties with the current value like scipy's `rankdata`: "max" (the default,
+/// counting values `<=` the current one), "min", "average" or "first" (the current value
+/// is the latest of its ties, so this matches "max").
+#[pyfunction]
+#[pyo3(name = "rolling_rank", signature = (data, window, weights=None, min_periods=None, method="max"))]
+fn py_rolling_rank<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    weights: Option<&PyArray1<f64>>,
+    min_periods: Option<usize>,
+    method: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let weights = weights.map(to_array);
+    let min_periods = min_periods.unwrap_or(window);
+    Ok(rolling_rank_with(&to_array(data), window, min_periods, weights.as_ref(), method.parse()?)?.into_pyarray(py))
+}
+
+/// Rolling z-score `(x - ts_mean) / ts_std`, the un-negated core of `mean_reversion_factor`
+#[pyfunction]
+#[pyo3(name = "rolling_zscore", signature = (data, window, ddof=0, std_floor=0.0, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_zscore<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    ddof: usize,
+    std_floor: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_zscore(&v[0], window, ddof, std_floor))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Exponentially weighted z-score, optionally with its effective sample size
+#[pyfunction]
+#[pyo3(name = "ew_zscore", signature = (data, alpha, return_ess=false, std_floor=0.0))]
+fn py_ew_zscore(py: Python<'_>, data: &PyArray1<f64>, alpha: f64, return_ess: bool, std_floor: f64) -> PyResult<PyObject> {
+    let data = to_array(data);
+    let z = ew_zscore(&data, alpha, std_floor)?.into_pyarray(py);
+    if return_ess {
+        let ess = ew_ess(&data, alpha)?.into_pyarray(py);
+        Ok((z, ess).into_py(py))
+    } else {
+        Ok(z.into_py(py))
+    }
+}
+
+/// Rolling effective sample size of a weight kernel
+#[pyfunction]
+#[pyo3(name = "rolling_ess", signature = (data, weights, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_ess<'py>(py: Python<'py>, data: &PyArray1<f64>, weights: &PyArray1<f64>, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let weights = to_array(weights);
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_ess(&v[0], &weights))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Discounted (fading-memory) cumulative sum
+#[pyfunction]
+#[pyo3(name = "discounted_sum")]
+fn py_discounted_sum<'py>(py: Python<'py>, data: &PyArray1<f64>, decay: f64) -> PyResult<&'py PyArray1<f64>> {
+    let result = discounted_sum(&to_array(data), decay)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Hybrid equal-weight / exponential z-score
+#[pyfunction]
+#[pyo3(name = "hybrid_zscore", signature = (data, window, alpha, blend=0.5, std_floor=0.0))]
+fn py_hybrid_zscore<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    alpha: f64,
+    blend: f64,
+    std_floor: f64,
+) -> PyResult<&'py PyArray1<f64>> {
+    let result = hybrid_zscore(&to_array(data), window, alpha, blend, std_floor)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Composite trend/vol regime label (0-3, -1 unknown); see `regime_label` for the encoding
+#[pyfunction]
+#[pyo3(name = "regime_label", signature = (prices, trend_window, vol_window, vol_high_q=0.8))]
+fn py_regime_label<'py>(py: Python<'py>, prices: &PyArray1<f64>, trend_window: usize, vol_window: usize, vol_high_q: f64) -> PyResult<&'py PyArray1<i64>> {
+    Ok(regime_label(&to_array(prices), trend_window, vol_window, vol_high_q)?.into_pyarray(py))
+}
+
+/// Regime-conditional rolling mean
+#[pyfunction]
+#[pyo3(name = "regime_rolling_mean")]
+fn py_regime_rolling_mean<'py>(py: Python<'py>, data: &PyArray1<f64>, regime: &PyArray1<i64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    let regime = regime.readonly().as_array().to_owned();
+    let result = regime_rolling_mean(&to_array(data), &regime, window)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling `func_name` computed within each group of long-format data
+///
+/// `params` may set `ddof` (std), `q` (quantile) and `std_floor` (zscore); unknown keys
+/// are rejected. Groups may be interleaved; see `group_apply`.
+#[pyfunction]
+#[pyo3(name = "groupby_rolling", signature = (data, group_ids, func_name, window, params=None))]
+fn py_groupby_rolling<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    group_ids: &PyArray1<i64>,
+    func_name: &str,
+    window: usize,
+    params: Option<&PyDict>,
+) -> PyResult<&'py PyArray1<f64>> {
+    let mut parsed = GroupParams::default();
+    if let Some(params) = params {
+        for (key, value) in params.iter() {
+            match key.extract::<&str>()? {
+                "ddof" => parsed.ddof = value.extract()?,
+                "q" => parsed.q = value.extract()?,
+                "std_floor" => parsed.std_floor = value.extract()?,
+                other => {
+                    return Err(ExprError::InvalidParameter(format!(
+                        "unknown param '{}', expected one of: ddof, q, std_floor", other
+                    )).into())
+                }
+            }
+        }
+    }
+    let group_ids = group_ids.readonly().as_array().to_owned();
+    Ok(groupby_rolling(&to_array(data), &group_ids, func_name.parse()?, window, &parsed)?.into_pyarray(py))
+}
+
+/// Rolling count of distinct values
+#[pyfunction]
+#[pyo3(name = "rolling_nunique", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_nunique<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_nunique(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Volatility of rolling volatility, a vol-clustering feature
+#[pyfunction]
+#[pyo3(name = "rolling_vol_of_vol", signature = (returns, vol_window, vov_window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_vol_of_vol<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    vol_window: usize,
+    vov_window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_vol_of_vol(&v[0], vol_window, vov_window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling (fractional) Kelly position size from the window mean and variance of returns
+#[pyfunction]
+#[pyo3(name = "rolling_kelly", signature = (returns, window, fraction=1.0, cap=None, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_kelly<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    window: usize,
+    fraction: f64,
+    cap: Option<f64>,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_kelly(&v[0], window, fraction, cap))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Drift strength relative to noise, `|rolling mean| / rolling std`
+#[pyfunction]
+#[pyo3(name = "rolling_snr", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_snr<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_snr(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling longest drawdown duration in bars
+#[pyfunction]
+#[pyo3(name = "rolling_drawdown_duration", signature = (prices, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_drawdown_duration<'py>(py: Python<'py>, prices: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(prices)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_drawdown_duration(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Worst peak-to-trough decline of an equity or price series, as a negative fraction
+///
+/// Leading NaNs are ignored and a series that never falls gives 0.0.
+#[pyfunction]
+#[pyo3(name = "max_drawdown")]
+fn py_max_drawdown(prices: &PyArray1<f64>) -> f64 {
+    max_drawdown(&to_array(prices))
+}
+
+/// Distance below the trailing-window peak, a continuous risk-state feature
+///
+/// Also exported as `rolling_drawdown`.
+#[pyfunction]
+#[pyo3(name = "rolling_current_drawdown", signature = (prices, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_current_drawdown<'py>(py: Python<'py>, prices: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(prices)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_current_drawdown(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Annualized rolling Sharpe ratio of returns; NaN where the window std is zero
+#[pyfunction]
+#[pyo3(name = "rolling_sharpe", signature = (returns, window, periods_per_year=252.0, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_sharpe<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    window: usize,
+    periods_per_year: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_sharpe(&v[0], window, periods_per_year))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling downside deviation below `mar`, over the full window count
+#[pyfunction]
+#[pyo3(name = "rolling_downside_dev", signature = (returns, window, mar=0.0, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_downside_dev<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    window: usize,
+    mar: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_downside_dev(&v[0], window, mar))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Annualized rolling Sortino ratio; NaN where the window has no shortfall below `mar`
+#[pyfunction]
+#[pyo3(name = "rolling_sortino", signature = (returns, window, mar=0.0, periods_per_year=252.0, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_sortino<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    window: usize,
+    mar: f64,
+    periods_per_year: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_sortino(&v[0], window, mar, periods_per_year))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling Sharpe / Sortino / MAD-ratio with one annualization path
+#[pyfunction]
+#[pyo3(name = "rolling_risk_adjusted", signature = (returns, window, denom="std", ann_factor=252.0, rf=0.0, mar=0.0, trim_edges=false, warmup_fill="nan"))]
+#[allow(clippy::too_many_arguments)]
+fn py_rolling_risk_adjusted<'py>(
+    py: Python<'py>,
+    returns: &PyArray1<f64>,
+    window: usize,
+    denom: &str,
+    ann_factor: f64,
+    rf: f64,
+    mar: f64,
+    trim_edges: bool,
+    warmup_fill: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_risk_adjusted(&v[0], window, denom.parse()?, ann_factor, rf, mar))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Windowed signed-volume order-flow proxy
+#[pyfunction]
+#[pyo3(name = "rolling_volume_delta", signature = (close, volume, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_volume_delta<'py>(py: Python<'py>, close: &PyArray1<f64>, volume: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(close), to_array(volume)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_volume_delta(&v[0], &v[1], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling volume-weighted average price over the last `window` bars with price and volume
+#[pyfunction]
+#[pyo3(name = "rolling_vwap", signature = (price, volume, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_vwap<'py>(py: Python<'py>, price: &PyArray1<f64>, volume: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(price), to_array(volume)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_vwap(&v[0], &v[1], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Trend-consistency feature: fraction of up moves in the trailing window
+#[pyfunction]
+#[pyo3(name = "rolling_up_fraction", signature = (returns, window, include_zeros=false, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_up_fraction<'py>(py: Python<'py>, returns: &PyArray1<f64>, window: usize, include_zeros: bool, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(returns)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_up_fraction(&v[0], window, include_zeros))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Adaptive-threshold breakout signal from rolling quantiles
+#[pyfunction]
+#[pyo3(name = "percentile_breakout", signature = (data, window, upper_q=0.9, lower_q=0.1))]
+fn py_percentile_breakout<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, upper_q: f64, lower_q: f64) -> PyResult<&'py PyArray1<f64>> {
+    let result = percentile_breakout(&to_array(data), window, upper_q, lower_q)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling minimum over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_min", signature = (data, window, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+fn py_rolling_min<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_min(&v[0], window))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling maximum over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_max", signature = (data, window, trim_edges=false, warmup_fill="nan", step=1, compact=false))]
+fn py_rolling_max<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    trim_edges: bool,
+    warmup_fill: &str,
+    step: usize,
+    compact: bool,
+) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_max(&v[0], window))?;
+    Ok(stride_windows(fill_warmup(result, warmup_fill.parse()?), window, step, compact)?.into_pyarray(py))
+}
+
+/// Rolling minimum and maximum in one pass, returned as `(min, max)`
+#[pyfunction]
+#[pyo3(name = "rolling_minmax")]
+fn py_rolling_minmax<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<f64>)> {
+    let (min, max) = rolling_minmax(&to_array(data), window)?;
+    Ok((min.into_pyarray(py), max.into_pyarray(py)))
+}
+
+/// Rolling `max - min` over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_range", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_range<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_range(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Stochastic oscillator `(x - min) / (max - min)` in `[0, 1]`; Williams %R is this minus 1
+#[pyfunction]
+#[pyo3(name = "stochastic", signature = (data, window, trim_edges=false, warmup_fill="nan"))]
+fn py_stochastic<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| stochastic(&v[0], window))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Bollinger %B, `(x - (mean - k * std)) / (2 * k * std)`: 0 on the lower band, 1 on the upper
+#[pyfunction]
+#[pyo3(name = "bollinger_percent_b", signature = (data, window, k=2.0, trim_edges=false, warmup_fill="nan"))]
+fn py_bollinger_percent_b<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, k: f64, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| bollinger_percent_b(&v[0], window, k))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Bollinger bandwidth `2 * k * std / mean`
+#[pyfunction]
+#[pyo3(name = "bollinger_bandwidth", signature = (data, window, k=2.0, trim_edges=false, warmup_fill="nan"))]
+fn py_bollinger_bandwidth<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, k: f64, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| bollinger_bandwidth(&v[0], window, k))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Rolling maximum over the last `window` valid values and bars since it occurred, as `(max_value, bars_since_max)`
+#[pyfunction]
+#[pyo3(name = "rolling_max_with_pos")]
+fn py_rolling_max_with_pos<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<f64>)> {
+    let (max_value, bars_since) = rolling_max_with_pos(&to_array(data), window)?;
+    Ok((max_value.into_pyarray(py), bars_since.into_pyarray(py)))
+}
+
+/// Choppiness feature: zero crossings in the trailing window
+#[pyfunction]
+#[pyo3(name = "rolling_zero_crossings", signature = (data, window, rate=false, trim_edges=false, warmup_fill="nan"))]
+fn py_rolling_zero_crossings<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, rate: bool, trim_edges: bool, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let inputs = [to_array(data)];
+    let result = compute_trimmed(&inputs, trim_edges, |v| rolling_zero_crossings(&v[0], window, rate))?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Market-wide cross-sectional dispersion
+#[pyfunction]
+#[pyo3(name = "cs_dispersion", signature = (data, smooth_window=None))]
+fn py_cs_dispersion<'py>(py: Python<'py>, data: &PyArray2<f64>, smooth_window: Option<usize>) -> PyResult<&'py PyArray1<f64>> {
+    let result = cs_dispersion(&to_array2(data), smooth_window)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Cross-sectional rank in `[0, 1]` per timestamp
+///
+/// `method` ranks ties: "average" (the default; tied values share their mean rank),
+/// "min", "max" or "first" (column order).
+#[pyfunction]
+#[pyo3(name = "cs_rank", signature = (data, method="average"))]
+fn py_cs_rank<'py>(py: Python<'py>, data: &PyArray2<f64>, method: &str) -> PyResult<&'py PyArray2<f64>> {
+    Ok(cs_rank_with(&to_array2(data), method.parse()?).into_pyarray(py))
+}
+
+/// Per-row rank buckets `0..q` of a `(time, assets)` panel as int64, -1 for NaN/inf entries
+#[pyfunction]
+#[pyo3(name = "cs_bucket")]
+fn py_cs_bucket<'py>(py: Python<'py>, data: &PyArray2<f64>, q: usize) -> PyResult<&'py PyArray2<i64>> {
+    Ok(cs_bucket(&to_array2(data), q)?.into_pyarray(py))
+}
+
+/// Alpha101 `scale(x, a)`: cross-sectional weights with gross exposure `a`
+#[pyfunction]
+#[pyo3(name = "scale", signature = (data, a=1.0))]
+fn py_scale<'py>(py: Python<'py>, data: &PyArray1<f64>, a: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(scale_to_gross(&to_array(data), a)?.into_pyarray(py))
+}
+
+/// Per-row demean of a `(time, assets)` panel over its finite entries
+#[pyfunction]
+#[pyo3(name = "cs_demean")]
+fn py_cs_demean<'py>(py: Python<'py>, data: &PyArray2<f64>) -> &'py PyArray2<f64> {
+    cs_demean(&to_array2(data)).into_pyarray(py)
+}
+
+/// Per-row rank, demean and unit-gross scaling of a `(time, assets)` panel, each optional
+#[pyfunction]
+#[pyo3(name = "cs_normalize", signature = (data, rank=true, demean=true, scale=true))]
+fn py_cs_normalize<'py>(py: Python<'py>, data: &PyArray2<f64>, rank: bool, demean: bool, scale: bool) -> PyResult<&'py PyArray2<f64>> {
+    Ok(cs_normalize(&to_array2(data), rank, demean, scale)?.into_pyarray(py))
+}
+
+/// Quantile-based outlier clipping over the whole array
+#[pyfunction]
+#[pyo3(name = "winsorize", signature = (data, lower_q=0.01, upper_q=0.99))]
+fn py_winsorize<'py>(py: Python<'py>, data: &PyArray1<f64>, lower_q: f64, upper_q: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(winsorize(&to_array(data), lower_q, upper_q)?.into_pyarray(py))
+}
+
+/// Median/MAD-based outlier clipping over the whole array
+#[pyfunction]
+#[pyo3(name = "winsorize_mad", signature = (data, k=5.0))]
+fn py_winsorize_mad<'py>(py: Python<'py>, data: &PyArray1<f64>, k: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(winsorize_mad(&to_array(data), k)?.into_pyarray(py))
+}
+
+/// Neutralize a factor against style exposures, returning the OLS residual
+///
+/// `exposures` has one row per entry of `factor` and one column per style factor.
+#[pyfunction]
+#[pyo3(name = "neutralize")]
+fn py_neutralize<'py>(py: Python<'py>, factor: &PyArray1<f64>, exposures: &PyArray2<f64>) -> PyResult<&'py PyArray1<f64>> {
+    Ok(neutralize(&to_array(factor), &to_array2(exposures))?.into_pyarray(py))
+}
+
+/// Sign-preserving power transform `sign(x) * |x|^a`
+#[pyfunction]
+#[pyo3(name = "signed_power")]
+fn py_signed_power<'py>(py: Python<'py>, data: &PyArray1<f64>, a: f64) -> &'py PyArray1<f64> {
+    signed_power(&to_array(data), a).into_pyarray(py)
+}
+
+/// Alpha101 `sign(x)`: -1, 0 or +1, NaN passing through
+#[pyfunction]
+#[pyo3(name = "sign")]
+fn py_sign<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    sign(&to_array(data)).into_pyarray(py)
+}
+
+/// Pick `a` where the boolean mask `cond` is true and `b` elsewhere; all three must match in length
+#[pyfunction]
+#[pyo3(name = "where_")]
+fn py_where<'py>(py: Python<'py>, cond: &PyArray1<bool>, a: &PyArray1<f64>, b: &PyArray1<f64>) -> PyResult<&'py PyArray1<f64>> {
+    let cond = cond.readonly().as_array().to_owned();
+    Ok(where_(&cond, &to_array(a), &to_array(b))?.into_pyarray(py))
+}
+
+/// Boolean mask `a > b`; comparisons with NaN are false
+#[pyfunction]
+#[pyo3(name = "gt")]
+fn py_gt<'py>(py: Python<'py>, a: &PyArray1<f64>, b: &PyArray1<f64>) -> PyResult<&'py PyArray1<bool>> {
+    Ok(compare(&to_array(a), &to_array(b), Comparison::Gt)?.into_pyarray(py))
+}
+
+/// Boolean mask `a < b`; comparisons with NaN are false
+#[pyfunction]
+#[pyo3(name = "lt")]
+fn py_lt<'py>(py: Python<'py>, a: &PyArray1<f64>, b: &PyArray1<f64>) -> PyResult<&'py PyArray1<bool>> {
+    Ok(compare(&to_array(a), &to_array(b), Comparison::Lt)?.into_pyarray(py))
+}
+
+/// Boolean mask `a >= b`; comparisons with NaN are false
+#[pyfunction]
+#[pyo3(name = "ge")]
+fn py_ge<'py>(py: Python<'py>, a: &PyArray1<f64>, b: &PyArray1<f64>) -> PyResult<&'py PyArray1<bool>> {
+    Ok(compare(&to_array(a), &to_array(b), Comparison::Ge)?.into_pyarray(py))
+}
+
+/// Boolean mask `a <= b`; comparisons with NaN are false
+#[pyfunction]
+#[pyo3(name = "le")]
+fn py_le<'py>(py: Python<'py>, a: &PyArray1<f64>, b: &PyArray1<f64>) -> PyResult<&'py PyArray1<bool>> {
+    Ok(compare(&to_array(a), &to_array(b), Comparison::Le)?.into_pyarray(py))
+}
+
+/// +1 on an up-cross through zero, -1 on a down-cross, 0 otherwise (zeros never cross)
+#[pyfunction]
+#[pyo3(name = "sign_change")]
+fn py_sign_change<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    sign_change(&to_array(data)).into_pyarray(py)
+}
+
+/// Clamp each value to `[lo, hi]`; NaN/inf inputs give NaN
+#[pyfunction]
+#[pyo3(name = "clip")]
+fn py_clip<'py>(py: Python<'py>, data: &PyArray1<f64>, lo: f64, hi: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(clip(&to_array(data), lo, hi)?.into_pyarray(py))
+}
+
+/// Logistic squash into `(0, 1)`; NaN/inf inputs give NaN
+#[pyfunction]
+#[pyo3(name = "sigmoid")]
+fn py_sigmoid<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    sigmoid(&to_array(data)).into_pyarray(py)
+}
+
+/// `tanh(x / scale)` squash into `(-1, 1)`; NaN/inf inputs give NaN
+#[pyfunction]
+#[pyo3(name = "tanh_squash", signature = (data, scale=1.0))]
+fn py_tanh_squash<'py>(py: Python<'py>, data: &PyArray1<f64>, scale: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(tanh_squash(&to_array(data), scale)?.into_pyarray(py))
+}
+
+/// Per-date weighted Spearman IC across assets
+#[pyfunction]
+#[pyo3(name = "weighted_cs_rank_ic")]
+fn py_weighted_cs_rank_ic<'py>(
+    py: Python<'py>,
+    factor: &PyArray2<f64>,
+    forward_ret: &PyArray2<f64>,
+    weights: &PyArray2<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    let result = weighted_cs_rank_ic(&to_array2(factor), &to_array2(forward_ret), &to_array2(weights))?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Per-date Spearman rank IC of a `(time, assets)` factor panel against forward returns
+#[pyfunction]
+#[pyo3(name = "rank_ic")]
+fn py_rank_ic<'py>(py: Python<'py>, factor: &PyArray2<f64>, forward_returns: &PyArray2<f64>) -> PyResult<&'py PyArray1<f64>> {
+    Ok(rank_ic(&to_array2(factor), &to_array2(forward_returns))?.into_pyarray(py))
+}
+
+/// Permute each row of a `(time, assets)` panel independently; the same `seed` gives the same permutations
+#[pyfunction]
+#[pyo3(name = "shuffle_rows")]
+fn py_shuffle_rows<'py>(py: Python<'py>, data: &PyArray2<f64>, seed: u64) -> &'py PyArray2<f64> {
+    shuffle_rows(&to_array2(data), seed).into_pyarray(py)
+}
+
+/// All pairwise rolling correlations of a small panel
+#[pyfunction]
+#[pyo3(name = "rolling_pairwise_corr", signature = (data, window, precision=None))]
+fn py_rolling_pairwise_corr<'py>(py: Python<'py>, data: &PyArray2<f64>, window: usize, precision: Option<&str>) -> PyResult<&'py PyArray3<f64>> {
+    let result = rolling_pairwise_corr(&to_array2(data), window, resolve_precision(precision)?)?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Rolling PC1 variance share of a return panel
+#[pyfunction]
+#[pyo3(name = "rolling_pc1_share", signature = (data, window, warmup_fill="nan"))]
+fn py_rolling_pc1_share<'py>(py: Python<'py>, data: &PyArray2<f64>, window: usize, warmup_fill: &str) -> PyResult<&'py PyArray1<f64>> {
+    let result = rolling_pc1_share(&to_array2(data), window)?;
+    Ok(fill_warmup(result, warmup_fill.parse()?).into_pyarray(py))
+}
+
+/// Per-column NaN statistics as a dict of integer arrays
+#[pyfunction]
+#[pyo3(name = "nan_report")]
+fn py_nan_report(py: Python<'_>, data: &PyArray2<f64>) -> PyResult<PyObject> {
+    let report = nan_report(&to_array2(data));
+    let dict = PyDict::new(py);
+    dict.set_item("nan_count", report.nan_count.into_pyarray(py))?;
+    dict.set_item("longest_nan_run", report.longest_nan_run.into_pyarray(py))?;
+    dict.set_item("first_valid", report.first_valid.into_pyarray(py))?;
+    dict.set_item("last_valid", report.last_valid.into_pyarray(py))?;
+    Ok(dict.into_py(py))
+}
+
+/// Rolling-median outlier replacement, returning `(cleaned, replaced_mask)`
+#[pyfunction]
+#[pyo3(name = "rolling_robust_clean", signature = (data, window, threshold=5.0))]
+fn py_rolling_robust_clean<'py>(
+    py: Python<'py>,
+    data: &PyArray1<f64>,
+    window: usize,
+    threshold: f64,
+) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<bool>)> {
+    let (cleaned, replaced) = rolling_robust_clean(&to_array(data), window, threshold)?;
+    Ok((cleaned.into_pyarray(py), replaced.into_pyarray(py)))
+}
+
+/// Number of finite values in each trailing `window` bars, as float64
+#[pyfunction]
+#[pyo3(name = "rolling_count")]
+fn py_rolling_count<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(rolling_count(&to_array(data), window)?.into_pyarray(py))
+}
+
+/// Fraction of NaN/inf values in each trailing `window` bars, for masking sparse windows
+#[pyfunction]
+#[pyo3(name = "rolling_nan_ratio")]
+fn py_rolling_nan_ratio<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(rolling_nan_ratio(&to_array(data), window)?.into_pyarray(py))
+}
+
+/// Centred-window Hampel despiking; with `return_mask` the result is `(cleaned, replaced_mask)`
+#[pyfunction]
+#[pyo3(name = "hampel_filter", signature = (data, window, n_sigmas=3.0, return_mask=false))]
+fn py_hampel_filter(py: Python<'_>, data: &PyArray1<f64>, window: usize, n_sigmas: f64, return_mask: bool) -> PyResult<PyObject> {
+    let (cleaned, replaced) = hampel_filter(&to_array(data), window, n_sigmas)?;
+    if return_mask {
+        Ok((cleaned.into_pyarray(py), replaced.into_pyarray(py)).into_py(py))
+    } else {
+        Ok(cleaned.into_pyarray(py).into_py(py))
+    }
+}
+
+/// Factor with its per-bar change capped at `max_change_per_bar`
+#[pyfunction]
+#[pyo3(name = "cost_aware_smooth")]
+fn py_cost_aware_smooth<'py>(py: Python<'py>, factor: &PyArray1<f64>, max_change_per_bar: f64) -> PyResult<&'py PyArray1<f64>> {
+    Ok(cost_aware_smooth(&to_array(factor), max_change_per_bar)?.into_pyarray(py))
+}
+
+/// Momentum and mean reversion factors in one call
+///
+/// `skipna` applies to both factors as in `momentum_factor` and `mean_reversion_factor`.
+#[pyfunction]
+#[pyo3(name = "momentum_and_reversion", signature = (prices, lookback, invert=false, std_floor=0.0, ddof=0, skipna=true))]
+fn py_momentum_and_reversion<'py>(
+    py: Python<'py>,
+    prices: &PyArray1<f64>,
+    lookback: usize,
+    invert: bool,
+    std_floor: f64,
+    ddof: usize,
+    skipna: bool,
+) -> PyResult<(&'py PyArray1<f64>, &'py PyArray1<f64>)> {
+    let prices = to_array(prices);
+    let (mom, rev) = momentum_and_reversion(&prices, lookback, invert, std_floor, ddof)?;
+    let mom = apply_skipna(mom, skipna, &[&prices], lookback + 1);
+    let rev = apply_skipna(rev, skipna, &[&prices], lookback);
+    Ok((mom.into_pyarray(py), rev.into_pyarray(py)))
+}
+
+/// Alpha101 Factor #42 calculation
+///
+/// The three lookbacks default to the spec's 10 bars; a window longer than the series
+/// raises `InvalidPeriod`. `fill_value`, if given, replaces NaN/inf in the result.
+#[pyfunction]
+#[pyo3(signature = (high, volume, std_window=10, rank_window=10, corr_window=10, fill_value=None))]
+fn alpha101_factor_42<'py>(
+    py: Python<'py>,
+    high: &PyArray1<f64>,
+    volume: &PyArray1<f64>,
+    std_window: usize,
+    rank_window: usize,
+    corr_window: usize,
+    fill_value: Option<f64>,
+) -> PyResult<&'py PyArray1<f64>> {
+    let result = alpha101_42_with(&to_array(high), &to_array(volume), std_window, rank_window, corr_window)?;
+    Ok(fill_non_finite(result, fill_value).into_pyarray(py))
+}
+
+/// `alpha101_factor_42` down each column of `(time, assets)` high and volume panels
+///
+/// Columns are computed like the 1D version, in parallel with the GIL released (see
+/// `momentum_factor_2d`), so column `j` equals `alpha101_factor_42(high[:, j], volume[:, j])`.
+#[pyfunction]
+#[pyo3(signature = (high, volume, std_window=10, rank_window=10, corr_window=10, fill_value=None))]
+fn alpha101_factor_42_2d<'py>(
+    py: Python<'py>,
+    high: &PyArray2<f64>,
+    volume: &PyArray2<f64>,
+    std_window: usize,
+    rank_window: usize,
+    corr_window: usize,
+    fill_value: Option<f64>,
+) -> PyResult<&'py PyArray2<f64>> {
+    let (high, volume) = (to_array2(high), to_array2(volume));
+    let result = py.allow_threads(|| {
+        apply_column_pairs_par(&high, &volume, |h, v| alpha101_42_with(h, v, std_window, rank_window, corr_window))
+    })?;
+    Ok(fill_non_finite(result, fill_value).into_pyarray(py))
+}
+
+/// Alpha101 reference `ts_rank(x, d)`: average-tie rank of the current value over `d`
+///
+/// Outputs `rankdata(window)[-1] / d`, NaN if the window holds any NaN/inf. Unlike
+/// `ts_rank`, ties are averaged rather than ranked high, and NaNs are never skipped; use
+/// this one when porting Alpha101 formulas.
+#[pyfunction]
+#[pyo3(name = "alpha101_ts_rank")]
+fn py_alpha101_ts_rank<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(alpha101_ts_rank(&to_array(data), window)?.into_pyarray(py))
+}
+
+/// Several Alpha101 factors in one call, one column per requested id
+///
+/// Inputs are copied once and the factors computed in parallel with the GIL released.
+/// Unknown ids raise `InvalidParameter` (a `ValueError`) listing the supported set.
+#[pyfunction]
+#[pyo3(name = "compute_alphas")]
+fn py_compute_alphas<'py>(
+    py: Python<'py>,
+    open: &PyArray1<f64>,
+    high: &PyArray1<f64>,
+    low: &PyArray1<f64>,
+    close: &PyArray1<f64>,
+    volume: &PyArray1<f64>,
+    ids: Vec<u32>,
+) -> PyResult<&'py PyArray2<f64>> {
+    let (open, high, low, close, volume) = (to_array(open), to_array(high), to_array(low), to_array(close), to_array(volume));
+    let result = py.allow_threads(|| compute_alphas(&open, &high, &low, &close, &volume, &ids))?;
+    Ok(result.into_pyarray(py))
+}
+
+/// Percentage change over `periods`, with `zero_prev` ("nan", "inf" or "zero") for zero denominators
+#[pyfunction]
+#[pyo3(name = "pct_change", signature = (data, periods=1, zero_prev="nan"))]
+fn py_pct_change<'py>(py: Python<'py>, data: &PyArray1<f64>, periods: usize, zero_prev: &str) -> PyResult<&'py PyArray1<f64>> {
+    Ok(pct_change(&to_array(data), periods, zero_prev.parse()?)?.into_pyarray(py))
+}
+
+/// Log return `ln(x_t / x_{t-periods})`, NaN where either price is non-positive or NaN/inf
+#[pyfunction]
+#[pyo3(name = "log_return", signature = (prices, periods=1))]
+fn py_log_return<'py>(py: Python<'py>, prices: &PyArray1<f64>, periods: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(log_return(&to_array(prices), periods)?.into_pyarray(py))
+}
+
+/// Value `n` bars ago, NaN for the first `n` bars and at NaN/inf inputs
+#[pyfunction]
+fn ts_delay<'py>(py: Python<'py>, data: &PyArray1<f64>, n: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(delay(&to_array(data), n)?.into_pyarray(py))
+}
+
+/// Change over `n` bars, `x[i] - x[i - n]`, NaN for the first `n` bars and at NaN/inf inputs
+#[pyfunction]
+fn ts_delta<'py>(py: Python<'py>, data: &PyArray1<f64>, n: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(delta(&to_array(data), n)?.into_pyarray(py))
+}
+
+/// Running sum over the finite values; NaN/inf inputs give NaN without resetting the total
+#[pyfunction]
+#[pyo3(name = "cumsum")]
+fn py_cumsum<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    cumsum(&to_array(data)).into_pyarray(py)
+}
+
+/// Running product with the same skip-and-carry NaN policy as `cumsum`
+///
+/// `cumprod(1 + returns)` is the growth of one unit invested.
+#[pyfunction]
+#[pyo3(name = "cumprod")]
+fn py_cumprod<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    cumprod(&to_array(data)).into_pyarray(py)
+}
+
+/// Percentile of each value within all valid history so far (ties ranked high, like `ts_rank`)
+#[pyfunction]
+#[pyo3(name = "expanding_rank")]
+fn py_expanding_rank<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    expanding_rank(&to_array(data)).into_pyarray(py)
+}
+
+/// Mean of all valid values so far
+#[pyfunction]
+#[pyo3(name = "expanding_mean")]
+fn py_expanding_mean<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    expanding_mean(&to_array(data)).into_pyarray(py)
+}
+
+/// Std of all valid values so far; NaN while the count is at most `ddof`
+#[pyfunction]
+#[pyo3(name = "expanding_std", signature = (data, ddof=0))]
+fn py_expanding_std<'py>(py: Python<'py>, data: &PyArray1<f64>, ddof: usize) -> &'py PyArray1<f64> {
+    expanding_std(&to_array(data), ddof).into_pyarray(py)
+}
+
+/// Running maximum with the same skip-and-carry NaN policy as `cumsum`
+#[pyfunction]
+#[pyo3(name = "cummax")]
+fn py_cummax<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    cummax(&to_array(data)).into_pyarray(py)
+}
+
+/// Running minimum with the same skip-and-carry NaN policy as `cumsum`
+#[pyfunction]
+#[pyo3(name = "cummin")]
+fn py_cummin<'py>(py: Python<'py>, data: &PyArray1<f64>) -> &'py PyArray1<f64> {
+    cummin(&to_array(data)).into_pyarray(py)
+}
+
+/// Apply a Python callable to every full trailing window of `window` bars
+///
+/// `func` gets a fresh float64 copy of each window, NaN/inf included, and must return
+/// something convertible to float; leading partial windows are NaN. The GIL is held for
+/// the whole loop, with one Python call per bar, so prefer a native primitive where one
+/// exists. The first exception raised by `func` propagates unchanged.
+#[pyfunction]
+#[pyo3(name = "rolling_apply")]
+fn py_rolling_apply<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize, func: &PyAny) -> PyResult<&'py PyArray1<f64>> {
+    if window == 0 {
+        return Err(ExprError::InvalidPeriod("Window size must be positive".into()).into());
+    }
+    let values = to_array(data).to_vec();
+    let mut result = Array1::from_elem(values.len(), f64::NAN);
+    for end in window..=values.len() {
+        let slice = PyArray1::from_slice(py, &values[end - window..end]);
+        result[end - 1] = func.call1((slice,))?.extract()?;
+    }
+    Ok(result.into_pyarray(py))
+}
+
+/// Bars since the maximum of the last `window` valid values (0 = current bar, ties to the latest)
+///
+/// The window is that of `rolling_max`: NaN/inf inputs are skipped and give NaN, and the
+/// output is NaN until `window` valid values have been seen.
+#[pyfunction]
+fn ts_argmax<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(rolling_extreme_with_pos(&to_array(data), window, true)?.1.into_pyarray(py))
+}
+
+/// Bars since the minimum of the last `window` valid values; see `ts_argmax`
+#[pyfunction]
+fn ts_argmin<'py>(py: Python<'py>, data: &PyArray1<f64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    Ok(rolling_extreme_with_pos(&to_array(data), window, false)?.1.into_pyarray(py))
+}
+
+/// Rolling mean, the building block behind the packaged factors
+///
+/// `min_periods` (default `window`) counts valid observations. `step` and `compact` select
+/// block statistics (see `stride_windows`); `step=window` gives non-overlapping blocks.
+/// This applies to every `ts_*` function and the distribution wrappers.
+///
+/// `skipna=True` (the default) drops NaN/inf from each window; `skipna=False` returns NaN
+/// for any window containing one, so `min_periods` only affects the initial warm-up.
+///
+/// `ts_mean`, `ts_std`, `ts_rank`, `ts_corr` and the 2D variants accept float32 as well as
+/// float64 arrays and return the input dtype (float32 only if all inputs are); float32
+/// results are computed in f64 and rounded.
+///
+/// `center=True` (`ts_mean` and `ts_std` only) aligns each window on its middle bar like
+/// pandas, leaving NaN at both ends. It uses future bars, so it is for smoothing and
+/// research only, not live signals, and cannot be combined with `step`/`compact`.
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, step=1, compact=false, skipna=true, center=false))]
+#[allow(clippy::too_many_arguments)]
+fn ts_mean<'py>(
+    py: Python<'py>,
+    data: FloatArray1<'py>,
+    window: usize,
+    min_periods: Option<usize>,
+    step: usize,
+    compact: bool,
+    skipna: bool,
+    center: bool,
+) -> PyResult<PyObject> {
+    let (data, as_f32) = (data.to_f64(), data.is_f32());
+    let (values, _) = rolling_mean_partial(&data, window, min_periods.unwrap_or(window))?;
+    let values = apply_skipna(values, skipna, &[&data], window);
+    Ok(float_output(py, align_windows(values, window, step, compact, center)?, as_f32))
+}
+
+/// Centre or stride a trailing rolling output for the `ts_*` wrappers
+fn align_windows(values: Array1<f64>, window: usize, step: usize, compact: bool, center: bool) -> Result<Array1<f64>> {
+    if !center {
+        return stride_windows(values, window, step, compact);
+    }
+    if step != 1 || compact {
+        return Err(ExprError::InvalidParameter("center cannot be combined with step or compact".into()));
+    }
+    Ok(center_windows(&values, window))
+}
+
+/// Rolling std over the last `window` finite values, population unless `ddof = 1`
+///
+/// `min_periods` (default `window`) counts valid observations. Flat windows give 0.0;
+/// rounding-negative variances give 0.0 too unless `clamp_negative=False`, which makes
+/// them NaN.
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, ddof=0, clamp_negative=true, step=1, compact=false, skipna=true, center=false))]
+#[allow(clippy::too_many_arguments)]
+fn ts_std<'py>(
+    py: Python<'py>,
+    data: FloatArray1<'py>,
+    window: usize,
+    min_periods: Option<usize>,
+    ddof: usize,
+    clamp_negative: bool,
+    step: usize,
+    compact: bool,
+    skipna: bool,
+    center: bool,
+) -> PyResult<PyObject> {
+    let (data, as_f32, min_periods) = (data.to_f64(), data.is_f32(), min_periods.unwrap_or(window));
+    let (values, _) = rolling_std_partial_with(&data, window, min_periods, ddof, precision_mode(), clamp_negative)?;
+    let values = apply_skipna(values, skipna, &[&data], window);
+    Ok(float_output(py, align_windows(values, window, step, compact, center)?, as_f32))
+}
+
+/// Rolling percentile rank of each value within its window
+///
+/// `min_periods` (default `window`) counts buffered elements, since the window is positional.
+/// `method` ("max", "min", "average" or "first") ranks ties as in `rolling_rank`. For the Alpha101
+/// reference normalization use `alpha101_ts_rank`.
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, step=1, compact=false, skipna=true, method="max"))]
+#[allow(clippy::too_many_arguments)]
+fn ts_rank<'py>(
+    py: Python<'py>,
+    data: FloatArray1<'py>,
+    window: usize,
+    min_periods: Option<usize>,
+    step: usize,
+    compact: bool,
+    skipna: bool,
+    method: &str,
+) -> PyResult<PyObject> {
+    let (data, as_f32) = (data.to_f64(), data.is_f32());
+    let values = rolling_rank_with(&data, window, min_periods.unwrap_or(window), None, method.parse()?)?;
+    let values = apply_skipna(values, skipna, &[&data], window);
+    Ok(float_output(py, stride_windows(values, window, step, compact)?, as_f32))
+}
+
+/// `ts_mean` within each group of a long-format series sorted by group then time
+///
+/// `group_ids` is an integer array parallel to `values`; every group repeats the warm-up,
+/// so its first `window - 1` rows are NaN. Groups must be contiguous, otherwise
+/// `InvalidParameter` is raised; `groupby_rolling` accepts interleaved ids. The
+/// `grouped_rolling_*` family shares this layout.
+#[pyfunction]
+fn grouped_rolling_mean<'py>(py: Python<'py>, values: &PyArray1<f64>, group_ids: &PyArray1<i64>, window: usize) -> PyResult<&'py PyArray1<f64>> {
+    let ids = group_ids.readonly().as_array().to_owned();
+    Ok(group_apply(&to_array(values), &ids, true, |v| rolling_mean(v, window))?.into_pyarray(py))
+}
+
+/// `ts_std` within each group; see `grouped_rolling_mean`
+#[pyfunction]
+#[pyo3(signature = (values, group_ids, window, ddof=0))]
+fn grouped_rolling_std<'py>(
+    py: Python<'py>,
+    values: &PyArray1<f64>,
+    group_ids: &PyArray1<i64>,
+    window: usize,
+    ddof: usize,
+) -> PyResult<&'py PyArray1<f64>> {
+    let ids = group_ids.readonly().as_array().to_owned();
+    Ok(group_apply(&to_array(values), &ids, true, |v| rolling_std(v, window, ddof))?.into_pyarray(py))
+}
+
+/// `ts_rank` within each group; see `grouped_rolling_mean`
+#[pyfunction]
+#[pyo3(signature = (values, group_ids, window, method="max"))]
+fn grouped_rolling_rank<'py>(
+    py: Python<'py>,
+    values: &PyArray1<f64>,
+    group_ids: &PyArray1<i64>,
+    window: usize,
+    method: &str,
+) -> PyResult<&'py PyArray1<f64>> {
+    let (ids, method) = (group_ids.readonly().as_array().to_owned(), method.parse()?);
+    Ok(group_apply(&to_array(values), &ids, true, |v| rolling_rank_with(v, window, window, None, method))?.into_pyarray(py))
+}
+
+/// `ts_mean` down each column of a `(time, assets)` panel
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, skipna=true))]
+fn ts_mean_2d<'py>(py: Python<'py>, data: FloatArray2<'py>, window: usize, min_periods: Option<usize>, skipna: bool) -> PyResult<PyObject> {
+    let as_f32 = data.is_f32();
+    let (data, min_periods) = (data.to_f64(), min_periods.unwrap_or(window));
+    let result = py.allow_threads(|| {
+        apply_columns_par(&data, |c| Ok(apply_skipna(rolling_mean_partial(c, window, min_periods)?.0, skipna, &[c], window)))
+    })?;
+    Ok(float_output(py, result, as_f32))
+}
+
+/// `ts_std` down each column of a `(time, assets)` panel
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, ddof=0, clamp_negative=true, skipna=true))]
+fn ts_std_2d<'py>(
+    py: Python<'py>,
+    data: FloatArray2<'py>,
+    window: usize,
+    min_periods: Option<usize>,
+    ddof: usize,
+    clamp_negative: bool,
+    skipna: bool,
+) -> PyResult<PyObject> {
+    let as_f32 = data.is_f32();
+    let (data, min_periods, mode) = (data.to_f64(), min_periods.unwrap_or(window), precision_mode());
+    let result = py.allow_threads(|| {
+        apply_columns_par(&data, |c| {
+            let (values, _) = rolling_std_partial_with(c, window, min_periods, ddof, mode, clamp_negative)?;
+            Ok(apply_skipna(values, skipna, &[c], window))
+        })
+    })?;
+    Ok(float_output(py, result, as_f32))
+}
+
+/// `ts_rank` down each column of a `(time, assets)` panel
+#[pyfunction]
+#[pyo3(signature = (data, window, min_periods=None, skipna=true))]
+fn ts_rank_2d<'py>(py: Python<'py>, data: FloatArray2<'py>, window: usize, min_periods: Option<usize>, skipna: bool) -> PyResult<PyObject> {
+    let as_f32 = data.is_f32();
+    let (data, min_periods) = (data.to_f64(), min_periods.unwrap_or(window));
+    let result = py.allow_threads(|| {
+        apply_columns_par(&data, |c| Ok(apply_skipna(rolling_rank_partial(c, window, min_periods, None)?, skipna, &[c], window)))
+    })?;
+    Ok(float_output(py, result, as_f32))
+}
+
+/// Rolling Pearson correlation over the last `window` jointly finite pairs
+///
+/// `min_periods` (default `window`) counts valid pairs. With `skipna=False` a NaN/inf in
+/// either series blanks every window containing it.
+#[pyfunction]
+#[pyo3(signature = (x, y, window, min_periods=None, step=1, compact=false, skipna=true))]
+#[allow(clippy::too_many_arguments)]
+fn ts_corr<'py>(
+    py: Python<'py>,
+    x: FloatArray1<'py>,
+    y: FloatArray1<'py>,
+    window: usize,
+    min_periods: Option<usize>,
+    step: usize,
+    compact: bool,
+    skipna: bool,
+) -> PyResult<PyObject> {
+    let as_f32 = x.is_f32() && y.is_f32();
+    let (x, y) = (x.to_f64(), y.to_f64());
+    check_same_length("x", &x, "y", &y)?;
+    let min_periods = min_periods.unwrap_or(window);
+    let values = rolling_correlation_partial_with(&x, &y, window, min_periods, precision_mode())?;
+    let values = apply_skipna(values, skipna, &[&x, &y], window);
+    Ok(float_output(py, stride_windows(values, window, step, compact)?, as_f32))
+}
+
+/// Evaluate a factor formula such as `"rank(ts_std(high, 10)) * correlation(high, volume, 10)"`
+///
+/// Identifiers refer to keys of `inputs`; `+ - * /`, unary minus, parentheses and numeric
+/// constants are supported. Functions: `ts_rank`/`mean`/`std`/`delay`/`delta(x, n)` (with
+/// `ts_` aliases) and `correlation(x, y, n)`, all over trailing windows, and `rank(x)`.
+/// Each input is one series, so `rank(x)` is the expanding percentile rank of each bar
+/// against the history up to it (`expanding_rank`), never a cross-sectional rank, and does
+/// not look ahead. Windows must be integer constants. Division by
+/// zero gives NaN. Unknown names, arity mismatches and syntax errors raise `ValueError`.
+#[pyfunction]
+#[pyo3(name = "eval_expr")]
+fn py_eval_expr<'py>(py: Python<'py>, formula: &str, inputs: HashMap<String, &PyArray1<f64>>) -> PyResult<&'py PyArray1<f64>> {
+    let inputs: HashMap<String, Array1<f64>> = inputs.into_iter().map(|(name, x)| (name, to_array(x))).collect();
+    Ok(eval_expr_arrays(formula, &inputs)?.into_pyarray(py))
+}
+
+/// Python module
+#[pymodule]
+fn rust_expr(py: Python, m: &PyModule) -> PyResult<()> {
+    /// Momentum factor calculation
+    ///
+    /// Positive when recent returns are up relative to their volatility; `invert` flips the sign.
+    /// `ddof=1` scales by the sample rather than the population volatility. The return
+    /// volatility skips non-finite returns; `skipna=False` instead gives NaN wherever the
+    /// `lookback + 1` prices behind a value contain a NaN or inf.
+    ///
+    /// `fill_value` is separate from `skipna`: it only replaces NaN/inf in the returned
+    /// array, after the factor is computed. The same holds for every factor function below.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, invert=false, ddof=0, skipna=true, fill_value=None))]
+    fn momentum_factor<'py>(
+        py: Python<'py>,
+        prices: &PyArray1<f64>,
+        lookback: usize,
+        invert: bool,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<&'py PyArray1<f64>> {
+        let prices = prices.readonly();
+        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
+        let result = momentum(&prices_arr, lookback, invert, ddof)?;
+        Ok(fill_non_finite(apply_skipna(result, skipna, &[&prices_arr], lookback + 1), fill_value).into_pyarray(py))
+    }
+
+    /// Mean reversion factor calculation
+    ///
+    /// Negated z-score, so it is positive when price sits below its moving average; `invert`
+    /// returns the raw z-score instead. The std is clamped to at least `std_floor`; `ddof=1`
+    /// uses the sample std. `skipna=False` gives NaN wherever the `lookback` window holds a
+    /// NaN or inf, rather than averaging over the finite prices.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, invert=false, std_floor=0.0, ddof=0, skipna=true, fill_value=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn mean_reversion_factor<'py>(
+        py: Python<'py>,
+        prices: &PyArray1<f64>,
+        lookback: usize,
+        invert: bool,
+        std_floor: f64,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<&'py PyArray1<f64>> {
+        let prices = prices.readonly();
+        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
+        let result = mean_reversion(&prices_arr, lookback, invert, std_floor, ddof)?;
+        Ok(fill_non_finite(apply_skipna(result, skipna, &[&prices_arr], lookback), fill_value).into_pyarray(py))
+    }
+
+    /// `momentum_factor` written into the preallocated float64 array `out`, returning None
+    ///
+    /// `out` must have the length of `prices` and may be reused across calls (or be
+    /// `prices` itself). Arguments and results otherwise match `momentum_factor`.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, out, invert=false, ddof=0, skipna=true, fill_value=None))]
+    fn momentum_factor_into(
+        prices: &PyArray1<f64>,
+        lookback: usize,
+        out: &PyArray1<f64>,
+        invert: bool,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<()> {
+        let prices = to_array(prices);
+        let result = apply_skipna(momentum(&prices, lookback, invert, ddof)?, skipna, &[&prices], lookback + 1);
+        write_into(out, &fill_non_finite(result, fill_value))
+    }
+
+    /// `mean_reversion_factor` written into the preallocated float64 array `out`; see `momentum_factor_into`
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, out, invert=false, std_floor=0.0, ddof=0, skipna=true, fill_value=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn mean_reversion_factor_into(
+        prices: &PyArray1<f64>,
+        lookback: usize,
+        out: &PyArray1<f64>,
+        invert: bool,
+        std_floor: f64,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<()> {
+        let prices = to_array(prices);
+        let result = mean_reversion(&prices, lookback, invert, std_floor, ddof)?;
+        write_into(out, &fill_non_finite(apply_skipna(result, skipna, &[&prices], lookback), fill_value))
+    }
+
+    /// `momentum_factor` down each column of a `(time, assets)` price panel
+    ///
+    /// With the `parallel` feature (on by default) columns are spread across threads with
+    /// the GIL released; results are bit-identical to the serial path.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, invert=false, ddof=0, skipna=true, fill_value=None))]
+    fn momentum_factor_2d<'py>(
+        py: Python<'py>,
+        prices: &PyArray2<f64>,
+        lookback: usize,
+        invert: bool,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<&'py PyArray2<f64>> {
+        let prices = to_array2(prices);
+        let result = py.allow_threads(|| {
+            apply_columns_par(&prices, |c| Ok(apply_skipna(momentum(c, lookback, invert, ddof)?, skipna, &[c], lookback + 1)))
+        })?;
+        Ok(fill_non_finite(result, fill_value).into_pyarray(py))
+    }
+
+    /// `mean_reversion_factor` down each column of a `(time, assets)` price panel
+    ///
+    /// Parallel across columns like `momentum_factor_2d`.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, invert=false, std_floor=0.0, ddof=0, skipna=true, fill_value=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn mean_reversion_factor_2d<'py>(
+        py: Python<'py>,
+        prices: &PyArray2<f64>,
+        lookback: usize,
+        invert: bool,
+        std_floor: f64,
+        ddof: usize,
+        skipna: bool,
+        fill_value: Option<f64>,
+    ) -> PyResult<&'py PyArray2<f64>> {
+        let prices = to_array2(prices);
+        let result = py.allow_threads(|| {
+            apply_columns_par(&prices, |c| {
+                let values = mean_reversion(c, lookback, invert, std_floor, ddof)?;
+                Ok(apply_skipna(values, skipna, &[c], lookback))
+            })
+        })?;
+        Ok(fill_non_finite(result, fill_value).into_pyarray(py))
+    }
+
+    /// Relative strength factor calculation
+    ///
+    /// Weighted sum of momentum percentile ranks, so higher means stronger relative momentum;
+    /// `invert` flips the sign. `nan_policy` controls how missing timeframe ranks are combined
+    /// (see `NanPolicy`); the default "zero" biases the warm-up region towards zero. With
+    /// `return_components` the result is `(factor, ranks)`, where `ranks` has one column per
+    /// timeframe (`max(lookback / 3, 1)`, `lookback`, `2 * lookback`) before weighting.
+    /// `lookback` must be at least 2.
+    ///
+    /// `timeframes` and `weights` override the default horizons and their `[0.5, 0.3, 0.2]`
+    /// blend; either may be given alone if it matches the other's default length of 3. The
+    /// weights are normalized to sum to 1. `fill_value` applies to the factor only, not to the
+    /// component ranks.
+    #[pyfunction]
+    #[pyo3(signature = (prices, lookback, invert=false, nan_policy="zero", return_components=false, timeframes=None, weights=None, fill_value=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn relative_strength_factor(
+        py: Python<'_>,
+        prices: &PyArray1<f64>,
+        lookback: usize,
+        invert: bool,
+        nan_policy: &str,
+        return_components: bool,
+        timeframes: Option<Vec<usize>>,
+        weights: Option<Vec<f64>>,
+        fill_value: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let prices = prices.readonly();
+        let prices_arr = Array1::from_vec(prices.as_array().to_vec());
+        let nan_policy = nan_policy.parse()?;
+        let (result, ranks) = if timeframes.is_none() && weights.is_none() {
+            relative_strength(&prices_arr, lookback, invert, nan_policy)?
+        } else {
+            let timeframes = timeframes.unwrap_or_else(|| vec![(lookback / 3).max(1), lookback, lookback * 2]);
+            let weights = weights.unwrap_or_else(|| vec![0.5, 0.3, 0.2]);
+            relative_strength_with(&prices_arr, lookback, &timeframes, &weights, invert, nan_policy)?
+        };
+        let result = fill_non_finite(result, fill_value);
+
+        if return_components {
+            Ok((result.into_pyarray(py), ranks.into_pyarray(py)).into_py(py))
+        } else {
+            Ok(result.into_pyarray(py).into_py(py))
+        }
+    }
+
+    // Exceptions
+    m.add("ExprError", py.get_type::<exceptions::ExprError>())?;
+    m.add("InvalidPeriod", py.get_type::<exceptions::InvalidPeriod>())?;
+    m.add("ComputationError", py.get_type::<exceptions::ComputationError>())?;
+    m.add("InvalidParameter", py.get_type::<exceptions::InvalidParameter>())?;
+    m.add("LengthMismatch", py.get_type::<exceptions::LengthMismatch>())?;
+    m.add("ParseError", py.get_type::<exceptions::ParseError>())?;
+
+    // Add functions to the module
+    m.add_function(wrap_pyfunction!(momentum_factor, m)?)?;
+    m.add_function(wrap_pyfunction!(mean_reversion_factor, m)?)?;
+    m.add_function(wrap_pyfunction!(momentum_factor_into, m)?)?;
+    m.add_function(wrap_pyfunction!(mean_reversion_factor_into, m)?)?;
+    m.add_function(wrap_pyfunction!(momentum_factor_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(mean_reversion_factor_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(py_momentum_and_reversion, m)?)?;
+    m.add_function(wrap_pyfunction!(relative_strength_factor, m)?)?;
+
+    // Alpha101
+    m.add_function(wrap_pyfunction!(alpha101_factor_42, m)?)?;
+    m.add_function(wrap_pyfunction!(alpha101_factor_42_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(py_compute_alphas, m)?)?;
+    m.add_function(wrap_pyfunction!(py_alpha101_ts_rank, m)?)?;
+
+    // Time-series primitives
+    m.add_function(wrap_pyfunction!(py_rolling_sum, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_var, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_prod, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_geomean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_pct_change, m)?)?;
+    m.add_function(wrap_pyfunction!(py_log_return, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_delay, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_delta, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_argmax, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_argmin, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cumsum, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cumprod, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cummax, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cummin, m)?)?;
+    m.add_function(wrap_pyfunction!(py_expanding_rank, m)?)?;
+    m.add_function(wrap_pyfunction!(py_expanding_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_expanding_std, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_apply, m)?)?;
+
+    // Expressions
+    m.add_function(wrap_pyfunction!(py_eval_expr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_where, m)?)?;
+    m.add_function(wrap_pyfunction!(py_gt, m)?)?;
+    m.add_function(wrap_pyfunction!(py_lt, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ge, m)?)?;
+    m.add_function(wrap_pyfunction!(py_le, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_std, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_rank, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_mean_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_std_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(ts_rank_2d, m)?)?;
+    m.add_function(wrap_pyfunction!(grouped_rolling_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(grouped_rolling_std, m)?)?;
+    m.add_function(wrap_pyfunction!(grouped_rolling_rank, m)?)?;
+
+    // Partial windows
+    m.add_function(wrap_pyfunction!(py_rolling_mean_partial, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_mean_with_count, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_std_partial, m)?)?;
+    m.add_function(wrap_pyfunction!(py_fractional_window_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_mean_time, m)?)?;
+
+    // Utilities
+    m.add_function(wrap_pyfunction!(set_precision_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(get_precision_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(set_zero_tolerance, m)?)?;
+    m.add_function(wrap_pyfunction!(get_zero_tolerance, m)?)?;
+    m.add_function(wrap_pyfunction!(py_round_window, m)?)?;
+    m.add_function(wrap_pyfunction!(py_drop_warmup, m)?)?;
+    m.add_function(wrap_pyfunction!(py_warmup_fill, m)?)?;
+    m.add_function(wrap_pyfunction!(py_forward_fill, m)?)?;
+    m.add_function(wrap_pyfunction!(py_backward_fill, m)?)?;
+    m.add_function(wrap_pyfunction!(py_window_at, m)?)?;
+    m.add_function(wrap_pyfunction!(py_nan_report, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_count, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_nan_ratio, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_robust_clean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_hampel_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cost_aware_smooth, m)?)?;
+
+    // Normalization
+    m.add_function(wrap_pyfunction!(py_hybrid_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_rank, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ew_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_ess, m)?)?;
+    m.add_function(wrap_pyfunction!(py_discounted_sum, m)?)?;
+
+    // Distribution
+    m.add_function(wrap_pyfunction!(py_rolling_jarque_bera, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_skew, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_kurt, m)?)?;
+    m.add_function(wrap_pyfunction!(py_adf_test, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_mean_abs_dev, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_iqr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_mad, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_entropy, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_quantile, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_median, m)?)?;
+
+    // Regression
+    m.add_function(wrap_pyfunction!(py_rolling_beta, m)?)?;
+    m.add_function(wrap_pyfunction!(py_panel_beta, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_cov, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_spearman, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_autocorr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_robust_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_partial_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_predictive_r2, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_beta_stability, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_residualize, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_corr_pvalue, m)?)?;
+    m.add_function(wrap_pyfunction!(py_factor_decay_flag, m)?)?;
+
+    // Pairs
+    m.add_function(wrap_pyfunction!(py_pair_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_lead_lag, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_logret_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ewm_correlation, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_coint_stat, m)?)?;
+
+    // Regimes
+    m.add_function(wrap_pyfunction!(py_regime_rolling_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_nunique, m)?)?;
+    m.add_function(wrap_pyfunction!(py_regime_label, m)?)?;
+    m.add_function(wrap_pyfunction!(py_groupby_rolling, m)?)?;
+
+    // Risk
+    m.add_function(wrap_pyfunction!(py_rolling_drawdown_duration, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_current_drawdown, m)?)?;
+    m.add_function(wrap_pyfunction!(py_max_drawdown, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_risk_adjusted, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_sharpe, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_downside_dev, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_sortino, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_vol_of_vol, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_kelly, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_snr, m)?)?;
+
+    // Trend
+    m.add_function(wrap_pyfunction!(py_ema, m)?)?;
+    m.add_function(wrap_pyfunction!(py_dema, m)?)?;
+    m.add_function(wrap_pyfunction!(py_tema, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ewm_var, m)?)?;
+    m.add_function(wrap_pyfunction!(py_ewm_std, m)?)?;
+    m.add_function(wrap_pyfunction!(py_decay_linear, m)?)?;
+    m.add_function(wrap_pyfunction!(py_weighted_rolling_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_slope, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_r2, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_up_fraction, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_zero_crossings, m)?)?;
+    m.add_function(wrap_pyfunction!(py_percentile_breakout, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_max_with_pos, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_min, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_max, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_minmax, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_range, m)?)?;
+    m.add_function(wrap_pyfunction!(py_stochastic, m)?)?;
+    m.add_function(wrap_pyfunction!(py_bollinger_percent_b, m)?)?;
+    m.add_function(wrap_pyfunction!(py_bollinger_bandwidth, m)?)?;
+
+    // Volume
+    m.add_function(wrap_pyfunction!(py_rolling_volume_delta, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_vwap, m)?)?;
+
+    // Cross-section
+    m.add_function(wrap_pyfunction!(py_cs_dispersion, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_pc1_share, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rolling_pairwise_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(py_weighted_cs_rank_ic, m)?)?;
+    m.add_function(wrap_pyfunction!(py_rank_ic, m)?)?;
+    m.add_function(wrap_pyfunction!(py_shuffle_rows, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cs_rank, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cs_bucket, m)?)?;
+    m.add_function(wrap_pyfunction!(py_scale, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cs_demean, m)?)?;
+    m.add_function(wrap_pyfunction!(py_cs_normalize, m)?)?;
+    m.add_function(wrap_pyfunction!(py_signed_power, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sign, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sign_change, m)?)?;
+    m.add_function(wrap_pyfunction!(py_clip, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sigmoid, m)?)?;
+    m.add_function(wrap_pyfunction!(py_tanh_squash, m)?)?;
+    m.add_function(wrap_pyfunction!(py_winsorize, m)?)?;
+    m.add_function(wrap_pyfunction!(py_winsorize_mad, m)?)?;
+    m.add_function(wrap_pyfunction!(py_neutralize, m)?)?;
+
+    // Streaming
+    m.add_class::<RollingRank>()?;
+    m.add_class::<Factor>()?;
+    m.add_class::<RollingMean>()?;
+    m.add_class::<RollingStd>()?;
+    m.add_class::<RollingMeanChunks>()?;
+    m.add_class::<StreamingCorrMatrix>()?;
+    m.add_class::<StreamingQuantile>()?;
+    m.add_function(wrap_pyfunction!(rolling_mean_chunks, m)?)?;
+
+    // Polars
+    #[cfg(feature = "polars")]
+    polars::register(py, m)?;
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Polars `Series` entry points for the main rolling kernels (`polars` feature)
+//!
+//! Registered as the `rust_expr.polars` submodule. Each function takes Float64 `pl.Series`
+//! in place of the numpy arrays of its namesake in the parent module and returns a
+//! Float64 series carrying the first input's name. A single-chunk series without nulls is
+//! read straight from its Arrow buffer; chunked series are gathered once, with nulls
+//! becoming NaN so they are skipped like any other missing value. Other dtypes raise
+//! `TypeError` rather than being cast.
+
+use polars_core::prelude::{DataType, Float64Chunked, IntoSeries, Series};
+use pyo3::exceptions::PyTypeError;
+use pyo3_polars::PySeries;
+
+use super::*;
+
+/// Copy the values of a Float64 series into the kernels' input array
+fn series_to_array(series: &Series) -> PyResult<Array1<f64>> {
+    if series.dtype() != &DataType::Float64 {
+        return Err(PyTypeError::new_err(format!(
+            "expected a Float64 Series, got {} for '{}'", series.dtype(), series.name()
+        )));
+    }
+    let values = series.f64().map_err(|e| PyTypeError::new_err(e.to_string()))?;
+    Ok(match values.cont_slice() {
+        Ok(buffer) => Array1::from(buffer.to_vec()),
+        Err(_) => values.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect(),
+    })
+}
+
+fn to_series(name: &str, values: Array1<f64>) -> PySeries {
+    PySeries(Float64Chunked::from_vec(name, values.into_raw_vec()).into_series())
+}
+
+/// Rolling mean over the last `window` finite values, as `ts_mean`
+#[pyfunction]
+#[pyo3(name = "ts_mean", signature = (data, window, min_periods=None))]
+fn series_ts_mean(data: PySeries, window: usize, min_periods: Option<usize>) -> PyResult<PySeries> {
+    let x = series_to_array(&data.0)?;
+    let (values, _) = rolling_mean_partial(&x, window, min_periods.unwrap_or(window))?;
+    Ok(to_series(data.0.name(), values))
+}
+
+/// Rolling std over the last `window` finite values, as `ts_std`
+#[pyfunction]
+#[pyo3(name = "ts_std", signature = (data, window, min_periods=None, ddof=0))]
+fn series_ts_std(data: PySeries, window: usize, min_periods: Option<usize>, ddof: usize) -> PyResult<PySeries> {
+    let x = series_to_array(&data.0)?;
+    let (values, _) = rolling_std_partial_with(&x, window, min_periods.unwrap_or(window), ddof, precision_mode(), true)?;
+    Ok(to_series(data.0.name(), values))
+}
+
+/// Rolling percentile rank of each value within its window, as `ts_rank`
+#[pyfunction]
+#[pyo3(name = "ts_rank", signature = (data, window, min_periods=None, method="max"))]
+fn series_ts_rank(data: PySeries, window: usize, min_periods: Option<usize>, method: &str) -> PyResult<PySeries> {
+    let x = series_to_array(&data.0)?;
+    let values = rolling_rank_with(&x, window, min_periods.unwrap_or(window), None, method.parse()?)?;
+    Ok(to_series(data.0.name(), values))
+}
+
+/// Rolling Pearson correlation over the last `window` jointly finite pairs, as `ts_corr`
+#[pyfunction]
+#[pyo3(name = "ts_corr", signature = (x, y, window, min_periods=None))]
+fn series_ts_corr(x: PySeries, y: PySeries, window: usize, min_periods: Option<usize>) -> PyResult<PySeries> {
+    let (a, b) = (series_to_array(&x.0)?, series_to_array(&y.0)?);
+    let values = rolling_correlation_partial_with(&a, &b, window, min_periods.unwrap_or(window), precision_mode())?;
+    Ok(to_series(x.0.name(), values))
+}
+
+/// Rolling sum over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_sum")]
+fn series_rolling_sum(data: PySeries, window: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_sum(&series_to_array(&data.0)?, window)?))
+}
+
+/// Rolling variance, `ts_std` before the square root
+#[pyfunction]
+#[pyo3(name = "rolling_var", signature = (data, window, ddof=0))]
+fn series_rolling_var(data: PySeries, window: usize, ddof: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_var(&series_to_array(&data.0)?, window, ddof)?))
+}
+
+/// Rolling z-score `(x - ts_mean) / ts_std`
+#[pyfunction]
+#[pyo3(name = "rolling_zscore", signature = (data, window, ddof=0, std_floor=0.0))]
+fn series_rolling_zscore(data: PySeries, window: usize, ddof: usize, std_floor: f64) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_zscore(&series_to_array(&data.0)?, window, ddof, std_floor)?))
+}
+
+/// Rolling minimum over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_min")]
+fn series_rolling_min(data: PySeries, window: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_min(&series_to_array(&data.0)?, window)?))
+}
+
+/// Rolling maximum over the last `window` valid values
+#[pyfunction]
+#[pyo3(name = "rolling_max")]
+fn series_rolling_max(data: PySeries, window: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_max(&series_to_array(&data.0)?, window)?))
+}
+
+/// Rolling median
+#[pyfunction]
+#[pyo3(name = "rolling_median")]
+fn series_rolling_median(data: PySeries, window: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_median(&series_to_array(&data.0)?, window)?))
+}
+
+/// Rolling quantile with numpy-style linear interpolation
+#[pyfunction]
+#[pyo3(name = "rolling_quantile")]
+fn series_rolling_quantile(data: PySeries, window: usize, q: f64) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_quantile(&series_to_array(&data.0)?, window, q)?))
+}
+
+/// Rolling sample skewness
+#[pyfunction]
+#[pyo3(name = "rolling_skew")]
+fn series_rolling_skew(data: PySeries, window: usize) -> PyResult<PySeries> {
+    Ok(to_series(data.0.name(), rolling_skew(&series_to_array(&data.0)?, window)?))
+}
+
+/// Exponential moving average from one of `span`, `alpha` or `halflife`, as `ema`
+#[pyfunction]
+#[pyo3(name = "ema", signature = (data, span=None, alpha=None, adjust=false, halflife=None))]
+fn series_ema(data: PySeries, span: Option<f64>, alpha: Option<f64>, adjust: bool, halflife: Option<f64>) -> PyResult<PySeries> {
+    let alpha = resolve_alpha(span, alpha, halflife)?;
+    Ok(to_series(data.0.name(), ema(&series_to_array(&data.0)?, alpha, adjust)?))
+}
+
+/// Momentum factor, as `momentum_factor` with `skipna=True`
+#[pyfunction]
+#[pyo3(name = "momentum_factor", signature = (prices, lookback, invert=false, ddof=0))]
+fn series_momentum_factor(prices: PySeries, lookback: usize, invert: bool, ddof: usize) -> PyResult<PySeries> {
+    Ok(to_series(prices.0.name(), momentum(&series_to_array(&prices.0)?, lookback, invert, ddof)?))
+}
+
+/// Mean reversion factor, as `mean_reversion_factor` with `skipna=True`
+#[pyfunction]
+#[pyo3(name = "mean_reversion_factor", signature = (prices, lookback, invert=false, std_floor=0.0, ddof=0))]
+fn series_mean_reversion_factor(prices: PySeries, lookback: usize, invert: bool, std_floor: f64, ddof: usize) -> PyResult<PySeries> {
+    let values = mean_reversion(&series_to_array(&prices.0)?, lookback, invert, std_floor, ddof)?;
+    Ok(to_series(prices.0.name(), values))
+}
+
+/// Add the `polars` submodule to `parent`
+pub(super) fn register(py: Python, parent: &PyModule) -> PyResult<()> {
+    let m = PyModule::new(py, "polars")?;
+    m.add_function(wrap_pyfunction!(series_ts_mean, m)?)?;
+    m.add_function(wrap_pyfunction!(series_ts_std, m)?)?;
+    m.add_function(wrap_pyfunction!(series_ts_rank, m)?)?;
+    m.add_function(wrap_pyfunction!(series_ts_corr, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_sum, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_var, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_zscore, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_min, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_max, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_median, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_quantile, m)?)?;
+    m.add_function(wrap_pyfunction!(series_rolling_skew, m)?)?;
+    m.add_function(wrap_pyfunction!(series_ema, m)?)?;
+    m.add_function(wrap_pyfunction!(series_momentum_factor, m)?)?;
+    m.add_function(wrap_pyfunction!(series_mean_reversion_factor, m)?)?;
+    parent.add_submodule(m)
+}
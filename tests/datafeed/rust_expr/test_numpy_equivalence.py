@@ -0,0 +1,966 @@
+import sys
+
+import pytest
+import numpy as np
+from numpy.testing import assert_allclose
+
+rust_expr = pytest.importorskip("datafeed.rust_expr")
+
+TINY = np.finfo(np.float64).tiny
+EPS = np.finfo(np.float64).eps
+
+def near_zero(denom, scale):
+    """Default module tolerance: |denom| <= max(eps * |scale|, eps)."""
+    scale = np.where(np.isfinite(scale), np.abs(scale), 0.0)
+    return np.abs(denom) <= np.maximum(EPS * scale, EPS)
+
+# ---------------------------------------------------------------------------
+# Pure-numpy reference implementations of the Rust kernels
+# ---------------------------------------------------------------------------
+
+def ref_pct_change(x, periods):
+    """Percentage change; subnormal and near-zero denominators give NaN."""
+    out = np.full(len(x), np.nan)
+    for i in range(periods, len(x)):
+        prev, curr = x[i - periods], x[i]
+        if np.isfinite(prev) and abs(prev) >= TINY and np.isfinite(curr) and not near_zero(prev, curr):
+            out[i] = (curr - prev) / prev
+    return out
+
+def ref_rolling_mean(x, window):
+    """Mean over the last `window` finite values; NaN at non-finite inputs."""
+    out = np.full(len(x), np.nan)
+    valid = []
+    for i, val in enumerate(x):
+        if not np.isfinite(val):
+            continue
+        valid.append(val)
+        if len(valid) >= window:
+            out[i] = np.mean(valid[-window:])
+    return out
+
+def ref_rolling_std(x, window):
+    """Population std over the last `window` finite values; 0.0 for flat windows."""
+    out = np.full(len(x), np.nan)
+    valid = []
+    for i, val in enumerate(x):
+        if not np.isfinite(val):
+            continue
+        valid.append(val)
+        if len(valid) >= window:
+            win = np.array(valid[-window:])
+            out[i] = 0.0 if np.all(win == win[0]) else np.sqrt(max(np.var(win), 0.0))
+    return out
+
+def ref_rolling_rank(x, window):
+    """Share of finite values in the trailing window that are <= the current value."""
+    out = np.full(len(x), np.nan)
+    for i in range(window - 1, len(x)):
+        win = x[i - window + 1:i + 1]
+        win = win[np.isfinite(win)]
+        if len(win) and np.isfinite(x[i]):
+            out[i] = np.sum(win <= x[i]) / len(win)
+    return out
+
+def _paired_windows(x, y, window):
+    """Yield (index, x_window, y_window) over the last `window` finite pairs."""
+    xs, ys = [], []
+    for i in range(len(x)):
+        if not (np.isfinite(x[i]) and np.isfinite(y[i])):
+            continue
+        xs.append(x[i])
+        ys.append(y[i])
+        if len(xs) >= window:
+            yield i, np.array(xs[-window:]), np.array(ys[-window:])
+
+def ref_rolling_correlation(x, y, window):
+    out = np.full(len(x), np.nan)
+    for i, wx, wy in _paired_windows(x, y, window):
+        var_x, var_y = np.var(wx), np.var(wy)
+        if var_x > 0 and var_y > 0:
+            cov = np.mean(wx * wy) - wx.mean() * wy.mean()
+            out[i] = cov / np.sqrt(var_x * var_y)
+    return out
+
+def ref_rolling_beta(x, y, window):
+    out = np.full(len(x), np.nan)
+    for i, wx, wy in _paired_windows(x, y, window):
+        var_x = np.var(wx)
+        if var_x > 0:
+            out[i] = (np.mean(wx * wy) - wx.mean() * wy.mean()) / var_x
+    return out
+
+def ref_rolling_zscore(x, window):
+    mean, std = ref_rolling_mean(x, window), ref_rolling_std(x, window)
+    with np.errstate(invalid="ignore", divide="ignore"):
+        z = (x - mean) / std
+    z[~(np.isfinite(x) & np.isfinite(mean) & np.isfinite(std)) | near_zero(std, mean)] = np.nan
+    return z
+
+def ref_ew_zscore(x, alpha):
+    out = np.full(len(x), np.nan)
+    mean, var = np.nan, 0.0
+    for i, val in enumerate(x):
+        if not np.isfinite(val):
+            continue
+        if np.isnan(mean):
+            mean = val
+        else:
+            diff = val - mean
+            mean += alpha * diff
+            var = (1 - alpha) * (var + alpha * diff * diff)
+        if var > 0 and not near_zero(np.sqrt(var), mean):
+            out[i] = (val - mean) / np.sqrt(var)
+    return out
+
+def mask_finite(x):
+    x = np.array(x, dtype=np.float64)
+    x[~np.isfinite(x)] = np.nan
+    return x
+
+def ref_momentum_factor(prices, lookback):
+    returns = ref_pct_change(prices, 1)
+    momentum = ref_pct_change(prices, lookback)
+    vol = ref_rolling_std(returns, lookback)
+    with np.errstate(invalid="ignore", divide="ignore"):
+        out = momentum / vol
+    out[near_zero(vol, momentum)] = np.nan
+    return mask_finite(out)
+
+def ref_mean_reversion_factor(prices, lookback):
+    mean = mask_finite(ref_rolling_mean(prices, lookback))
+    std = ref_rolling_std(prices, lookback)
+    with np.errstate(invalid="ignore", divide="ignore"):
+        out = -(prices - mean) / std
+    out[near_zero(std, mean)] = np.nan
+    return mask_finite(out)
+
+def ref_relative_strength_factor(prices, lookback):
+    out = np.zeros(len(prices))
+    for tf, weight in zip([max(lookback // 3, 1), lookback, lookback * 2], [0.5, 0.3, 0.2]):
+        rank = ref_rolling_rank(ref_pct_change(prices, tf), lookback)
+        out += np.where(np.isfinite(rank), rank * weight, 0.0)
+    return out
+
+def ref_alpha101_factor_42(high, volume, std_window=10, rank_window=10, corr_window=10):
+    rank = mask_finite(ref_rolling_rank(ref_rolling_std(high, std_window), rank_window))
+    corr = mask_finite(ref_rolling_correlation(high, volume, corr_window))
+    return -rank * corr
+
+# ---------------------------------------------------------------------------
+# Input battery
+# ---------------------------------------------------------------------------
+
+def _price_path(rng, n):
+    return 100.0 * np.exp(np.cumsum(rng.normal(0.0, 0.02, n)))
+
+def _battery():
+    rng = np.random.default_rng(42)
+    gappy = _price_path(rng, 120)
+    gappy[[5, 40, 41, 90]] = np.nan
+    return {
+        "empty": np.array([], dtype=np.float64),
+        "single": np.array([101.0]),
+        "all_nan": np.full(30, np.nan),
+        # Exactly representable so the running sums cancel without rounding
+        "constant": np.full(40, 2.0),
+        "short": _price_path(rng, 8),
+        "random_walk": _price_path(rng, 250),
+        "gappy": gappy,
+        "with_inf": np.where(np.arange(60) == 30, np.inf, _price_path(rng, 60)),
+    }
+
+BATTERY = _battery()
+CASES = sorted(BATTERY)
+
+def assert_equivalent(actual, expected):
+    """Values agree within tolerance and NaN masks match exactly."""
+    actual = np.asarray(actual, dtype=np.float64)
+    expected = np.asarray(expected, dtype=np.float64)
+    assert actual.shape == expected.shape
+    np.testing.assert_array_equal(np.isnan(actual), np.isnan(expected))
+    assert_allclose(actual, expected, rtol=1e-6, atol=1e-9, equal_nan=True)
+
+# ---------------------------------------------------------------------------
+# Equivalence tests
+# ---------------------------------------------------------------------------
+
+def test_kernels_do_not_need_numba():
+    """The compiled kernels are the fast path; nothing should pull in numba."""
+    assert "numba" not in sys.modules
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("lookback", [2, 5, 20, 500])
+def test_momentum_factor(case, lookback):
+    prices = BATTERY[case]
+    if lookback > len(prices):
+        with pytest.raises(rust_expr.InvalidPeriod, match="exceeds series length"):
+            rust_expr.momentum_factor(prices, lookback)
+        return
+    assert_equivalent(rust_expr.momentum_factor(prices, lookback), ref_momentum_factor(prices, lookback))
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("lookback", [2, 5, 20, 500])
+def test_mean_reversion_factor(case, lookback):
+    prices = BATTERY[case]
+    if lookback > len(prices):
+        with pytest.raises(rust_expr.InvalidPeriod, match="exceeds series length"):
+            rust_expr.mean_reversion_factor(prices, lookback)
+        return
+    assert_equivalent(rust_expr.mean_reversion_factor(prices, lookback), ref_mean_reversion_factor(prices, lookback))
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("lookback", [2, 20])
+def test_momentum_and_reversion(case, lookback):
+    prices = BATTERY[case]
+    if lookback > len(prices):
+        with pytest.raises(rust_expr.InvalidPeriod):
+            rust_expr.momentum_and_reversion(prices, lookback)
+        return
+    mom, rev = rust_expr.momentum_and_reversion(prices, lookback)
+    assert_equivalent(mom, ref_momentum_factor(prices, lookback))
+    assert_equivalent(rev, ref_mean_reversion_factor(prices, lookback))
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("lookback", [2, 3, 9, 500])
+def test_relative_strength_factor(case, lookback):
+    prices = BATTERY[case]
+    if 2 * lookback > len(prices):
+        with pytest.raises(rust_expr.InvalidPeriod, match="exceeds series length"):
+            rust_expr.relative_strength_factor(prices, lookback)
+        return
+    assert_equivalent(rust_expr.relative_strength_factor(prices, lookback), ref_relative_strength_factor(prices, lookback))
+
+@pytest.mark.parametrize("case", CASES)
+def test_alpha101_factor_42(case):
+    high = BATTERY[case]
+    volume = np.abs(np.sin(np.arange(len(high)))) * 1e6 + 1.0
+    if len(high) < 10:
+        with pytest.raises(rust_expr.InvalidPeriod):
+            rust_expr.alpha101_factor_42(high, volume)
+        return
+    assert_equivalent(rust_expr.alpha101_factor_42(high, volume), ref_alpha101_factor_42(high, volume))
+
+@pytest.mark.parametrize("windows", [(5, 8, 12), (20, 3, 6)])
+def test_alpha101_factor_42_windows(windows):
+    high = BATTERY["random_walk"]
+    volume = np.abs(np.sin(np.arange(len(high)))) * 1e6 + 1.0
+    assert_equivalent(rust_expr.alpha101_factor_42(high, volume, *windows), ref_alpha101_factor_42(high, volume, *windows))
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("blend", [0.0, 0.3, 1.0])
+def test_hybrid_zscore(case, blend):
+    data = BATTERY[case]
+    eq, ew = ref_rolling_zscore(data, 5), ref_ew_zscore(data, 0.2)
+    if blend == 1.0:
+        expected = eq
+    elif blend == 0.0:
+        expected = ew
+    else:
+        expected = blend * eq + (1 - blend) * ew
+    assert_equivalent(rust_expr.hybrid_zscore(data, 5, 0.2, blend), expected)
+
+@pytest.mark.parametrize("case", CASES)
+def test_ew_zscore(case):
+    data = BATTERY[case]
+    assert_equivalent(rust_expr.ew_zscore(data, 0.1), ref_ew_zscore(data, 0.1))
+
+@pytest.mark.parametrize("case", CASES)
+def test_rolling_ess(case):
+    data = BATTERY[case]
+    weights = np.array([1.0, 2.0, 3.0, 4.0])
+    expected = np.full(len(data), np.nan)
+    for i in range(len(weights) - 1, len(data)):
+        w = weights[np.isfinite(data[i - len(weights) + 1:i + 1])]
+        if np.sum(w * w) > 0:
+            expected[i] = np.sum(w) ** 2 / np.sum(w * w)
+    assert_equivalent(rust_expr.rolling_ess(data, weights), expected)
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("mode", ["ratio", "diff", "ols"])
+def test_pair_zscore(case, mode):
+    y = BATTERY[case]
+    x = np.linspace(50.0, 80.0, len(y)) + np.cos(np.arange(len(y)))
+    if mode == "ratio":
+        with np.errstate(invalid="ignore", divide="ignore"):
+            spread = np.where((y > 0) & (x > 0), np.log(y) - np.log(x), np.nan)
+    elif mode == "diff":
+        spread = y - x
+    else:
+        spread = y - ref_rolling_beta(x, y, 6) * x
+    assert_equivalent(rust_expr.pair_zscore(y, x, 6, mode), ref_rolling_zscore(spread, 6))
+
+@pytest.mark.parametrize("case", CASES)
+def test_rolling_lead_lag(case):
+    x = BATTERY[case]
+    y = np.sin(np.arange(len(x)) * 0.7) + np.arange(len(x)) * 0.01
+    window, max_lag = 8, 2
+    result = rust_expr.rolling_lead_lag(x, y, window, max_lag)
+    assert result.shape == (len(x), 2 * max_lag + 1)
+    for k in range(2 * max_lag + 1):
+        lag = k - max_lag
+        sx = np.full(len(x), np.nan)
+        sy = np.full(len(y), np.nan)
+        if lag >= 0:
+            sx[:] = x
+            sy[lag:] = y[:len(y) - lag]
+        else:
+            sx[-lag:] = x[:len(x) + lag]
+            sy[:] = y
+        assert_equivalent(result[:, k], ref_rolling_correlation(sx, sy, window))
+
+@pytest.mark.parametrize("case", CASES)
+def test_rolling_volume_delta(case):
+    close = BATTERY[case]
+    volume = np.arange(len(close), dtype=np.float64) + 1.0
+    signed = np.full(len(close), np.nan)
+    for i in range(1, len(close)):
+        if np.isfinite(close[i]) and np.isfinite(close[i - 1]):
+            signed[i] = np.sign(close[i] - close[i - 1]) * volume[i]
+    expected = np.full(len(close), np.nan)
+    valid = []
+    for i, val in enumerate(signed):
+        if np.isfinite(val):
+            valid.append(val)
+            if len(valid) >= 5:
+                expected[i] = np.sum(valid[-5:])
+    assert_equivalent(rust_expr.rolling_volume_delta(close, volume, 5), expected)
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("include_zeros", [False, True])
+def test_rolling_up_fraction(case, include_zeros):
+    returns = np.diff(BATTERY[case], prepend=np.nan)
+    window = 6
+    expected = np.full(len(returns), np.nan)
+    for i in range(window - 1, len(returns)):
+        win = returns[i - window + 1:i + 1]
+        eligible = np.isfinite(win) & ((win != 0) | include_zeros)
+        if eligible.any():
+            expected[i] = np.sum(win[eligible] > 0) / eligible.sum()
+    assert_equivalent(rust_expr.rolling_up_fraction(returns, window, include_zeros), expected)
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("chunk_size", [1, 7, 1000])
+def test_rolling_mean_chunks(case, chunk_size):
+    data = BATTERY[case]
+    chunks = list(rust_expr.rolling_mean_chunks(data, 4, chunk_size))
+    stitched = np.concatenate(chunks) if chunks else np.array([], dtype=np.float64)
+    assert_equivalent(stitched, ref_rolling_mean(data, 4))
+
+@pytest.mark.parametrize("case", CASES)
+def test_rolling_rank_streaming(case):
+    data = BATTERY[case]
+    ranker = rust_expr.RollingRank(5)
+    streamed = np.array([ranker.push(v) for v in data], dtype=np.float64)
+    assert_equivalent(streamed, ref_rolling_rank(data, 5))
+    # `update` is kept as an alias of `push`, as on RollingMean and RollingStd
+    alias = rust_expr.RollingRank(5)
+    assert_equivalent(np.array([alias.update(v) for v in data], dtype=np.float64), streamed)
+
+@pytest.mark.parametrize("case", CASES)
+def test_streaming_corr_matrix(case):
+    a = BATTERY[case]
+    b = np.cos(np.arange(len(a)) * 0.3)
+    window = 6
+    stream = rust_expr.StreamingCorrMatrix(2, window)
+    streamed = np.array([stream.update(np.array([a[t], b[t]]))[0, 1] for t in range(len(a))])
+    expected = np.full(len(a), np.nan)
+    for t in range(window - 1, len(a)):
+        wa, wb = a[t - window + 1:t + 1], b[t - window + 1:t + 1]
+        if np.all(np.isfinite(wa)) and np.var(wa) > 0 and np.var(wb) > 0:
+            expected[t] = (np.mean(wa * wb) - wa.mean() * wb.mean()) / np.sqrt(np.var(wa) * np.var(wb))
+    assert_equivalent(streamed, expected)
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("window", [2, 5, 20])
+def test_ts_primitives(case, window):
+    data = BATTERY[case]
+    other = np.sin(np.arange(len(data)) * 0.7)
+    assert_equivalent(rust_expr.ts_mean(data, window), ref_rolling_mean(data, window))
+    assert_equivalent(rust_expr.ts_std(data, window), ref_rolling_std(data, window))
+    assert_equivalent(rust_expr.ts_rank(data, window), ref_rolling_rank(data, window))
+    assert_equivalent(rust_expr.ts_corr(data, other, window), ref_rolling_correlation(data, other, window))
+
+def test_panel_variants_match_per_column_calls():
+    panel = np.column_stack([BATTERY[case][:60] for case in ("random_walk", "gappy", "with_inf")])
+    window = 5
+    pairs = [
+        (rust_expr.ts_mean_2d, rust_expr.ts_mean),
+        (rust_expr.ts_std_2d, rust_expr.ts_std),
+        (rust_expr.ts_rank_2d, rust_expr.ts_rank),
+        (rust_expr.momentum_factor_2d, rust_expr.momentum_factor),
+        (rust_expr.mean_reversion_factor_2d, rust_expr.mean_reversion_factor),
+    ]
+    for panel_fn, column_fn in pairs:
+        result = panel_fn(panel, window)
+        assert result.shape == panel.shape
+        for j in range(panel.shape[1]):
+            assert_equivalent(result[:, j], column_fn(np.ascontiguousarray(panel[:, j]), window))
+
+@pytest.mark.parametrize("ddof", [0, 1])
+def test_rolling_cov_matches_numpy_cov(ddof):
+    rng = np.random.default_rng(3)
+    x, y = rng.normal(size=60), rng.normal(size=60)
+    window = 12
+    actual = rust_expr.rolling_cov(x, y, window, ddof=ddof)
+    expected = np.full(len(x), np.nan)
+    for t in range(window - 1, len(x)):
+        expected[t] = np.cov(x[t - window + 1:t + 1], y[t - window + 1:t + 1], ddof=ddof)[0, 1]
+    assert_allclose(actual, expected, rtol=0, atol=1e-12, equal_nan=True)
+
+@pytest.mark.parametrize("case", CASES)
+@pytest.mark.parametrize("q", [0.0, 0.3, 0.5, 1.0])
+def test_rolling_quantile(case, q):
+    data = BATTERY[case]
+    window = 5
+    expected = np.full(len(data), np.nan)
+    valid = []
+    for i, val in enumerate(data):
+        if np.isfinite(val):
+            valid.append(val)
+            if len(valid) >= window:
+                expected[i] = np.quantile(valid[-window:], q)
+    assert_equivalent(rust_expr.rolling_quantile(data, window, q), expected)
+
+def test_errors_raise_typed_exceptions():
+    data = np.arange(10, dtype=np.float64)
+    assert issubclass(rust_expr.InvalidPeriod, rust_expr.ExprError)
+    assert issubclass(rust_expr.ExprError, ValueError)
+    with pytest.raises(rust_expr.InvalidPeriod, match="Invalid period: Window size must be at least 2"):
+        rust_expr.ts_std(data, 1)
+    with pytest.raises(rust_expr.InvalidParameter):
+        rust_expr.winsorize(data, 0.9, 0.1)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.ts_corr(data, data[:5], 3)
+    with pytest.raises(rust_expr.ParseError):
+        rust_expr.eval_expr("mean(x)", {"x": data})
+    # Still catchable as ValueError
+    with pytest.raises(ValueError):
+        rust_expr.ts_std(data, 1)
+
+def test_neutralize_matches_lstsq_residual():
+    rng = np.random.default_rng(11)
+    exposures = rng.normal(size=(40, 2))
+    factor = 0.5 + exposures @ np.array([1.5, -2.0]) + rng.normal(scale=0.3, size=40)
+    factor[[3, 17]] = np.nan
+    exposures[25, 1] = np.nan
+    keep = np.isfinite(factor) & np.isfinite(exposures).all(axis=1)
+    design = np.column_stack([np.ones(keep.sum()), exposures[keep]])
+    coef, *_ = np.linalg.lstsq(design, factor[keep], rcond=None)
+    expected = np.full(len(factor), np.nan)
+    expected[keep] = factor[keep] - design @ coef
+    assert_allclose(rust_expr.neutralize(factor, exposures), expected, rtol=0, atol=1e-10, equal_nan=True)
+
+def test_max_drawdown_matches_running_peak():
+    rng = np.random.default_rng(5)
+    prices = 100 * np.cumprod(1 + rng.normal(scale=0.02, size=80))
+    prices[:3] = np.nan
+    finite = prices[3:]
+    expected = np.min(finite / np.maximum.accumulate(finite) - 1)
+    assert rust_expr.max_drawdown(prices) == pytest.approx(expected, abs=1e-12)
+    assert rust_expr.max_drawdown(np.arange(1.0, 10.0)) == 0.0
+    assert_allclose(rust_expr.rolling_drawdown(prices, 10), rust_expr.rolling_current_drawdown(prices, 10), equal_nan=True)
+
+def test_ts_functions_keep_float32():
+    data = BATTERY["gappy"].astype(np.float32)
+    for func in (rust_expr.ts_mean, rust_expr.ts_std, rust_expr.ts_rank):
+        actual = func(data, 10)
+        assert actual.dtype == np.float32
+        expected = func(data.astype(np.float64), 10).astype(np.float32)
+        np.testing.assert_array_equal(actual, expected)
+    assert rust_expr.ts_corr(data, data[::-1].copy(), 10).dtype == np.float32
+    assert rust_expr.ts_corr(data, data.astype(np.float64), 10).dtype == np.float64
+    panel = np.column_stack([data, data * 2]).astype(np.float32)
+    assert rust_expr.ts_mean_2d(panel, 5).dtype == np.float32
+
+def test_centered_windows_align_on_the_middle_bar():
+    data = BATTERY["random_walk"]
+    for window in (5, 6):
+        trailing = rust_expr.ts_mean(data, window)
+        offset = (window - 1) // 2
+        expected = np.full_like(trailing, np.nan)
+        expected[:len(data) - offset] = trailing[offset:]
+        assert_allclose(rust_expr.ts_mean(data, window, center=True), expected, equal_nan=True)
+    assert np.isnan(rust_expr.ts_std(data, 5, center=True)[[0, 1, -2, -1]]).all()
+    with pytest.raises(rust_expr.InvalidParameter):
+        rust_expr.ts_mean(data, 5, step=2, center=True)
+
+def test_rolling_apply_calls_back_per_window():
+    data = BATTERY["random_walk"]
+    actual = rust_expr.rolling_apply(data, 7, lambda w: w.max() - w.min())
+    expected = np.full_like(data, np.nan)
+    for end in range(7, len(data) + 1):
+        window = data[end - 7:end]
+        expected[end - 1] = window.max() - window.min()
+    assert_allclose(actual, expected, equal_nan=True)
+
+    def fail(window):
+        raise KeyError("boom")
+    with pytest.raises(KeyError, match="boom"):
+        rust_expr.rolling_apply(data, 7, fail)
+
+def test_grouped_rolling_matches_per_group_calls():
+    a, b = BATTERY["random_walk"][:40], BATTERY["gappy"][:30]
+    values = np.concatenate([a, b])
+    groups = np.repeat(np.array([4, 1], dtype=np.int64), [len(a), len(b)])
+    for grouped, single in ((rust_expr.grouped_rolling_mean, rust_expr.ts_mean),
+                            (rust_expr.grouped_rolling_std, rust_expr.ts_std),
+                            (rust_expr.grouped_rolling_rank, rust_expr.ts_rank)):
+        expected = np.concatenate([single(a, 5, skipna=False), single(b, 5, skipna=False)])
+        assert_allclose(grouped(values, groups, 5), expected, equal_nan=True)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.grouped_rolling_mean(values, groups[1:], 5)
+    with pytest.raises(rust_expr.InvalidParameter, match="not contiguous"):
+        rust_expr.grouped_rolling_mean(values, np.roll(groups, 5), 5)
+
+def test_rolling_mean_time_matches_brute_force_span():
+    rng = np.random.default_rng(11)
+    values = BATTERY["gappy"]
+    stamps = np.cumsum(rng.integers(1, 5, size=len(values))).astype(np.int64) * 86_400 * 10**9
+    window = 7 * 86_400 * 10**9
+    expected = np.full_like(values, np.nan)
+    for i, t in enumerate(stamps):
+        span = values[(stamps > t - window) & (stamps <= t)]
+        span = span[np.isfinite(span)]
+        if np.isfinite(values[i]):
+            expected[i] = span.mean()
+    assert_allclose(rust_expr.rolling_mean_time(values, stamps, window), expected, equal_nan=True)
+    with pytest.raises(rust_expr.InvalidParameter, match="non-decreasing"):
+        rust_expr.rolling_mean_time(values, stamps[::-1].copy(), window)
+
+def test_elementwise_transforms_match_numpy():
+    data = BATTERY["with_inf"]
+    finite = np.isfinite(data)
+    expected = np.where(finite, np.clip(data, -1.0, 1.0), np.nan)
+    assert_allclose(rust_expr.clip(data, -1.0, 1.0), expected, equal_nan=True)
+    assert_allclose(rust_expr.sigmoid(data), np.where(finite, 1 / (1 + np.exp(-data)), np.nan), equal_nan=True)
+    assert_allclose(rust_expr.tanh_squash(data, 3.0), np.where(finite, np.tanh(data / 3.0), np.nan), equal_nan=True)
+    prices = 100 * np.exp(np.cumsum(BATTERY["random_walk"] * 0.01))
+    expected = np.full_like(prices, np.nan)
+    expected[5:] = np.log(prices[5:] / prices[:-5])
+    assert_allclose(rust_expr.log_return(prices, 5), expected, equal_nan=True)
+
+def test_into_variants_fill_the_output_in_place():
+    prices = 100 + np.cumsum(BATTERY["random_walk"])
+    out = np.empty_like(prices)
+    assert rust_expr.momentum_factor_into(prices, 10, out) is None
+    assert_allclose(out, rust_expr.momentum_factor(prices, 10), equal_nan=True)
+    assert rust_expr.mean_reversion_factor_into(prices, 10, out, ddof=1) is None
+    assert_allclose(out, rust_expr.mean_reversion_factor(prices, 10, ddof=1), equal_nan=True)
+    with pytest.raises(ValueError, match="out has length"):
+        rust_expr.momentum_factor_into(prices, 10, out[:-1])
+
+def test_streaming_mean_and_std_match_batch():
+    data = BATTERY["gappy"]
+    mean, std = rust_expr.RollingMean(8), rust_expr.RollingStd(8, ddof=1)
+    streamed = np.array([[mean.push(x), std.push(x)] for x in data])
+    assert_allclose(streamed[:, 0], rust_expr.ts_mean(data, 8, skipna=False), equal_nan=True)
+    assert_allclose(streamed[:, 1], rust_expr.ts_std(data, 8, ddof=1, skipna=False), equal_nan=True)
+    mean.reset()
+    assert np.isnan(mean.update(1.0))
+    assert np.isclose(std.update(data[-1]), rust_expr.ts_std(np.append(data, data[-1]), 8, ddof=1)[-1])
+
+def test_rolling_sum_and_var_compose_with_mean_and_std():
+    for name in ("random_walk", "gappy", "with_inf"):
+        data = BATTERY[name]
+        assert_allclose(rust_expr.rolling_sum(data, 6), 6 * rust_expr.ts_mean(data, 6, skipna=False), equal_nan=True)
+        std = rust_expr.ts_std(data, 6, ddof=1, skipna=False)
+        assert_allclose(rust_expr.rolling_var(data, 6, ddof=1), std ** 2, equal_nan=True)
+
+def test_alpha101_ts_rank_matches_reference_rankdata():
+    data = np.round(BATTERY["gappy"], 1)
+    window = 6
+    expected = np.full_like(data, np.nan)
+    for end in range(window, len(data) + 1):
+        span = data[end - window:end]
+        if np.isfinite(span).all():
+            below, equal = (span < span[-1]).sum(), (span == span[-1]).sum()
+            expected[end - 1] = (below + (equal + 1) / 2) / window
+    assert_allclose(rust_expr.alpha101_ts_rank(data, window), expected, equal_nan=True)
+
+def test_rolling_zscore_is_the_un_negated_mean_reversion():
+    prices = 100 + np.cumsum(BATTERY["random_walk"])
+    z = rust_expr.rolling_zscore(prices, 12, ddof=1)
+    mean = rust_expr.ts_mean(prices, 12)
+    std = rust_expr.ts_std(prices, 12, ddof=1)
+    assert_allclose(z, (prices - mean) / std, equal_nan=True)
+    assert_allclose(-z, rust_expr.mean_reversion_factor(prices, 12, ddof=1), equal_nan=True)
+    assert np.isnan(rust_expr.rolling_zscore(np.full(20, 3.0), 5)).all()
+
+def test_rolling_prod_and_geomean_compound_returns():
+    growth = 1 + 0.01 * BATTERY["random_walk"]
+    expected = np.full_like(growth, np.nan)
+    for end in range(10, len(growth) + 1):
+        expected[end - 1] = np.prod(growth[end - 10:end])
+    assert_allclose(rust_expr.rolling_prod(growth, 10), expected, rtol=1e-10, equal_nan=True)
+    assert_allclose(rust_expr.rolling_geomean(growth, 10), expected ** 0.1, rtol=1e-10, equal_nan=True)
+
+def test_polars_series_entry_points_match_numpy():
+    pl = pytest.importorskip("polars")
+    polars_interop = pytest.importorskip("datafeed.rust_expr.polars_interop")
+
+    data = BATTERY["gappy"]
+    series = pl.Series("close", data)
+    result = polars_interop.ts_mean(series, 5)
+    assert isinstance(result, pl.Series) and result.name == "close"
+    assert_allclose(result.to_numpy(), rust_expr.ts_mean(data, 5), equal_nan=True)
+    chunked = pl.concat([pl.Series("close", data[:50]), pl.Series("close", data[50:])], rechunk=False)
+    assert_allclose(polars_interop.ts_std(chunked, 5).to_numpy(), rust_expr.ts_std(data, 5), equal_nan=True)
+    with_nulls = pl.Series("close", [None if np.isnan(v) else v for v in data], dtype=pl.Float64)
+    assert_allclose(polars_interop.rolling_sum(with_nulls, 4).to_numpy(), rust_expr.rolling_sum(data, 4), equal_nan=True)
+    other = pl.Series("open", data[::-1].copy())
+    assert_allclose(polars_interop.ts_corr(series, other, 10).to_numpy(), rust_expr.ts_corr(data, data[::-1].copy(), 10), equal_nan=True)
+    with pytest.raises(TypeError, match="Float64"):
+        polars_interop.rolling_sum(pl.Series("n", [1, 2, 3, 4]), 2)
+    with pytest.raises(TypeError):
+        polars_interop.rolling_sum(pl.Series("b", [True, False, True]), 2)
+
+def test_halflife_matches_the_equivalent_alpha():
+    data = BATTERY["random_walk"]
+    alpha = 1 - np.exp(np.log(0.5) / 20)
+    assert_allclose(rust_expr.ema(data, halflife=20), rust_expr.ema(data, alpha=alpha), equal_nan=True)
+    assert_allclose(rust_expr.ewm_std(data, halflife=20), rust_expr.ewm_std(data, alpha=alpha), equal_nan=True)
+    impulse = np.zeros(11)
+    impulse[0] = 1.0
+    assert rust_expr.ema(impulse, halflife=5)[5] == pytest.approx(0.5)
+    with pytest.raises(ValueError, match="exactly one"):
+        rust_expr.ema(data, span=10, halflife=20)
+
+def test_rolling_minmax_range_and_stochastic():
+    data = BATTERY["gappy"]
+    low, high = rust_expr.rolling_minmax(data, 9)
+    assert_allclose(low, rust_expr.rolling_min(data, 9), equal_nan=True)
+    assert_allclose(high, rust_expr.rolling_max(data, 9), equal_nan=True)
+    assert_allclose(rust_expr.rolling_range(data, 9), high - low, equal_nan=True)
+    with np.errstate(invalid="ignore", divide="ignore"):
+        expected = (data - low) / (high - low)
+    assert_allclose(rust_expr.stochastic(data, 9), expected, equal_nan=True)
+
+def test_tied_values_share_the_average_percentile():
+    row = np.array([[1.0, 2.0, 2.0, 2.0, 3.0]])
+    assert_allclose(rust_expr.cs_rank(row), [[0.0, 0.5, 0.5, 0.5, 1.0]])
+    assert_allclose(rust_expr.cs_rank(row, method="first"), [[0.0, 0.25, 0.5, 0.75, 1.0]])
+    series = np.array([1.0, 3.0, 2.0, 2.0, 2.0])
+    assert rust_expr.rolling_rank(series, 5, method="average")[-1] == pytest.approx(3 / 5)
+    assert rust_expr.rolling_rank(series, 5, method="first")[-1] == rust_expr.rolling_rank(series, 5)[-1]
+
+def test_relative_strength_custom_blend():
+    prices = 100 + np.cumsum(BATTERY["random_walk"])
+    default = rust_expr.relative_strength_factor(prices, 9)
+    assert_allclose(rust_expr.relative_strength_factor(prices, 9, weights=[5, 3, 2]), default)
+    factor, ranks = rust_expr.relative_strength_factor(prices, 9, return_components=True, timeframes=[1, 5], weights=[1, 3])
+    assert ranks.shape == (len(prices), 2)
+    expected = np.where(np.isfinite(ranks), ranks, 0.0) @ np.array([0.25, 0.75])
+    assert_allclose(factor, expected)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.relative_strength_factor(prices, 9, timeframes=[1, 5])
+
+def test_sign_and_sign_change():
+    data = BATTERY["with_inf"]
+    assert_allclose(rust_expr.sign(data), np.sign(data), equal_nan=True)
+    flips = rust_expr.sign_change(np.array([-1.0, -2.0, 0.0, 2.0, np.nan, 3.0, -1.0]))
+    assert_allclose(flips, [0, 0, 0, 1, np.nan, 0, -1], equal_nan=True)
+
+def test_rolling_autocorr_matches_lagged_corrcoef():
+    data = BATTERY["random_walk"]
+    window, lag = 20, 3
+    actual = rust_expr.rolling_autocorr(data, window, lag)
+    assert np.isnan(actual[:window + lag - 1]).all()
+    for end in range(window + lag, len(data) + 1, 17):
+        x = data[end - window:end]
+        y = data[end - window - lag:end - lag]
+        assert actual[end - 1] == pytest.approx(np.corrcoef(x, y)[0, 1], abs=1e-9)
+    with pytest.raises(rust_expr.InvalidPeriod):
+        rust_expr.rolling_autocorr(data, 5, 5)
+
+def test_rolling_slope_and_r2_match_polyfit():
+    data = 100 + np.cumsum(BATTERY["random_walk"])
+    window = 15
+    slope, r2 = rust_expr.rolling_slope(data, window), rust_expr.rolling_r2(data, window)
+    assert np.isnan(slope[:window - 1]).all()
+    t = np.arange(window, dtype=float)
+    for end in range(window, len(data) + 1, 11):
+        y = data[end - window:end]
+        assert slope[end - 1] == pytest.approx(np.polyfit(t, y, 1)[0], abs=1e-9)
+        assert r2[end - 1] == pytest.approx(np.corrcoef(t, y)[0, 1] ** 2, abs=1e-9)
+    gappy = BATTERY["gappy"]
+    strict = rust_expr.rolling_slope(gappy, 5, skipna=False)
+    assert np.isnan(strict[~np.isfinite(gappy)]).all()
+
+def _average_ranks(x):
+    order = np.argsort(x, kind="stable")
+    ranks = np.empty(len(x))
+    ranks[order] = np.arange(1, len(x) + 1)
+    for value in np.unique(x):
+        ranks[x == value] = ranks[x == value].mean()
+    return ranks
+
+def test_rank_ic_matches_per_row_spearman():
+    rng = np.random.default_rng(7)
+    factor = rng.normal(size=(30, 12)).round(1)
+    returns = 0.3 * factor + rng.normal(size=factor.shape)
+    factor[3, :11] = np.nan
+    returns[5, 2] = np.nan
+    actual = rust_expr.rank_ic(factor, returns)
+    assert np.isnan(actual[3])
+    for row in set(range(len(factor))) - {3}:
+        valid = np.isfinite(factor[row]) & np.isfinite(returns[row])
+        expected = np.corrcoef(_average_ranks(factor[row, valid]), _average_ranks(returns[row, valid]))[0, 1]
+        assert actual[row] == pytest.approx(expected, abs=1e-9)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.rank_ic(factor, returns[:, :5])
+
+@pytest.mark.parametrize("limit", [None, 1, 3])
+def test_forward_and_backward_fill_match_pandas(limit):
+    pd = pytest.importorskip("pandas")
+    data = BATTERY["gappy"]
+    assert_allclose(rust_expr.forward_fill(data, limit), pd.Series(data).ffill(limit=limit).to_numpy(), equal_nan=True)
+    assert_allclose(rust_expr.backward_fill(data, limit), pd.Series(data).bfill(limit=limit).to_numpy(), equal_nan=True)
+
+def test_rolling_entropy_matches_numpy_histogram():
+    data = BATTERY["random_walk"]
+    window, bins = 30, 8
+    actual = rust_expr.rolling_entropy(data, window, bins)
+    assert np.isnan(actual[:window - 1]).all()
+    for end in range(window, len(data) + 1, 13):
+        counts, _ = np.histogram(data[end - window:end], bins=bins)
+        p = counts[counts > 0] / window
+        assert actual[end - 1] == pytest.approx(-(p * np.log2(p)).sum(), abs=1e-9)
+    assert np.isnan(rust_expr.rolling_entropy(np.ones(10), 5, bins)[4:]).all()
+
+@pytest.mark.filterwarnings("ignore:Mean of empty slice")
+def test_cs_normalize_composes_rank_demean_and_scale():
+    rng = np.random.default_rng(11)
+    data = rng.normal(size=(20, 9))
+    data[rng.random(data.shape) < 0.2] = np.nan
+    data[4] = np.nan
+    assert_allclose(rust_expr.cs_demean(data), data - np.nanmean(data, axis=1, keepdims=True), equal_nan=True)
+    ranked = rust_expr.cs_rank(data)
+    demeaned = ranked - np.nanmean(ranked, axis=1, keepdims=True)
+    with np.errstate(invalid="ignore"):
+        expected = demeaned / np.nansum(np.abs(demeaned), axis=1, keepdims=True)
+    actual = rust_expr.cs_normalize(data)
+    assert_allclose(actual, expected, equal_nan=True, atol=1e-12)
+    assert np.isnan(actual[4]).all()
+    assert_allclose(rust_expr.cs_normalize(data, rank=False, scale=False), rust_expr.cs_demean(data), equal_nan=True)
+
+def test_alpha101_factor_42_2d_matches_per_column():
+    rng = np.random.default_rng(3)
+    high = 100 + np.cumsum(rng.normal(size=(80, 5)), axis=0)
+    volume = rng.lognormal(13, 0.5, size=high.shape)
+    high[10, 2] = np.nan
+    panel = rust_expr.alpha101_factor_42_2d(high, volume, 5, 5, 5)
+    for j in range(high.shape[1]):
+        column = rust_expr.alpha101_factor_42(np.ascontiguousarray(high[:, j]), np.ascontiguousarray(volume[:, j]), 5, 5, 5)
+        np.testing.assert_array_equal(panel[:, j], column)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.alpha101_factor_42_2d(high, volume[:, :3])
+
+def test_panel_beta_matches_rolling_beta_per_column():
+    rng = np.random.default_rng(5)
+    market = rng.normal(0, 0.01, size=120)
+    returns = market[:, None] * np.array([0.5, 1.0, 1.5, 2.0]) + rng.normal(0, 0.005, size=(120, 4))
+    returns[30:35, 1] = np.nan
+    market[60] = np.nan
+    panel = rust_expr.panel_beta(returns, market, 20)
+    for j in range(returns.shape[1]):
+        np.testing.assert_array_equal(panel[:, j], rust_expr.rolling_beta(market, np.ascontiguousarray(returns[:, j]), 20))
+    assert np.isnan(panel[30:35, 1]).all() and np.isfinite(panel[30:35, 0]).all()
+
+def test_fill_value_only_replaces_missing_factor_outputs():
+    prices = BATTERY["gappy"] + 100
+    raw = rust_expr.momentum_factor(prices, 10)
+    filled = rust_expr.momentum_factor(prices, 10, fill_value=0.0)
+    assert_allclose(filled, np.where(np.isfinite(raw), raw, 0.0))
+    panel = np.column_stack([prices, prices[::-1]])
+    assert np.isfinite(rust_expr.mean_reversion_factor_2d(panel, 10, fill_value=-1.0)).all()
+    factor, ranks = rust_expr.relative_strength_factor(prices, 10, return_components=True, fill_value=0.0)
+    assert np.isfinite(factor).all() and np.isnan(ranks).any()
+
+def test_rolling_vwap_matches_windowed_sums():
+    rng = np.random.default_rng(9)
+    price = 100 + np.cumsum(rng.normal(size=200))
+    volume = rng.lognormal(10, 1, size=200)
+    volume[50:60] = 0.0
+    window = 12
+    actual = rust_expr.rolling_vwap(price, volume, window)
+    assert np.isnan(actual[:window - 1]).all()
+    for end in range(window, len(price) + 1):
+        p, v = price[end - window:end], volume[end - window:end]
+        assert actual[end - 1] == pytest.approx((p * v).sum() / v.sum(), rel=1e-9)
+    flat = rust_expr.rolling_vwap(price, np.zeros_like(price), window)
+    assert np.isnan(flat).all()
+
+def test_where_and_comparisons_match_numpy():
+    a = BATTERY["gappy"]
+    b = BATTERY["random_walk"][:len(a)]
+    for name, op in [("gt", np.greater), ("lt", np.less), ("ge", np.greater_equal), ("le", np.less_equal)]:
+        with np.errstate(invalid="ignore"):
+            np.testing.assert_array_equal(getattr(rust_expr, name)(a, b), op(a, b))
+    mask = rust_expr.gt(b, np.full_like(b, 100.0))
+    assert_allclose(rust_expr.where_(mask, a, b), np.where(mask, a, b), equal_nan=True)
+    with pytest.raises(rust_expr.LengthMismatch):
+        rust_expr.where_(mask[:-1], a, b)
+
+@pytest.mark.parametrize("normalized", [False, True])
+def test_rolling_mad_matches_numpy(normalized):
+    data = BATTERY["random_walk"]
+    window = 21
+    actual = rust_expr.rolling_mad(data, window, normalized)
+    assert np.isnan(actual[:window - 1]).all()
+    scale = 1.4826 if normalized else 1.0
+    for end in range(window, len(data) + 1, 9):
+        x = data[end - window:end]
+        assert actual[end - 1] == pytest.approx(scale * np.median(np.abs(x - np.median(x))), abs=1e-12)
+
+def test_rolling_mean_with_count_surfaces_valid_counts():
+    data = BATTERY["gappy"]
+    window = 10
+    mean, counts = rust_expr.rolling_mean_with_count(data, window, min_periods=3)
+    assert counts.dtype == np.int64
+    expected_counts = np.minimum(np.cumsum(np.isfinite(data)), window)
+    np.testing.assert_array_equal(counts, expected_counts)
+    partial, _ = rust_expr.rolling_mean_partial(data, window, 3)
+    assert_allclose(mean, partial, equal_nan=True)
+
+def test_bollinger_factors_match_pandas_bands():
+    pd = pytest.importorskip("pandas")
+    data = BATTERY["random_walk"]
+    window, k = 20, 2.0
+    rolling = pd.Series(data).rolling(window)
+    mean, std = rolling.mean().to_numpy(), rolling.std(ddof=0).to_numpy()
+    lower = mean - k * std
+    assert_allclose(rust_expr.bollinger_percent_b(data, window, k), (data - lower) / (2 * k * std), equal_nan=True, atol=1e-9)
+    assert_allclose(rust_expr.bollinger_bandwidth(data, window, k), 2 * k * std / mean, equal_nan=True, atol=1e-9)
+    assert np.isnan(rust_expr.bollinger_percent_b(BATTERY["constant"], 5)).all()
+
+def test_shuffle_rows_supports_a_seeded_permutation_test():
+    rng = np.random.default_rng(13)
+    factor = rng.normal(size=(40, 15))
+    factor[rng.random(factor.shape) < 0.1] = np.nan
+    first, second = rust_expr.shuffle_rows(factor, 7), rust_expr.shuffle_rows(factor, 7)
+    np.testing.assert_array_equal(first, second)
+    assert not np.array_equal(first, rust_expr.shuffle_rows(factor, 8), equal_nan=True)
+    np.testing.assert_array_equal(np.isnan(first).sum(axis=1), np.isnan(factor).sum(axis=1))
+    np.testing.assert_array_equal(np.sort(first, axis=1), np.sort(factor, axis=1))
+    returns = factor + rng.normal(0, 0.5, size=factor.shape)
+    null = [np.nanmean(rust_expr.rank_ic(rust_expr.shuffle_rows(factor, seed), returns)) for seed in range(20)]
+    assert np.nanmean(rust_expr.rank_ic(factor, returns)) > max(null)
+
+def test_dema_and_tema_compose_ema():
+    data = BATTERY["gappy"]
+    gaps = ~np.isfinite(data)
+    e1 = rust_expr.ema(data, span=10)
+    e2 = rust_expr.ema(np.where(gaps, np.nan, e1), span=10)
+    e3 = rust_expr.ema(np.where(gaps, np.nan, e2), span=10)
+    assert_allclose(rust_expr.dema(data, span=10), 2 * e1 - e2, equal_nan=True)
+    assert_allclose(rust_expr.tema(data, span=10), 3 * e1 - 3 * e2 + e3, equal_nan=True)
+    ramp = np.arange(300, dtype=float)
+    lags = [ramp[-1] - f(ramp, span=20)[-1] for f in (rust_expr.ema, rust_expr.dema, rust_expr.tema)]
+    assert lags[0] > 1 and abs(lags[1]) < 1e-6 and abs(lags[2]) < 1e-6
+    dema = rust_expr.dema(data, span=10)
+    assert_allclose(dema[5], dema[4])
+
+def test_cs_bucket_matches_rank_deciles():
+    rng = np.random.default_rng(21)
+    data = rng.normal(size=(25, 37))
+    data[rng.random(data.shape) < 0.15] = np.nan
+    buckets = rust_expr.cs_bucket(data, 10)
+    assert buckets.dtype == np.int64
+    np.testing.assert_array_equal(buckets == -1, np.isnan(data))
+    for row, labels in zip(data, buckets):
+        valid = ~np.isnan(row)
+        positions = np.argsort(np.argsort(row[valid], kind="stable"), kind="stable")
+        np.testing.assert_array_equal(labels[valid], positions * 10 // valid.sum())
+        counts = np.bincount(labels[valid], minlength=10)
+        assert counts.max() - counts.min() <= 1
+
+def test_two_array_functions_reject_mismatched_lengths():
+    x, y = BATTERY["random_walk"][:100], BATTERY["random_walk"][:98]
+    with pytest.raises(rust_expr.LengthMismatch, match="x has length 100 but y has length 98"):
+        rust_expr.ts_corr(x, y, 10)
+    with pytest.raises(rust_expr.LengthMismatch, match="high has length 100 but volume has length 98"):
+        rust_expr.alpha101_factor_42(x, y)
+    with pytest.raises(ValueError):
+        rust_expr.rolling_beta(x, y, 10)
+
+def test_expanding_stats_match_pandas():
+    pd = pytest.importorskip("pandas")
+    data = BATTERY["gappy"]
+    valid = np.isfinite(data)
+    series = pd.Series(data[valid])
+    expected_rank = series.expanding().rank(method="max").to_numpy() / np.arange(1, valid.sum() + 1)
+    for name, expected in [
+        ("expanding_rank", expected_rank),
+        ("expanding_mean", series.expanding().mean().to_numpy()),
+    ]:
+        actual = getattr(rust_expr, name)(data)
+        assert np.isnan(actual[~valid]).all()
+        assert_allclose(actual[valid], expected, rtol=1e-12)
+    std = rust_expr.expanding_std(data, ddof=1)
+    assert_allclose(std[valid], series.expanding().std().to_numpy(), rtol=1e-9, equal_nan=True)
+
+def test_factor_builder_matches_chained_calls():
+    data = BATTERY["gappy"]
+    chained = rust_expr.Factor(data).rolling_mean(5).zscore(20, ddof=1).clip(-2.0, 2.0)
+    direct = rust_expr.clip(rust_expr.rolling_zscore(rust_expr.rolling_mean(data, 5), 20, 1), -2.0, 2.0)
+    np.testing.assert_array_equal(chained.to_numpy(), direct)
+    assert len(chained) == len(data)
+    assert np.isfinite(chained.fill_nan(0.0).to_numpy()).all()
+    with pytest.raises(rust_expr.InvalidParameter):
+        rust_expr.Factor(data).clip(1.0, -1.0)
+
+def ref_hampel(data, window, n_sigmas):
+    half_left, half_right = window // 2, (window - 1) // 2
+    cleaned, mask = data.copy(), np.zeros(len(data), dtype=bool)
+    for i in range(half_left, len(data) - half_right):
+        x = data[i - half_left:i + half_right + 1]
+        x = x[np.isfinite(x)]
+        if not np.isfinite(data[i]):
+            continue
+        median = np.median(x)
+        mad = np.median(np.abs(x - median))
+        if abs(data[i] - median) > n_sigmas * 1.4826 * mad:
+            cleaned[i], mask[i] = median, True
+    return cleaned, mask
+
+def test_hampel_filter_matches_reference():
+    rng = np.random.default_rng(17)
+    data = 100 + rng.normal(size=300)
+    data[rng.choice(300, 12, replace=False)] += rng.choice([-20.0, 20.0], 12)
+    cleaned, mask = rust_expr.hampel_filter(data, 7, 3.0, return_mask=True)
+    expected, expected_mask = ref_hampel(data, 7, 3.0)
+    assert_allclose(cleaned, expected)
+    np.testing.assert_array_equal(mask, expected_mask)
+    assert_allclose(rust_expr.hampel_filter(data, 7), cleaned)
+    gappy = data.copy()
+    gappy[[20, 21, 60]] = np.nan
+    cleaned, mask = rust_expr.hampel_filter(gappy, 7, 3.0, return_mask=True)
+    expected, expected_mask = ref_hampel(gappy, 7, 3.0)
+    assert_allclose(cleaned, expected, equal_nan=True)
+    np.testing.assert_array_equal(mask, expected_mask)
+
+def test_rolling_count_and_nan_ratio_match_pandas():
+    pd = pytest.importorskip("pandas")
+    data = BATTERY["with_inf"].copy()
+    data[[3, 4, 10]] = np.nan
+    window = 6
+    expected = pd.Series(np.isfinite(data).astype(float)).rolling(window).sum().to_numpy()
+    assert_allclose(rust_expr.rolling_count(data, window), expected, equal_nan=True)
+    assert_allclose(rust_expr.rolling_nan_ratio(data, window), 1 - expected / window, equal_nan=True)
+
+def ref_weighted_rolling_mean(data, weights):
+    window, out = len(weights), np.full(len(data), np.nan)
+    for i in range(window - 1, len(data)):
+        x = data[i + 1 - window:i + 1]
+        valid = np.isfinite(x)
+        if weights[valid].sum() > 0:
+            out[i] = (weights[valid] * x[valid]).sum() / weights[valid].sum()
+    return out
+
+@pytest.mark.parametrize("name", sorted(BATTERY))
+def test_weighted_rolling_mean_matches_reference(name):
+    data = BATTERY[name]
+    weights = np.exp(-0.5 * ((np.arange(9) - 8) / 3.0) ** 2)
+    assert_allclose(rust_expr.weighted_rolling_mean(data, weights), ref_weighted_rolling_mean(data, weights), equal_nan=True)
+    assert_allclose(rust_expr.weighted_rolling_mean(data, np.arange(1.0, 6.0)), rust_expr.decay_linear(data, 5), equal_nan=True)